@@ -8,7 +8,7 @@ use betfair_rs::{
             CancelInstruction, CancelOrdersRequest, LimitOrder, ListCurrentOrdersRequest,
             PlaceInstruction, PlaceOrdersRequest,
         },
-        ListMarketCatalogueRequest, MarketFilter,
+        ListMarketCatalogueRequest, MarketFilter, RunnerKey, SubscriptionOptions,
     },
     orderbook::Orderbook,
     BetfairClient,
@@ -66,6 +66,24 @@ enum Panel {
     Diagnostics,
 }
 
+/// Market browser drill-down applied on top of the sport/competition/event hierarchy
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BrowseFilterKind {
+    Country,
+    Venue,
+    MarketType,
+}
+
+impl BrowseFilterKind {
+    fn label(&self) -> &'static str {
+        match self {
+            BrowseFilterKind::Country => "Country",
+            BrowseFilterKind::Venue => "Venue",
+            BrowseFilterKind::MarketType => "Market Type",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Market {
     id: String,
@@ -113,6 +131,7 @@ struct Order {
 #[derive(Debug, Clone)]
 struct RunnerOrderBook {
     runner_id: u64,
+    handicap: Decimal, // Asian handicap line, zero for ordinary runners
     runner_name: String,
     bids: Vec<(f64, f64)>, // (price, size)
     asks: Vec<(f64, f64)>, // (price, size)
@@ -148,10 +167,18 @@ struct App {
     selected_market: Option<usize>,
     market_browser_scroll_offset: usize, // Viewport scroll offset for market browser
 
+    // Country / venue / market type filters narrowing every browser level
+    filter_country: Option<String>,
+    filter_venue: Option<String>,
+    filter_market_type: Option<String>,
+    filter_picker: Option<BrowseFilterKind>, // Filter currently being picked, if any
+    filter_options: Vec<(Option<String>, u32)>, // (value, market_count); None clears the filter
+    selected_filter_option: Option<usize>,
+
     // Order book state
     current_orderbook: Option<OrderBookData>,
     selected_runner: Option<usize>,
-    streaming_orderbooks: Arc<RwLock<HashMap<String, HashMap<String, Orderbook>>>>, // market_id -> runner_id -> orderbook
+    streaming_orderbooks: Arc<RwLock<HashMap<String, HashMap<RunnerKey, Orderbook>>>>, // market_id -> runner key -> orderbook
     last_streaming_update: Option<Instant>, // Track when we last received streaming data
 
     // Active orders state
@@ -162,6 +189,7 @@ struct App {
     // Order entry state
     order_market_id: String,
     order_selection_id: String,
+    order_handicap: Decimal,
     order_runner_name: String,
     order_side: Side,
     order_price: String,
@@ -201,6 +229,7 @@ struct App {
     client: Option<BetfairClient>,
 }
 
+#[allow(clippy::unnecessary_sort_by)]
 impl App {
     fn new() -> Self {
         Self {
@@ -217,6 +246,13 @@ impl App {
             selected_market: None,
             market_browser_scroll_offset: 0,
 
+            filter_country: None,
+            filter_venue: None,
+            filter_market_type: None,
+            filter_picker: None,
+            filter_options: vec![],
+            selected_filter_option: None,
+
             current_orderbook: None,
             selected_runner: None,
             streaming_orderbooks: Arc::new(RwLock::new(HashMap::new())),
@@ -228,6 +264,7 @@ impl App {
 
             order_market_id: String::new(),
             order_selection_id: String::new(),
+            order_handicap: Decimal::ZERO,
             order_runner_name: String::new(),
             order_side: Side::Back,
             order_price: String::new(),
@@ -279,6 +316,13 @@ impl App {
         self.api_connected = true;
         self.status_message = "Initializing streaming...".to_string();
 
+        // The UI only redraws every 100ms, so conflated market data is plenty
+        if let Err(e) =
+            client.set_market_subscription_options(SubscriptionOptions::new(Some(100), None))
+        {
+            warn!("Failed to set market subscription options: {e}");
+        }
+
         // Try to start the streaming client
         match client.start_streaming().await {
             Ok(()) => {
@@ -317,8 +361,10 @@ impl App {
     }
 
     async fn load_sports(&mut self) -> Result<()> {
+        let mut filter = MarketFilter::default();
+        self.apply_browse_filters(&mut filter);
         if let Some(client) = &mut self.client {
-            let sports = client.list_sports(None).await?;
+            let sports = client.list_sports(Some(filter)).await?;
             self.sports = sports
                 .into_iter()
                 .filter(|s| s.market_count > 0)
@@ -330,11 +376,12 @@ impl App {
     }
 
     async fn load_competitions(&mut self, sport_id: &str) -> Result<()> {
+        let mut filter = MarketFilter {
+            event_type_ids: Some(vec![sport_id.to_string()]),
+            ..Default::default()
+        };
+        self.apply_browse_filters(&mut filter);
         if let Some(client) = &mut self.client {
-            let filter = MarketFilter {
-                event_type_ids: Some(vec![sport_id.to_string()]),
-                ..Default::default()
-            };
             let competitions = client.list_competitions(Some(filter)).await?;
             self.competitions = competitions
                 .into_iter()
@@ -346,14 +393,15 @@ impl App {
     }
 
     async fn load_events(&mut self, sport_id: &str, competition_id: Option<&str>) -> Result<()> {
+        let mut filter = MarketFilter {
+            event_type_ids: Some(vec![sport_id.to_string()]),
+            ..Default::default()
+        };
+        if let Some(comp_id) = competition_id {
+            filter.competition_ids = Some(vec![comp_id.to_string()]);
+        }
+        self.apply_browse_filters(&mut filter);
         if let Some(client) = &mut self.client {
-            let mut filter = MarketFilter {
-                event_type_ids: Some(vec![sport_id.to_string()]),
-                ..Default::default()
-            };
-            if let Some(comp_id) = competition_id {
-                filter.competition_ids = Some(vec![comp_id.to_string()]);
-            }
             let events = client.list_events(Some(filter)).await?;
             self.events = events
                 .into_iter()
@@ -365,15 +413,15 @@ impl App {
     }
 
     async fn load_markets(&mut self, sport_id: &str, event_id: Option<&str>) -> Result<()> {
+        let mut filter = MarketFilter {
+            event_type_ids: Some(vec![sport_id.to_string()]),
+            ..Default::default()
+        };
+        if let Some(ev_id) = event_id {
+            filter.event_ids = Some(vec![ev_id.to_string()]);
+        }
+        self.apply_browse_filters(&mut filter);
         if let Some(client) = &mut self.client {
-            let mut filter = MarketFilter {
-                event_type_ids: Some(vec![sport_id.to_string()]),
-                ..Default::default()
-            };
-            if let Some(ev_id) = event_id {
-                filter.event_ids = Some(vec![ev_id.to_string()]);
-            }
-
             let request = ListMarketCatalogueRequest {
                 filter,
                 market_projection: Some(vec![
@@ -420,6 +468,173 @@ impl App {
         Ok(())
     }
 
+    fn browse_filter_mut(&mut self, kind: BrowseFilterKind) -> &mut Option<String> {
+        match kind {
+            BrowseFilterKind::Country => &mut self.filter_country,
+            BrowseFilterKind::Venue => &mut self.filter_venue,
+            BrowseFilterKind::MarketType => &mut self.filter_market_type,
+        }
+    }
+
+    /// Restrict a filter to the selected country, venue and market type
+    fn apply_browse_filters(&self, filter: &mut MarketFilter) {
+        if let Some(country) = &self.filter_country {
+            filter.market_countries = Some(vec![country.clone()]);
+        }
+        if let Some(venue) = &self.filter_venue {
+            filter.venues = Some(vec![venue.clone()]);
+        }
+        if let Some(market_type) = &self.filter_market_type {
+            filter.market_type_codes = Some(vec![market_type.clone()]);
+        }
+    }
+
+    /// Filter matching the level the market browser is currently showing
+    fn browse_context_filter(&self) -> MarketFilter {
+        let mut filter = MarketFilter::default();
+        if !self.competitions.is_empty() {
+            if let Some((id, _, _)) = self.selected_sport.and_then(|i| self.sports.get(i)) {
+                filter.event_type_ids = Some(vec![id.clone()]);
+            }
+        }
+        if !self.events.is_empty() {
+            if let Some((id, _, _)) = self
+                .selected_competition
+                .and_then(|i| self.competitions.get(i))
+            {
+                filter.competition_ids = Some(vec![id.clone()]);
+            }
+        }
+        if !self.markets.is_empty() {
+            if let Some((id, _, _)) = self.selected_event.and_then(|i| self.events.get(i)) {
+                filter.event_ids = Some(vec![id.clone()]);
+            }
+        }
+        self.apply_browse_filters(&mut filter);
+        filter
+    }
+
+    async fn open_filter_picker(&mut self, kind: BrowseFilterKind) -> Result<()> {
+        let mut filter = self.browse_context_filter();
+        // Offer every value of the filter being picked, not just the current one
+        match kind {
+            BrowseFilterKind::Country => filter.market_countries = None,
+            BrowseFilterKind::Venue => filter.venues = None,
+            BrowseFilterKind::MarketType => filter.market_type_codes = None,
+        }
+
+        if let Some(client) = &mut self.client {
+            let mut options: Vec<(String, u32)> = match kind {
+                BrowseFilterKind::Country => client
+                    .list_countries(Some(filter))
+                    .await?
+                    .into_iter()
+                    .map(|c| (c.country_code, c.market_count as u32))
+                    .collect(),
+                BrowseFilterKind::Venue => client
+                    .list_venues(Some(filter))
+                    .await?
+                    .into_iter()
+                    .map(|v| (v.venue, v.market_count as u32))
+                    .collect(),
+                BrowseFilterKind::MarketType => client
+                    .list_market_types(Some(filter))
+                    .await?
+                    .into_iter()
+                    .map(|m| (m.market_type, m.market_count as u32))
+                    .collect(),
+            };
+            options.sort_by_key(|b| std::cmp::Reverse(b.1));
+
+            self.filter_options = std::iter::once((None, 0))
+                .chain(
+                    options
+                        .into_iter()
+                        .map(|(value, count)| (Some(value), count)),
+                )
+                .collect();
+            let current = self.browse_filter_mut(kind).clone();
+            self.selected_filter_option = self
+                .filter_options
+                .iter()
+                .position(|(value, _)| *value == current)
+                .or(Some(0));
+            self.filter_picker = Some(kind);
+            self.reset_market_browser_scroll();
+            self.status_message = format!("Select {}", kind.label().to_lowercase());
+        }
+        Ok(())
+    }
+
+    fn close_filter_picker(&mut self) {
+        self.filter_picker = None;
+        self.filter_options.clear();
+        self.selected_filter_option = None;
+        self.reset_market_browser_scroll();
+    }
+
+    /// Apply the highlighted filter option and reload the current browser level
+    async fn apply_filter_option(&mut self) -> Result<()> {
+        let Some(kind) = self.filter_picker else {
+            return Ok(());
+        };
+        let value = self
+            .selected_filter_option
+            .and_then(|i| self.filter_options.get(i))
+            .and_then(|(value, _)| value.clone());
+        self.status_message = match &value {
+            Some(value) => format!("{}: {value}", kind.label()),
+            None => format!("{}: All", kind.label()),
+        };
+        *self.browse_filter_mut(kind) = value;
+        self.close_filter_picker();
+
+        let sport_id = self
+            .selected_sport
+            .and_then(|i| self.sports.get(i))
+            .map(|s| s.0.clone());
+        if !self.markets.is_empty() {
+            let event_id = self
+                .selected_event
+                .and_then(|i| self.events.get(i))
+                .map(|e| e.0.clone());
+            if let Some(sport_id) = sport_id {
+                self.load_markets(&sport_id, event_id.as_deref()).await?;
+                self.selected_market = if self.markets.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                };
+            }
+        } else if !self.events.is_empty() {
+            let comp_id = self
+                .selected_competition
+                .and_then(|i| self.competitions.get(i))
+                .map(|c| c.0.clone());
+            if let Some(sport_id) = sport_id {
+                self.load_events(&sport_id, comp_id.as_deref()).await?;
+                self.selected_event = if self.events.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                };
+            }
+        } else if !self.competitions.is_empty() {
+            if let Some(sport_id) = sport_id {
+                self.load_competitions(&sport_id).await?;
+                self.selected_competition = if self.competitions.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                };
+            }
+        } else {
+            self.load_sports().await?;
+            self.selected_sport = None;
+        }
+        Ok(())
+    }
+
     async fn load_orderbook(&mut self, market_id: &str) -> Result<()> {
         // Clear current orderbook and reset selection
         self.current_orderbook = None;
@@ -447,10 +662,9 @@ impl App {
                 if let Some(market) = markets.first() {
                     if let Some(runners) = &market.runners {
                         for runner in runners {
-                            runner_names.insert(
-                                runner.selection_id.to_string(),
-                                runner.runner_name.clone(),
-                            );
+                            let key =
+                                RunnerKey::new(runner.selection_id as u64, Some(runner.handicap));
+                            runner_names.insert(key, runner.runner_name.clone());
                         }
                     }
                 }
@@ -477,6 +691,11 @@ impl App {
                     if let Some(oldest_market) = self.subscribed_markets.iter().next().cloned() {
                         info!("Removing oldest market {oldest_market} to stay within limit");
                         self.subscribed_markets.remove(&oldest_market);
+                        if let Some(client) = &self.client {
+                            if let Err(e) = client.unsubscribe_from_market(oldest_market).await {
+                                warn!("Failed to unsubscribe from market: {e}");
+                            }
+                        }
                     }
                 }
 
@@ -546,8 +765,8 @@ impl App {
                         if !market_orderbooks.is_empty() {
                             let mut runner_books = vec![];
 
-                            for (runner_id_str, orderbook) in market_orderbooks {
-                                let runner_id: u64 = runner_id_str.parse().unwrap_or(0);
+                            for (runner_key, orderbook) in market_orderbooks {
+                                let runner_id = runner_key.selection_id;
 
                                 // Convert streaming orderbook to our format
                                 let bids: Vec<(f64, f64)> = orderbook
@@ -575,12 +794,13 @@ impl App {
                                     .collect();
 
                                 let runner_name = runner_names
-                                    .get(runner_id_str)
+                                    .get(runner_key)
                                     .cloned()
                                     .unwrap_or_else(|| format!("Runner {runner_id}"));
 
                                 runner_books.push(RunnerOrderBook {
                                     runner_id,
+                                    handicap: runner_key.handicap,
                                     runner_name,
                                     bids,
                                     asks,
@@ -593,8 +813,8 @@ impl App {
                                 });
                             }
 
-                            // Sort runners by ID for consistent ordering
-                            runner_books.sort_by_key(|r| r.runner_id);
+                            // Sort runners by ID and handicap for consistent ordering
+                            runner_books.sort_by_key(|r| (r.runner_id, r.handicap));
 
                             self.current_orderbook = Some(OrderBookData {
                                 market_id: market_id.to_string(),
@@ -682,8 +902,10 @@ impl App {
                             }
                         }
 
+                        let runner_key =
+                            RunnerKey::new(runner.selection_id as u64, Some(runner.handicap));
                         let runner_name = runner_names
-                            .get(&runner.selection_id.to_string())
+                            .get(&runner_key)
                             .cloned()
                             .or_else(|| {
                                 self.markets
@@ -700,6 +922,7 @@ impl App {
 
                         runner_books.push(RunnerOrderBook {
                             runner_id: runner.selection_id as u64,
+                            handicap: runner_key.handicap,
                             runner_name,
                             bids,
                             asks,
@@ -715,8 +938,8 @@ impl App {
                         });
                     }
 
-                    // Sort runners by ID for consistent ordering
-                    runner_books.sort_by_key(|r| r.runner_id);
+                    // Sort runners by ID and handicap for consistent ordering
+                    runner_books.sort_by_key(|r| (r.runner_id, r.handicap));
 
                     self.current_orderbook = Some(OrderBookData {
                         market_id: market_id.to_string(),
@@ -1040,7 +1263,7 @@ impl App {
             let instruction = PlaceInstruction {
                 order_type: OrderType::Limit,
                 selection_id,
-                handicap: Some(Decimal::ZERO),
+                handicap: Some(self.order_handicap),
                 side: self.order_side.clone(),
                 limit_order: Some(LimitOrder {
                     size: Decimal::from_f64(size).unwrap_or(Decimal::ZERO),
@@ -1159,7 +1382,9 @@ impl App {
 
     fn update_market_browser_scroll(&mut self, viewport_height: usize) {
         // Update scroll offset based on selected item
-        let selected_index = if !self.markets.is_empty() {
+        let selected_index = if self.filter_picker.is_some() {
+            self.selected_filter_option
+        } else if !self.markets.is_empty() {
             self.selected_market
         } else if !self.events.is_empty() {
             self.selected_event
@@ -1309,16 +1534,16 @@ impl App {
 
         // Update existing runners with streaming data
         for (runner_index, runner) in current_ob.runners.iter_mut().enumerate() {
-            let runner_id_str = runner.runner_id.to_string();
-            debug!("Checking runner {runner_index}: ID {runner_id_str}");
+            let runner_key = RunnerKey::new(runner.runner_id, Some(runner.handicap));
+            debug!("Checking runner {runner_index}: ID {runner_key}");
 
-            let Some(streaming_ob) = market_orderbooks.get(&runner_id_str) else {
-                debug!("No streaming data found for runner {runner_id_str}");
+            let Some(streaming_ob) = market_orderbooks.get(&runner_key) else {
+                debug!("No streaming data found for runner {runner_key}");
                 continue;
             };
 
             debug!(
-                "Found streaming data for runner {runner_id_str}: {} bids, {} asks",
+                "Found streaming data for runner {runner_key}: {} bids, {} asks",
                 streaming_ob.bids.len(),
                 streaming_ob.asks.len()
             );
@@ -1349,16 +1574,16 @@ impl App {
 
             // Log current vs new data for comparison
             debug!(
-                "Runner {runner_id_str} current bids: {:?}",
+                "Runner {runner_key} current bids: {:?}",
                 runner.bids.iter().take(3).collect::<Vec<_>>()
             );
             debug!(
-                "Runner {runner_id_str} new bids: {:?}",
+                "Runner {runner_key} new bids: {:?}",
                 new_bids.iter().take(3).collect::<Vec<_>>()
             );
 
             if new_bids != runner.bids || new_asks != runner.asks {
-                info!("Data changed for runner {runner_id_str} - applying update");
+                info!("Data changed for runner {runner_key} - applying update");
                 data_updated = true;
                 updates_applied += 1;
 
@@ -1370,12 +1595,12 @@ impl App {
                 runner.last_update = Some(Instant::now());
 
                 debug!(
-                    "Updated runner {runner_id_str}: {} bids, {} asks",
+                    "Updated runner {runner_key}: {} bids, {} asks",
                     runner.bids.len(),
                     runner.asks.len()
                 );
             } else {
-                debug!("No data changes detected for runner {runner_id_str}");
+                debug!("No data changes detected for runner {runner_key}");
             }
 
             runner.is_streaming = true;
@@ -1541,6 +1766,21 @@ fn render_market_browser(f: &mut Frame, area: Rect, app: &App) {
             breadcrumb.push_str(&format!(" > {name}"));
         }
     }
+    let active_filters: Vec<&str> = [
+        &app.filter_country,
+        &app.filter_venue,
+        &app.filter_market_type,
+    ]
+    .into_iter()
+    .flatten()
+    .map(String::as_str)
+    .collect();
+    if !active_filters.is_empty() {
+        breadcrumb.push_str(&format!(" [{}]", active_filters.join(" | ")));
+    }
+    if let Some(kind) = app.filter_picker {
+        breadcrumb.push_str(&format!(" > Filter: {}", kind.label()));
+    }
     breadcrumb.push(' ');
 
     let block = Block::default()
@@ -1555,8 +1795,29 @@ fn render_market_browser(f: &mut Frame, area: Rect, app: &App) {
     let visible_height = inner.height as usize;
 
     // Create list items based on current navigation level with selection keys
-    // Priority: markets > events > competitions > sports
-    let all_items: Vec<ListItem> = if !app.markets.is_empty() {
+    // Priority: filter picker > markets > events > competitions > sports
+    let all_items: Vec<ListItem> = if app.filter_picker.is_some() {
+        app.filter_options
+            .iter()
+            .enumerate()
+            .map(|(idx, (value, count))| {
+                let is_selected = app.selected_filter_option == Some(idx);
+                let style = if is_selected {
+                    Style::default()
+                        .bg(Color::Yellow)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                match value {
+                    Some(value) => ListItem::new(format!("{value:<42} [{count} markets]")),
+                    None => ListItem::new("All"),
+                }
+                .style(style)
+            })
+            .collect()
+    } else if !app.markets.is_empty() {
         // Show markets
         app.markets
             .iter()
@@ -1732,7 +1993,14 @@ fn render_order_book(f: &mut Frame, area: Rect, app: &App) {
             };
 
             // Create runner header - show both name and ID
-            let runner_title = format!("{} (ID: {})", runner.runner_name, runner.runner_id);
+            let runner_title = if runner.handicap.is_zero() {
+                format!("{} (ID: {})", runner.runner_name, runner.runner_id)
+            } else {
+                format!(
+                    "{} [{}] (ID: {})",
+                    runner.runner_name, runner.handicap, runner.runner_id
+                )
+            };
 
             // Create order book rows for this runner
             let mut rows = vec![];
@@ -2128,6 +2396,7 @@ fn render_shortcuts_bar(f: &mut Frame, area: Rect, app: &App) {
                     ("↑↓/jk", "Navigate"),
                     ("Enter", "Select"),
                     ("Backspace", "Back"),
+                    ("c/v/t", "Country/Venue/Type"),
                     ("Tab", "Next Panel"),
                     ("d", "Diagnostics"),
                     ("o", "Order"),
@@ -2309,11 +2578,14 @@ fn handle_runner_selection(app: &mut App, index: usize) {
             app.selected_runner = Some(index);
             let runner = &orderbook.runners[index];
             app.order_selection_id = runner.runner_id.to_string();
+            app.order_handicap = runner.handicap;
             app.order_runner_name = runner.runner_name.clone();
         }
     }
 }
 
+// Panel-specific keys are swallowed outside their panel, so the nested `if`s stay
+#[allow(clippy::collapsible_match)]
 async fn handle_input(app: &mut App, key: KeyCode) -> Result<bool> {
     match app.mode {
         AppMode::Browse => {
@@ -2348,12 +2620,35 @@ async fn handle_input(app: &mut App, key: KeyCode) -> Result<bool> {
                     }
                 }
                 KeyCode::Char('?') => app.mode = AppMode::Help,
+                KeyCode::Char('c') | KeyCode::Char('v') | KeyCode::Char('t')
+                    if app.active_panel == Panel::MarketBrowser =>
+                {
+                    let kind = match key {
+                        KeyCode::Char('c') => BrowseFilterKind::Country,
+                        KeyCode::Char('v') => BrowseFilterKind::Venue,
+                        _ => BrowseFilterKind::MarketType,
+                    };
+                    if let Err(e) = app.open_filter_picker(kind).await {
+                        app.error_message = Some(format!(
+                            "Failed to load {}s: {}",
+                            kind.label().to_lowercase(),
+                            e
+                        ));
+                    }
+                }
                 // Arrow key navigation (Up/Down for moving within panel)
                 KeyCode::Up | KeyCode::Char('k') => {
                     match app.active_panel {
                         Panel::MarketBrowser => {
                             // Navigate up in the currently active list
-                            if !app.markets.is_empty() {
+                            if app.filter_picker.is_some() {
+                                if let Some(selected) = app.selected_filter_option {
+                                    if selected > 0 {
+                                        app.selected_filter_option = Some(selected - 1);
+                                        app.update_market_browser_scroll(20);
+                                    }
+                                }
+                            } else if !app.markets.is_empty() {
                                 if let Some(selected) = app.selected_market {
                                     if selected > 0 {
                                         app.selected_market = Some(selected - 1);
@@ -2427,7 +2722,14 @@ async fn handle_input(app: &mut App, key: KeyCode) -> Result<bool> {
                     match app.active_panel {
                         Panel::MarketBrowser => {
                             // Navigate down in the currently active list
-                            if !app.markets.is_empty() {
+                            if app.filter_picker.is_some() {
+                                if let Some(selected) = app.selected_filter_option {
+                                    if selected < app.filter_options.len() - 1 {
+                                        app.selected_filter_option = Some(selected + 1);
+                                        app.update_market_browser_scroll(20);
+                                    }
+                                }
+                            } else if !app.markets.is_empty() {
                                 if let Some(selected) = app.selected_market {
                                     if selected < app.markets.len() - 1 {
                                         app.selected_market = Some(selected + 1);
@@ -2513,7 +2815,12 @@ async fn handle_input(app: &mut App, key: KeyCode) -> Result<bool> {
                     match app.active_panel {
                         Panel::MarketBrowser => {
                             // Handle navigation through hierarchy based on current level
-                            if !app.markets.is_empty() {
+                            if app.filter_picker.is_some() {
+                                if let Err(e) = app.apply_filter_option().await {
+                                    app.error_message =
+                                        Some(format!("Failed to apply filter: {}", e));
+                                }
+                            } else if !app.markets.is_empty() {
                                 // We're at market level - load orderbook for selected market
                                 if let Some(index) = app.selected_market {
                                     if let Some(market) = app.markets.get(index) {
@@ -2532,6 +2839,7 @@ async fn handle_input(app: &mut App, key: KeyCode) -> Result<bool> {
                                                 {
                                                     app.order_selection_id =
                                                         first_runner.runner_id.to_string();
+                                                    app.order_handicap = first_runner.handicap;
                                                     app.order_runner_name =
                                                         first_runner.runner_name.clone();
                                                     app.selected_runner = Some(0);
@@ -2624,7 +2932,11 @@ async fn handle_input(app: &mut App, key: KeyCode) -> Result<bool> {
                 }
                 KeyCode::Backspace | KeyCode::Esc => {
                     // Navigate back in hierarchy
-                    if !app.markets.is_empty() {
+                    if app.filter_picker.is_some() {
+                        // Leave the filter picker without changing the filter
+                        app.close_filter_picker();
+                        app.status_message = "Filter unchanged".to_string();
+                    } else if !app.markets.is_empty() {
                         // We're viewing markets - go back to events
                         app.markets.clear();
                         app.selected_market = None;
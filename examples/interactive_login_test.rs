@@ -23,6 +23,9 @@ async fn main() -> Result<()> {
             password: String::new(), // Not used for interactive login
             api_key,
             pem_path: String::new(), // Not used for interactive login
+            stream_url: None,
+            jurisdiction: Default::default(),
+            endpoints: None,
         },
     };
 
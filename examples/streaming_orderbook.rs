@@ -1,4 +1,5 @@
 use anyhow::Result;
+use betfair_rs::dto::RunnerKey;
 use betfair_rs::orderbook::{Orderbook, PriceLevel};
 use betfair_rs::{BetfairClient, Config, StreamingClient};
 use rust_decimal::Decimal;
@@ -60,7 +61,7 @@ async fn main() -> Result<()> {
     }
 }
 
-fn print_orderbook_summary(market_id: &str, market_books: &HashMap<String, Orderbook>) {
+fn print_orderbook_summary(market_id: &str, market_books: &HashMap<RunnerKey, Orderbook>) {
     println!("\n{}", "=".repeat(80));
     println!(
         "Market ID: {} | Time: {}",
@@ -75,16 +75,16 @@ fn print_order_summary(market_id: &str, cache: &betfair_rs::order_cache::OrderCa
         return;
     }
 
-    for (selection_id, runner) in cache.runners.iter() {
-        println!("\nSelection ID: {selection_id}");
+    for runner in cache.runners.values() {
+        println!("\nSelection ID: {}", runner.selection_id);
         if let Some(handicap) = runner.handicap {
             println!("  Handicap: {handicap}");
         }
 
         if runner.orders.is_empty() {
-            println!("  No active orders");
+            println!("  No orders");
         } else {
-            println!("\n  Active Orders ({}):", runner.orders.len());
+            println!("\n  Orders ({}):", runner.orders.len());
             println!(
                 "  {:^15} {:^10} {:^10} {:^10} {:^15} {:^10}",
                 "Bet ID", "Side", "Price", "Size", "Status", "Matched"
@@ -1,4 +1,148 @@
 // Re-export account DTOs from the dto module
 pub use crate::dto::account::*;
 
-// Additional account-specific functionality can be added here if needed
+use crate::api_client::RestClient;
+use anyhow::Result;
+use std::collections::VecDeque;
+
+/// Async iterator over an account statement, requesting the next page from `fromRecord`
+/// while `moreAvailable` is set
+pub struct AccountStatementPager<'a> {
+    client: &'a RestClient,
+    request: GetAccountStatementRequest,
+    next_record: i32,
+    buffered: VecDeque<StatementItem>,
+    done: bool,
+}
+
+impl<'a> AccountStatementPager<'a> {
+    pub(crate) fn new(client: &'a RestClient, request: GetAccountStatementRequest) -> Self {
+        Self {
+            client,
+            next_record: request.from_record.unwrap_or(0),
+            request,
+            buffered: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// The next page of items, or `None` once every item has been fetched
+    pub async fn next_page(&mut self) -> Result<Option<Vec<StatementItem>>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let mut request = self.request.clone();
+        request.from_record = Some(self.next_record);
+        let report = self.client.get_account_statement(request).await?;
+        Ok(self.record_page(report))
+    }
+
+    /// The next item, fetching another page when the current one is used up
+    pub async fn next_item(&mut self) -> Result<Option<StatementItem>> {
+        while self.buffered.is_empty() {
+            match self.next_page().await? {
+                Some(items) => self.buffered.extend(items),
+                None => return Ok(None),
+            }
+        }
+        Ok(self.buffered.pop_front())
+    }
+
+    /// Fetch every remaining item
+    pub async fn collect_all(mut self) -> Result<Vec<StatementItem>> {
+        let mut items: Vec<StatementItem> = self.buffered.drain(..).collect();
+        while let Some(page) = self.next_page().await? {
+            items.extend(page);
+        }
+        Ok(items)
+    }
+
+    fn record_page(&mut self, report: AccountStatementReport) -> Option<Vec<StatementItem>> {
+        self.next_record += report.account_statement.len() as i32;
+        // An empty page cannot advance `fromRecord`, so it ends the statement too
+        self.done = !report.more_available || report.account_statement.is_empty();
+
+        if report.account_statement.is_empty() {
+            None
+        } else {
+            Some(report.account_statement)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BetfairConfig, Config};
+
+    fn create_test_client() -> RestClient {
+        RestClient::new(Config {
+            betfair: BetfairConfig {
+                username: "test_user".to_string(),
+                password: "test_pass".to_string(),
+                api_key: "test_api_key".to_string(),
+                pem_path: "/tmp/test.pem".to_string(),
+                stream_url: None,
+                jurisdiction: Default::default(),
+                endpoints: None,
+            },
+        })
+    }
+
+    fn report(ref_ids: &[&str], more_available: bool) -> AccountStatementReport {
+        let items = ref_ids
+            .iter()
+            .map(|ref_id| {
+                serde_json::from_value(serde_json::json!({
+                    "refId": ref_id,
+                    "itemDate": "2025-10-05T01:02:11.000Z",
+                    "amount": -2.0,
+                    "balance": 100.0,
+                    "itemClass": "UNKNOWN",
+                    "itemClassData": {}
+                }))
+                .unwrap()
+            })
+            .collect();
+        AccountStatementReport {
+            account_statement: items,
+            more_available,
+        }
+    }
+
+    #[test]
+    fn test_pager_follows_more_available() {
+        let client = create_test_client();
+        let mut pager = AccountStatementPager::new(
+            &client,
+            GetAccountStatementRequest {
+                from_record: Some(10),
+                record_count: Some(2),
+                ..Default::default()
+            },
+        );
+        assert_eq!(pager.next_record, 10);
+
+        let page = pager.record_page(report(&["1", "2"], true)).unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(pager.next_record, 12);
+        assert!(!pager.done);
+
+        pager.record_page(report(&["3"], false)).unwrap();
+        assert_eq!(pager.next_record, 13);
+        assert!(pager.done);
+    }
+
+    #[tokio::test]
+    async fn test_pager_stops_on_empty_page() {
+        let client = create_test_client();
+        let mut pager = AccountStatementPager::new(&client, GetAccountStatementRequest::default());
+
+        assert!(pager.record_page(report(&[], true)).is_none());
+        assert!(pager.done);
+        // Nothing else is requested once done
+        assert!(pager.next_page().await.unwrap().is_none());
+        assert!(pager.next_item().await.unwrap().is_none());
+    }
+}
@@ -1,19 +1,127 @@
-use crate::config::Config;
+use crate::account::AccountStatementPager;
+use crate::config::{Config, Endpoints};
 use crate::dto::rpc::{InteractiveLoginResponse, LoginResponse};
 use crate::dto::*;
+use crate::error::{ApiErrorCode, BetfairError};
+use crate::heartbeat::{self, HeartbeatHandle, HeartbeatStatus};
 use crate::rate_limiter::BetfairRateLimiter;
+use crate::request_weight;
 use crate::retry::RetryPolicy;
+use crate::session::{self, KeepAliveHandle, SessionToken};
 use anyhow::Result;
 use reqwest::{header::HeaderMap, Client};
 use rust_decimal::{prelude::FromPrimitive, Decimal};
 use serde::{de::DeserializeOwned, Serialize};
-use std::sync::Arc;
-use tracing::debug;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+
+/// The result of a JSON-RPC response body, or the error it reports.
+///
+/// A JSON-RPC error takes precedence over the HTTP status, as Betfair sends some with a 400.
+fn json_rpc_result<U: DeserializeOwned>(
+    status: reqwest::StatusCode,
+    body: &str,
+) -> std::result::Result<U, BetfairError> {
+    match serde_json::from_str::<JsonRpcResponse<U>>(body) {
+        Ok(JsonRpcResponse {
+            error: Some(error), ..
+        }) => Err(BetfairError::from_api_error(error)),
+        _ if !status.is_success() => Err(BetfairError::Http {
+            status: status.as_u16(),
+            body: body.to_string(),
+        }),
+        Ok(JsonRpcResponse {
+            result: Some(result),
+            ..
+        }) => Ok(result),
+        Ok(_) => Err(BetfairError::InvalidResponse(
+            "No result in response".to_string(),
+        )),
+        Err(e) => Err(BetfairError::InvalidResponse(format!(
+            "{e}\nResponse body: {body}"
+        ))),
+    }
+}
+
+/// Whether a keepAlive or logout failed because the session had already expired
+fn is_session_expired(response: &SessionResponse) -> bool {
+    response
+        .error
+        .as_deref()
+        .is_some_and(|code| ApiErrorCode::from(code).is_session_error())
+}
 
-const LOGIN_URL: &str = "https://identitysso-cert.betfair.com/api/certlogin";
-const INTERACTIVE_LOGIN_URL: &str = "https://identitysso.betfair.com/api/login";
-const BETTING_URL: &str = "https://api.betfair.com/exchange/betting/json-rpc/v1";
-const ACCOUNT_URL: &str = "https://api.betfair.com/exchange/account/json-rpc/v1";
+/// A unique reference for `customerRef` and `customerOrderRef`, both limited to 32 characters.
+///
+/// Betfair ignores a repeated `customerRef` for a while, so a transaction resent by mistake
+/// is not executed twice.
+fn transaction_ref() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Give the request and every instruction a reference they can be recognised by
+fn assign_place_refs(request: &mut PlaceOrdersRequest) {
+    request.customer_ref.get_or_insert_with(transaction_ref);
+    for instruction in &mut request.instructions {
+        instruction
+            .customer_order_ref
+            .get_or_insert_with(transaction_ref);
+    }
+}
+
+/// Current orders touched by a cancel, replace or update; all of the market's if no bets are named
+fn transaction_lookup(
+    market_id: &str,
+    bet_ids: impl Iterator<Item = String>,
+) -> ListCurrentOrdersRequest {
+    let bet_ids: Vec<String> = bet_ids.collect();
+    ListCurrentOrdersRequest {
+        bet_ids: (!bet_ids.is_empty()).then_some(bet_ids),
+        market_ids: (!market_id.is_empty()).then(|| vec![market_id.to_string()]),
+        ..Default::default()
+    }
+}
+
+/// The report Betfair would have sent, if every instruction shows up in `current_orders`
+fn recovered_place_report(
+    request: &PlaceOrdersRequest,
+    current_orders: &[CurrentOrderSummary],
+) -> Option<PlaceOrdersResponse> {
+    let instruction_reports = request
+        .instructions
+        .iter()
+        .map(|instruction| {
+            let order = current_orders.iter().find(|order| {
+                order.customer_order_ref.is_some()
+                    && order.customer_order_ref == instruction.customer_order_ref
+            })?;
+            Some(PlaceInstructionReport {
+                status: "SUCCESS".to_string(),
+                error_code: None,
+                order_status: Some(order.status.clone()),
+                instruction: instruction.clone(),
+                bet_id: Some(order.bet_id.clone()),
+                placed_date: order.placed_date.clone(),
+                average_price_matched: order.average_price_matched,
+                size_matched: order.size_matched,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(PlaceOrdersResponse {
+        status: "SUCCESS".to_string(),
+        error_code: None,
+        market_id: request.market_id.clone(),
+        instruction_reports: Some(instruction_reports),
+        customer_ref: request.customer_ref.clone(),
+    })
+}
 
 fn load_pem_identity(pem_path: &str) -> Result<reqwest::Identity> {
     let pem_contents = std::fs::read(pem_path)
@@ -23,37 +131,62 @@ fn load_pem_identity(pem_path: &str) -> Result<reqwest::Identity> {
         .map_err(|e| anyhow::anyhow!("Failed to parse PEM identity: {e}"))
 }
 
-/// REST API client for all Betfair operations
+/// How the current session was obtained, so it can be repeated when it expires
+#[derive(Clone)]
+enum LoginMethod {
+    Certificate,
+    Interactive { username: String, password: String },
+}
+
+/// REST API client for all Betfair operations.
+///
+/// Clones share the session and the rate limits.
+#[derive(Clone)]
 pub struct RestClient {
     client: Client,
     config: Arc<Config>,
-    session_token: Option<String>,
+    endpoints: Arc<Endpoints>,
+    session: SessionToken,
+    login_method: Arc<RwLock<Option<LoginMethod>>>,
+    relogin_lock: Arc<Mutex<()>>,
     retry_policy: RetryPolicy,
     rate_limiter: BetfairRateLimiter,
 }
 
 impl RestClient {
-    /// Create a new API client
+    /// Create a new API client for the endpoints of the config
     pub fn new(config: Config) -> Self {
         let client = Client::new();
 
         Self {
             client,
+            endpoints: Arc::new(config.endpoints()),
             config: Arc::new(config),
-            session_token: None,
+            session: SessionToken::default(),
+            login_method: Arc::new(RwLock::new(None)),
+            relogin_lock: Arc::new(Mutex::new(())),
             retry_policy: RetryPolicy::default(),
             rate_limiter: BetfairRateLimiter::new(),
         }
     }
 
-    /// Login to Betfair using certificate authentication and obtain session token
+    /// Login to Betfair using certificate authentication and obtain session token.
+    ///
+    /// A refused login fails with `BetfairError::Login`.
     pub async fn login(&mut self) -> Result<LoginResponse> {
+        let response = self.certificate_login().await?;
+        self.set_login_method(Some(LoginMethod::Certificate));
+        Ok(response)
+    }
+
+    async fn certificate_login(&self) -> Result<LoginResponse> {
         crate::ensure_crypto_provider();
 
         let api_key = self.config.betfair.api_key.clone();
         let username = self.config.betfair.username.clone();
         let password = self.config.betfair.password.clone();
         let pem_path = self.config.betfair.pem_path.clone();
+        let login_url = self.endpoints.cert_login_url();
 
         let response = self
             .retry_policy
@@ -62,6 +195,7 @@ impl RestClient {
                 let username = username.clone();
                 let password = password.clone();
                 let pem_path = pem_path.clone();
+                let login_url = login_url.clone();
                 async move {
                     let identity = load_pem_identity(&pem_path)?;
 
@@ -79,23 +213,32 @@ impl RestClient {
                     ];
 
                     let http_response = client
-                        .post(LOGIN_URL)
+                        .post(&login_url)
                         .headers(headers)
                         .header("X-Application", format!("app_{}", rand::random::<u128>()))
                         .form(&form)
                         .send()
-                        .await?;
+                        .await
+                        .map_err(BetfairError::Transport)?;
 
-                    let response_text = http_response.text().await?;
+                    let status = http_response.status();
+                    let response_text =
+                        http_response.text().await.map_err(BetfairError::Transport)?;
                     tracing::info!("Login response: {}", response_text);
 
-                    let response: LoginResponse = serde_json::from_str(&response_text)
-                        .map_err(|e| {
-                            anyhow::anyhow!(
-                                "Failed to deserialize login response: {}\nResponse body: {}",
-                                e,
-                                response_text
-                            )
+                    if !status.is_success() {
+                        return Err(BetfairError::Http {
+                            status: status.as_u16(),
+                            body: response_text,
+                        }
+                        .into());
+                    }
+
+                    let response: LoginResponse =
+                        serde_json::from_str(&response_text).map_err(|e| {
+                            BetfairError::InvalidResponse(format!(
+                                "Failed to deserialize login response: {e}\nResponse body: {response_text}"
+                            ))
                         })?;
 
                     Ok(response)
@@ -103,10 +246,15 @@ impl RestClient {
             })
             .await?;
 
-        if response.login_status == "SUCCESS" {
-            self.session_token = Some(response.session_token.clone());
+        if response.login_status != "SUCCESS" {
+            return Err(BetfairError::Login {
+                status: response.login_status,
+                message: None,
+            }
+            .into());
         }
 
+        self.session.set(response.session_token.clone());
         Ok(response)
     }
 
@@ -115,10 +263,23 @@ impl RestClient {
         &mut self,
         username: String,
         password: String,
+    ) -> Result<InteractiveLoginResponse> {
+        let response = self
+            .interactive_login(username.clone(), password.clone())
+            .await?;
+        self.set_login_method(Some(LoginMethod::Interactive { username, password }));
+        Ok(response)
+    }
+
+    async fn interactive_login(
+        &self,
+        username: String,
+        password: String,
     ) -> Result<InteractiveLoginResponse> {
         let api_key = self.config.betfair.api_key.clone();
         let client = self.client.clone();
         let retry_policy = self.retry_policy.clone();
+        let login_url = self.endpoints.login_url();
 
         let response = retry_policy
             .retry(|| {
@@ -126,6 +287,7 @@ impl RestClient {
                 let username = username.clone();
                 let password = password.clone();
                 let client = client.clone();
+                let login_url = login_url.clone();
 
                 async move {
                     let mut headers = HeaderMap::new();
@@ -139,27 +301,31 @@ impl RestClient {
                     ];
 
                     let response = client
-                        .post(INTERACTIVE_LOGIN_URL)
+                        .post(&login_url)
                         .headers(headers)
                         .form(&form)
                         .send()
-                        .await?;
+                        .await
+                        .map_err(BetfairError::Transport)?;
 
                     let status = response.status();
-                    let body = response.text().await?;
+                    let body = response.text().await.map_err(BetfairError::Transport)?;
 
                     debug!("Interactive login response status: {}", status);
                     debug!("Interactive login response body: {}", body);
 
                     if !status.is_success() {
-                        return Err(anyhow::anyhow!(
-                            "Interactive login failed with status {}: {}",
-                            status,
-                            body.trim()
-                        ));
+                        return Err(BetfairError::Http {
+                            status: status.as_u16(),
+                            body,
+                        }
+                        .into());
                     }
 
-                    let login_response: InteractiveLoginResponse = serde_json::from_str(&body)?;
+                    let login_response: InteractiveLoginResponse = serde_json::from_str(&body)
+                        .map_err(|e| {
+                            BetfairError::InvalidResponse(format!("{e}\nResponse body: {body}"))
+                        })?;
 
                     Ok(login_response)
                 }
@@ -173,7 +339,11 @@ impl RestClient {
         let status = self.get_login_status(&response);
         if !status.is_empty() && status.to_uppercase() != "SUCCESS" {
             let error_msg = self.get_error_message(&response, &status);
-            return Err(anyhow::anyhow!("Interactive login {status}: {error_msg}"));
+            return Err(BetfairError::Login {
+                status,
+                message: Some(error_msg),
+            }
+            .into());
         }
 
         if session_token.is_empty() {
@@ -182,7 +352,7 @@ impl RestClient {
             ));
         }
 
-        self.session_token = Some(session_token);
+        self.session.set(session_token);
         Ok(response)
     }
 
@@ -224,127 +394,627 @@ impl RestClient {
             .unwrap_or_else(|| format!("Login failed with status: {status}"))
     }
 
+    fn set_login_method(&self, method: Option<LoginMethod>) {
+        if let Ok(mut login_method) = self.login_method.write() {
+            *login_method = method;
+        }
+    }
+
     /// Get current session token
     pub fn get_session_token(&self) -> Option<String> {
-        self.session_token.clone()
+        self.session.get()
     }
 
     /// Set session token (useful for restoring sessions)
     pub fn set_session_token(&mut self, token: String) {
-        self.session_token = Some(token);
+        self.session.set(token);
+    }
+
+    /// Shared handle to the session token, kept up to date across re-logins
+    pub fn session(&self) -> SessionToken {
+        self.session.clone()
+    }
+
+    /// Extend the session so it does not expire through inactivity
+    pub async fn keep_alive(&self) -> Result<SessionResponse> {
+        let response = self
+            .session_request(&self.endpoints.keep_alive_url())
+            .await?;
+        if response.is_success() {
+            if let Some(token) = response.token.as_ref().filter(|token| !token.is_empty()) {
+                self.session.set(token.clone());
+            }
+        }
+        Ok(response)
     }
 
-    /// Generic method to make JSON-RPC API requests
+    /// Log out, invalidating the session token.
+    ///
+    /// Expired sessions are no longer renewed automatically until the next login.
+    pub async fn logout(&self) -> Result<SessionResponse> {
+        let response = self.session_request(&self.endpoints.logout_url()).await?;
+        if response.is_success() {
+            self.set_login_method(None);
+            self.session.clear();
+        }
+        Ok(response)
+    }
+
+    /// Call `keep_alive` every `interval` from a background task.
+    ///
+    /// If the session expired anyway it is replaced by logging in again. The task runs
+    /// until the returned handle is stopped or dropped.
+    pub fn spawn_keep_alive(&self, interval: Duration) -> KeepAliveHandle {
+        let client = self.clone();
+        KeepAliveHandle::new(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+            loop {
+                ticker.tick().await;
+                client.refresh_session().await;
+            }
+        }))
+    }
+
+    async fn refresh_session(&self) {
+        let Some(session_token) = self.session.get() else {
+            return;
+        };
+
+        match self.keep_alive().await {
+            Ok(response) if response.is_success() => debug!("Session kept alive"),
+            Ok(response) if is_session_expired(&response) => {
+                warn!(
+                    "Session expired before keep-alive ({:?}), logging in again",
+                    response.error
+                );
+                if let Err(e) = self.relogin(&session_token).await {
+                    error!("Failed to log in again after session expiry: {e}");
+                }
+            }
+            Ok(response) => warn!(
+                "Keep-alive failed with status {}: {:?}",
+                response.status, response.error
+            ),
+            Err(e) => warn!("Keep-alive request failed: {e}"),
+        }
+    }
+
+    /// Replace an expired session by repeating the last login.
+    ///
+    /// Callers that saw the same expired token share one login.
+    async fn relogin(&self, expired_token: &str) -> Result<()> {
+        let _guard = self.relogin_lock.lock().await;
+        if self.session.get().as_deref() != Some(expired_token) {
+            // Another caller already logged in again
+            return Ok(());
+        }
+
+        let method = self
+            .login_method
+            .read()
+            .ok()
+            .and_then(|method| method.clone())
+            .ok_or_else(|| anyhow::anyhow!("No login to repeat for an externally set session"))?;
+
+        match method {
+            LoginMethod::Certificate => {
+                self.certificate_login().await?;
+            }
+            LoginMethod::Interactive { username, password } => {
+                self.interactive_login(username, password).await?;
+            }
+        }
+
+        info!("Logged in again after session expiry");
+        Ok(())
+    }
+
+    async fn session_request(&self, url: &str) -> Result<SessionResponse> {
+        let session_token = self
+            .session
+            .get()
+            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
+
+        let mut headers = HeaderMap::with_capacity(3);
+        headers.insert("X-Application", self.config.betfair.api_key.parse()?);
+        headers.insert("X-Authentication", session_token.parse()?);
+        headers.insert("Accept", "application/json".parse()?);
+
+        let response = self
+            .client
+            .post(url)
+            .headers(headers)
+            .send()
+            .await
+            .map_err(BetfairError::Transport)?;
+        let status = response.status();
+        let body = response.text().await.map_err(BetfairError::Transport)?;
+        debug!("Session response from {url} ({status}): {body}");
+
+        if !status.is_success() {
+            return Err(BetfairError::Http {
+                status: status.as_u16(),
+                body,
+            }
+            .into());
+        }
+
+        serde_json::from_str(&body).map_err(|e| {
+            BetfairError::InvalidResponse(format!(
+                "Failed to deserialize session response: {e}\nResponse body: {body}"
+            ))
+            .into()
+        })
+    }
+
+    /// Generic method to make JSON-RPC API requests, retrying transient failures
     async fn make_json_rpc_request<T, U>(&self, url: &str, method: &str, params: T) -> Result<U>
+    where
+        T: Serialize + Clone,
+        U: DeserializeOwned,
+    {
+        self.call_json_rpc(url, method, params, true).await
+    }
+
+    /// Send a transaction once, so a retry can never execute it twice.
+    ///
+    /// If it fails without a clear outcome, the bets `lookup` selects are fetched and
+    /// returned in `BetfairError::Unconfirmed` with the refs the transaction was sent with.
+    async fn make_transaction_request<T, U>(
+        &self,
+        method: &str,
+        params: T,
+        customer_ref: String,
+        lookup: ListCurrentOrdersRequest,
+    ) -> Result<U>
+    where
+        T: Serialize + Clone,
+        U: DeserializeOwned,
+    {
+        let error = match self
+            .call_json_rpc(&self.endpoints.betting_url(), method, params, false)
+            .await
+        {
+            Ok(result) => return Ok(result),
+            Err(e) => e,
+        };
+
+        let error = match error.downcast::<BetfairError>() {
+            Ok(error) if error.is_ambiguous() => error,
+            Ok(error) => return Err(error.into()),
+            Err(error) => return Err(error),
+        };
+
+        warn!("{method} failed with an unknown outcome, checking current orders: {error}");
+        // Placements are looked up by the customer order refs they were sent with
+        let lookup_order_refs = lookup.customer_order_refs.clone().unwrap_or_default();
+        match self.list_current_orders(lookup).await {
+            Ok(response) => Err(BetfairError::Unconfirmed {
+                source: Box::new(error),
+                customer_ref,
+                customer_order_refs: lookup_order_refs,
+                current_orders: response.current_orders,
+            }
+            .into()),
+            Err(lookup_error) => {
+                error!("Failed to look up orders after {method} failed: {lookup_error}");
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Send a JSON-RPC request, retrying transient failures if `retry` is set.
+    ///
+    /// A request rejected for an expired session is sent once more after logging in again;
+    /// Betfair rejects it before executing anything, so this is safe for transactions too.
+    async fn call_json_rpc<T, U>(
+        &self,
+        url: &str,
+        method: &str,
+        params: T,
+        retry: bool,
+    ) -> Result<U>
     where
         T: Serialize + Clone,
         U: DeserializeOwned,
     {
         let session_token = self
-            .session_token
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?
-            .clone();
+            .session
+            .get()
+            .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
+
+        match self
+            .send_json_rpc_request(url, method, &session_token, params.clone(), retry)
+            .await
+        {
+            Err(e) if session::is_session_error(&e) => {
+                warn!("{method} was rejected, logging in again: {e}");
+                if let Err(relogin_error) = self.relogin(&session_token).await {
+                    error!("Failed to log in again: {relogin_error}");
+                    return Err(e);
+                }
 
+                let session_token = self
+                    .session
+                    .get()
+                    .ok_or_else(|| anyhow::anyhow!("Not logged in"))?;
+                self.send_json_rpc_request(url, method, &session_token, params, retry)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    async fn send_json_rpc_request<T, U>(
+        &self,
+        url: &str,
+        method: &str,
+        session_token: &str,
+        params: T,
+        retry: bool,
+    ) -> Result<U>
+    where
+        T: Serialize + Clone,
+        U: DeserializeOwned,
+    {
+        let session_token = session_token.to_string();
         let api_key = self.config.betfair.api_key.clone();
         let method_str = method.to_string();
         let url_str = url.to_string();
 
-        self.retry_policy
-            .retry(|| {
-                let session_token = session_token.clone();
-                let api_key = api_key.clone();
-                let method_str = method_str.clone();
-                let url_str = url_str.clone();
-                let params = params.clone();
-                let client = self.client.clone();
-
-                async move {
-                    let mut headers = HeaderMap::with_capacity(3);
-                    headers.insert("X-Application", api_key.parse()?);
-                    headers.insert("X-Authentication", session_token.parse()?);
-                    headers.insert("Content-Type", "application/json".parse()?);
-
-                    let jsonrpc_request = JsonRpcRequest {
-                        jsonrpc: "2.0".to_string(),
-                        method: method_str,
-                        params,
-                        id: 1,
-                    };
-
-                    debug!("API request: {}", serde_json::to_string(&jsonrpc_request)?);
+        let attempt = || {
+            let session_token = session_token.clone();
+            let api_key = api_key.clone();
+            let method_str = method_str.clone();
+            let url_str = url_str.clone();
+            let params = params.clone();
+            let client = self.client.clone();
+
+            async move {
+                let mut headers = HeaderMap::with_capacity(3);
+                headers.insert("X-Application", api_key.parse()?);
+                headers.insert("X-Authentication", session_token.parse()?);
+                headers.insert("Content-Type", "application/json".parse()?);
+
+                let jsonrpc_request = JsonRpcRequest {
+                    jsonrpc: "2.0".to_string(),
+                    method: method_str,
+                    params,
+                    id: 1,
+                };
+
+                debug!("API request: {}", serde_json::to_string(&jsonrpc_request)?);
+
+                let response = client
+                    .post(&url_str)
+                    .headers(headers)
+                    .json(&jsonrpc_request)
+                    .send()
+                    .await
+                    .map_err(BetfairError::Transport)?;
+
+                let status = response.status();
+                debug!("API response status: {}", status);
+
+                let response_text = response.text().await.map_err(BetfairError::Transport)?;
+                debug!("API response: {}", response_text);
+
+                Ok(json_rpc_result(status, &response_text)?)
+            }
+        };
 
-                    let response = client
-                        .post(&url_str)
-                        .headers(headers)
-                        .json(&jsonrpc_request)
-                        .send()
-                        .await?;
+        // Only transient failures are retried; a rejected session needs a new login first
+        if retry {
+            self.retry_policy.retry(attempt).await
+        } else {
+            attempt().await
+        }
+    }
 
-                    let status = response.status();
-                    debug!("API response status: {}", status);
+    // ========================================================================
+    // Heartbeat
+    // ========================================================================
 
-                    let response_text = response.text().await?;
-                    debug!("API response: {}", response_text);
+    /// Arm or refresh Betfair's dead man's switch; a zero timeout disarms it.
+    ///
+    /// If no heartbeat arrives within the actual timeout, all unmatched bets are cancelled.
+    pub async fn heartbeat(&self, preferred_timeout: Duration) -> Result<HeartbeatReport> {
+        let params = HeartbeatParams {
+            preferred_timeout_seconds: u32::try_from(preferred_timeout.as_secs())
+                .unwrap_or(u32::MAX),
+        };
+        self.rate_limiter.acquire_for_heartbeat().await?;
+        self.make_json_rpc_request(
+            &self.endpoints.heartbeat_url(),
+            "HeartbeatAPING/v1.0/heartbeat",
+            params,
+        )
+        .await
+    }
 
-                    if !status.is_success() {
-                        return Err(anyhow::anyhow!(
-                            "API request failed with status {status}: {response_text}"
-                        ));
+    /// Send heartbeats from a background task, well within the timeout Betfair reports.
+    ///
+    /// The task runs until the returned handle is stopped or dropped.
+    pub fn spawn_heartbeat(&self, preferred_timeout: Duration) -> HeartbeatHandle {
+        let client = self.clone();
+        let (reports, receiver) = watch::channel(HeartbeatStatus::default());
+        let task = tokio::spawn(async move {
+            let mut interval =
+                heartbeat::heartbeat_interval(preferred_timeout.as_secs().try_into().unwrap_or(0));
+            loop {
+                match client.heartbeat(preferred_timeout).await {
+                    Ok(report) => {
+                        if report.action_performed != ActionPerformed::None {
+                            warn!(
+                                "A heartbeat was missed, Betfair reported {:?}",
+                                report.action_performed
+                            );
+                        }
+                        interval = heartbeat::heartbeat_interval(report.actual_timeout_seconds);
+                        reports.send_replace(HeartbeatStatus {
+                            last_report: Some(report),
+                            last_error: None,
+                            consecutive_failures: 0,
+                        });
+                    }
+                    Err(e) => {
+                        warn!("Heartbeat failed: {e}");
+                        reports.send_modify(|status| {
+                            status.last_error = Some(e.to_string());
+                            status.consecutive_failures += 1;
+                        });
                     }
-
-                    let json_response: JsonRpcResponse<U> = serde_json::from_str(&response_text)?;
-                    json_response.result.ok_or_else(|| {
-                        anyhow::anyhow!("No result in response: {:?}", json_response.error)
-                    })
                 }
-            })
-            .await
+                tokio::time::sleep(interval).await;
+            }
+        });
+        HeartbeatHandle::new(task, receiver, self.clone())
     }
 
     // ========================================================================
     // Market Operations
     // ========================================================================
 
-    /// List market catalogue
+    /// List market catalogue.
+    ///
+    /// If the projections for `max_results` markets weigh more than Betfair allows, the
+    /// matching markets are listed first and their projections fetched in concurrent parts.
     pub async fn list_market_catalogue(
         &self,
         request: ListMarketCatalogueRequest,
+    ) -> Result<Vec<MarketCatalogue>> {
+        if !request_weight::is_market_catalogue_request_too_heavy(&request) {
+            return self.send_list_market_catalogue(request).await;
+        }
+
+        let market_ids: Vec<String> = self
+            .send_list_market_catalogue(ListMarketCatalogueRequest {
+                market_projection: None,
+                ..request.clone()
+            })
+            .await?
+            .into_iter()
+            .map(|catalogue| catalogue.market_id)
+            .collect();
+
+        let requests = request_weight::market_catalogue_requests(&request, &market_ids);
+        let mut catalogues = self
+            .join_requests(requests, |client, request| async move {
+                client.send_list_market_catalogue(request).await
+            })
+            .await?;
+
+        // Keep the order, e.g. the sort, of the first listing
+        let positions: HashMap<&str, usize> = market_ids
+            .iter()
+            .enumerate()
+            .map(|(position, market_id)| (market_id.as_str(), position))
+            .collect();
+        catalogues.sort_by_key(|catalogue| positions.get(catalogue.market_id.as_str()).copied());
+        Ok(catalogues)
+    }
+
+    async fn send_list_market_catalogue(
+        &self,
+        request: ListMarketCatalogueRequest,
     ) -> Result<Vec<MarketCatalogue>> {
         self.rate_limiter.acquire_for_navigation().await?;
-        self.make_json_rpc_request(BETTING_URL, "SportsAPING/v1.0/listMarketCatalogue", request)
-            .await
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listMarketCatalogue",
+            request,
+        )
+        .await
     }
 
-    /// List market book
+    /// List market book.
+    ///
+    /// Requests weighing more than Betfair allows are split by market and sent concurrently;
+    /// the books are returned in the order of `market_ids`.
     pub async fn list_market_book(
         &self,
         request: ListMarketBookRequest,
+    ) -> Result<Vec<MarketBook>> {
+        let requests = request_weight::split_market_book_request(request);
+        self.join_requests(requests, |client, request| async move {
+            client.send_list_market_book(request).await
+        })
+        .await
+    }
+
+    async fn send_list_market_book(
+        &self,
+        request: ListMarketBookRequest,
     ) -> Result<Vec<MarketBook>> {
         self.rate_limiter.acquire_for_navigation().await?;
-        self.make_json_rpc_request(BETTING_URL, "SportsAPING/v1.0/listMarketBook", request)
-            .await
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listMarketBook",
+            request,
+        )
+        .await
+    }
+
+    /// Send `requests` concurrently, each within the rate limits, and join the results in order
+    async fn join_requests<R, T, F, Fut>(&self, requests: Vec<R>, send: F) -> Result<Vec<T>>
+    where
+        F: Fn(RestClient, R) -> Fut,
+        Fut: Future<Output = Result<Vec<T>>> + Send + 'static,
+        T: Send + 'static,
+    {
+        if requests.len() == 1 {
+            let request = requests.into_iter().next().expect("one request");
+            return send(self.clone(), request).await;
+        }
+
+        let mut parts: Vec<Option<Vec<T>>> = requests.iter().map(|_| None).collect();
+        let mut tasks = JoinSet::new();
+        for (index, request) in requests.into_iter().enumerate() {
+            let sending = send(self.clone(), request);
+            tasks.spawn(async move { (index, sending.await) });
+        }
+
+        // Dropping the set on the first error aborts the remaining requests
+        while let Some(joined) = tasks.join_next().await {
+            let (index, result) =
+                joined.map_err(|e| anyhow::anyhow!("Request task failed: {e}"))?;
+            parts[index] = Some(result?);
+        }
+
+        Ok(parts.into_iter().flatten().flatten().collect())
     }
 
     // ========================================================================
     // Order Operations
     // ========================================================================
 
-    /// Place orders
-    pub async fn place_orders(&self, request: PlaceOrdersRequest) -> Result<PlaceOrdersResponse> {
+    /// Place orders.
+    ///
+    /// Missing `customer_ref`s and `customer_order_ref`s are filled in with unique values.
+    /// The request is never retried; if it fails without a clear outcome the orders are
+    /// looked up by their customer order refs. When all of them were placed their report is
+    /// returned, otherwise the error is `BetfairError::Unconfirmed`, which holds the refs
+    /// to resend the request with.
+    pub async fn place_orders(
+        &self,
+        mut request: PlaceOrdersRequest,
+    ) -> Result<PlaceOrdersResponse> {
+        assign_place_refs(&mut request);
+        let lookup = ListCurrentOrdersRequest {
+            market_ids: Some(vec![request.market_id.clone()]),
+            customer_order_refs: Some(
+                request
+                    .instructions
+                    .iter()
+                    .filter_map(|instruction| instruction.customer_order_ref.clone())
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+
         self.rate_limiter.acquire_for_transaction().await?;
-        self.make_json_rpc_request(BETTING_URL, "SportsAPING/v1.0/placeOrders", request)
-            .await
+        let customer_ref = request.customer_ref.clone().unwrap_or_default();
+        let result = self
+            .make_transaction_request(
+                "SportsAPING/v1.0/placeOrders",
+                request.clone(),
+                customer_ref,
+                lookup,
+            )
+            .await;
+
+        match result {
+            Err(e) => match e.downcast_ref::<BetfairError>() {
+                Some(BetfairError::Unconfirmed { current_orders, .. }) => {
+                    match recovered_place_report(&request, current_orders) {
+                        Some(response) => {
+                            info!(
+                                "Orders on {} were placed despite the error",
+                                request.market_id
+                            );
+                            Ok(response)
+                        }
+                        None => Err(e),
+                    }
+                }
+                _ => Err(e),
+            },
+            result => result,
+        }
     }
 
-    /// Cancel orders
+    /// Cancel orders; sent once with a unique `customer_ref`, like `place_orders`
     pub async fn cancel_orders(
         &self,
-        request: CancelOrdersRequest,
+        mut request: CancelOrdersRequest,
     ) -> Result<CancelOrdersResponse> {
+        let customer_ref = request
+            .customer_ref
+            .get_or_insert_with(transaction_ref)
+            .clone();
+        let lookup = transaction_lookup(
+            &request.market_id,
+            request.instructions.iter().map(|i| i.bet_id.clone()),
+        );
+
         self.rate_limiter.acquire_for_transaction().await?;
-        self.make_json_rpc_request(BETTING_URL, "SportsAPING/v1.0/cancelOrders", request)
-            .await
+        self.make_transaction_request(
+            "SportsAPING/v1.0/cancelOrders",
+            request,
+            customer_ref,
+            lookup,
+        )
+        .await
+    }
+
+    /// Replace orders with new prices; each replace is a cancel and a place in one transaction.
+    ///
+    /// Sent once with a unique `customer_ref`, like `place_orders`.
+    pub async fn replace_orders(
+        &self,
+        mut request: ReplaceOrdersRequest,
+    ) -> Result<ReplaceOrdersResponse> {
+        let customer_ref = request
+            .customer_ref
+            .get_or_insert_with(transaction_ref)
+            .clone();
+        let lookup = transaction_lookup(
+            &request.market_id,
+            request.instructions.iter().map(|i| i.bet_id.clone()),
+        );
+
+        self.rate_limiter.acquire_for_transaction().await?;
+        self.make_transaction_request(
+            "SportsAPING/v1.0/replaceOrders",
+            request,
+            customer_ref,
+            lookup,
+        )
+        .await
+    }
+
+    /// Update the persistence type of orders; sent once with a unique `customer_ref`
+    pub async fn update_orders(
+        &self,
+        mut request: UpdateOrdersRequest,
+    ) -> Result<UpdateOrdersResponse> {
+        let customer_ref = request
+            .customer_ref
+            .get_or_insert_with(transaction_ref)
+            .clone();
+        let lookup = transaction_lookup(
+            &request.market_id,
+            request.instructions.iter().map(|i| i.bet_id.clone()),
+        );
+
+        self.rate_limiter.acquire_for_transaction().await?;
+        self.make_transaction_request(
+            "SportsAPING/v1.0/updateOrders",
+            request,
+            customer_ref,
+            lookup,
+        )
+        .await
     }
 
     /// List current orders
@@ -353,8 +1023,12 @@ impl RestClient {
         request: ListCurrentOrdersRequest,
     ) -> Result<ListCurrentOrdersResponse> {
         self.rate_limiter.acquire_for_data().await?;
-        self.make_json_rpc_request(BETTING_URL, "SportsAPING/v1.0/listCurrentOrders", request)
-            .await
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listCurrentOrders",
+            request,
+        )
+        .await
     }
 
     /// List cleared orders
@@ -363,8 +1037,26 @@ impl RestClient {
         request: ListClearedOrdersRequest,
     ) -> Result<ListClearedOrdersResponse> {
         self.rate_limiter.acquire_for_data().await?;
-        self.make_json_rpc_request(BETTING_URL, "SportsAPING/v1.0/listClearedOrders", request)
-            .await
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listClearedOrders",
+            request,
+        )
+        .await
+    }
+
+    /// List profit and loss per runner for markets with matched bets
+    pub async fn list_market_profit_and_loss(
+        &self,
+        request: ListMarketProfitAndLossRequest,
+    ) -> Result<Vec<MarketProfitAndLoss>> {
+        self.rate_limiter.acquire_for_data().await?;
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listMarketProfitAndLoss",
+            request,
+        )
+        .await
     }
 
     // ========================================================================
@@ -377,33 +1069,66 @@ impl RestClient {
         request: GetAccountFundsRequest,
     ) -> Result<GetAccountFundsResponse> {
         self.rate_limiter.acquire_for_data().await?;
-        self.make_json_rpc_request(ACCOUNT_URL, "AccountAPING/v1.0/getAccountFunds", request)
-            .await
+        self.make_json_rpc_request(
+            &self.endpoints.account_url(),
+            "AccountAPING/v1.0/getAccountFunds",
+            request,
+        )
+        .await
     }
 
     /// Get account details
     pub async fn get_account_details(&self) -> Result<GetAccountDetailsResponse> {
         self.rate_limiter.acquire_for_data().await?;
         self.make_json_rpc_request(
-            ACCOUNT_URL,
+            &self.endpoints.account_url(),
             "AccountAPING/v1.0/getAccountDetails",
             GetAccountDetailsRequest {},
         )
         .await
     }
 
+    /// Get one page of the account statement
+    pub async fn get_account_statement(
+        &self,
+        request: GetAccountStatementRequest,
+    ) -> Result<AccountStatementReport> {
+        self.rate_limiter.acquire_for_data().await?;
+        self.make_json_rpc_request(
+            &self.endpoints.account_url(),
+            "AccountAPING/v1.0/getAccountStatement",
+            request,
+        )
+        .await
+    }
+
+    /// Page through the whole account statement matching `request`, starting at its
+    /// `from_record`.
+    ///
+    /// Every page is a separate rate-limited account request.
+    pub fn account_statement(
+        &self,
+        request: GetAccountStatementRequest,
+    ) -> AccountStatementPager<'_> {
+        AccountStatementPager::new(self, request)
+    }
+
     /// Transfer funds between wallets
     pub async fn transfer_funds(
         &self,
         request: TransferFundsRequest,
     ) -> Result<TransferFundsResponse> {
         self.rate_limiter.acquire_for_transaction().await?;
-        self.make_json_rpc_request(ACCOUNT_URL, "AccountAPING/v1.0/transferFunds", request)
-            .await
+        self.make_json_rpc_request(
+            &self.endpoints.account_url(),
+            "AccountAPING/v1.0/transferFunds",
+            request,
+        )
+        .await
     }
 
     /// List currency exchange rates
-    /// 
+    ///
     /// Returns a list of currency rates based on given currency.
     /// Currently only GBP is supported as the from_currency parameter.
     pub async fn list_currency_rates(
@@ -411,8 +1136,12 @@ impl RestClient {
         request: ListCurrencyRatesRequest,
     ) -> Result<Vec<CurrencyRate>> {
         self.rate_limiter.acquire_for_data().await?;
-        self.make_json_rpc_request(ACCOUNT_URL, "AccountAPING/v1.0/listCurrencyRates", request)
-            .await
+        self.make_json_rpc_request(
+            &self.endpoints.account_url(),
+            "AccountAPING/v1.0/listCurrencyRates",
+            request,
+        )
+        .await
     }
 
     // ========================================================================
@@ -623,7 +1352,7 @@ impl RestClient {
     pub async fn list_sports(&self, filter: Option<MarketFilter>) -> Result<Vec<EventTypeResult>> {
         self.rate_limiter.acquire_for_navigation().await?;
         self.make_json_rpc_request(
-            BETTING_URL,
+            &self.endpoints.betting_url(),
             "SportsAPING/v1.0/listEventTypes",
             ListEventTypesRequest {
                 filter: filter.unwrap_or_default(),
@@ -662,7 +1391,7 @@ impl RestClient {
     pub async fn list_events(&self, filter: Option<MarketFilter>) -> Result<Vec<EventResult>> {
         self.rate_limiter.acquire_for_navigation().await?;
         self.make_json_rpc_request(
-            BETTING_URL,
+            &self.endpoints.betting_url(),
             "SportsAPING/v1.0/listEvents",
             ListEventsRequest {
                 filter: filter.unwrap_or_default(),
@@ -704,7 +1433,7 @@ impl RestClient {
     ) -> Result<Vec<CompetitionResult>> {
         self.rate_limiter.acquire_for_navigation().await?;
         self.make_json_rpc_request(
-            BETTING_URL,
+            &self.endpoints.betting_url(),
             "SportsAPING/v1.0/listCompetitions",
             ListCompetitionsRequest {
                 filter: filter.unwrap_or_default(),
@@ -714,6 +1443,88 @@ impl RestClient {
         .await
     }
 
+    /// List countries with markets matching the filter
+    ///
+    /// # Arguments
+    /// * `filter` - Optional market filter. If None, returns all countries.
+    pub async fn list_countries(
+        &self,
+        filter: Option<MarketFilter>,
+    ) -> Result<Vec<CountryCodeResult>> {
+        self.rate_limiter.acquire_for_navigation().await?;
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listCountries",
+            ListCountriesRequest {
+                filter: filter.unwrap_or_default(),
+                locale: Some("en".to_string()),
+            },
+        )
+        .await
+    }
+
+    /// List venues with markets matching the filter
+    ///
+    /// Betfair only reports venues for horse and greyhound racing.
+    ///
+    /// # Arguments
+    /// * `filter` - Optional market filter, usually restricting `event_type_ids`
+    ///   and `market_countries`.
+    pub async fn list_venues(&self, filter: Option<MarketFilter>) -> Result<Vec<VenueResult>> {
+        self.rate_limiter.acquire_for_navigation().await?;
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listVenues",
+            ListVenuesRequest {
+                filter: filter.unwrap_or_default(),
+                locale: Some("en".to_string()),
+            },
+        )
+        .await
+    }
+
+    /// List market types (e.g. `WIN`, `MATCH_ODDS`) with markets matching the filter
+    ///
+    /// # Arguments
+    /// * `filter` - Optional market filter. If None, returns all market types.
+    pub async fn list_market_types(
+        &self,
+        filter: Option<MarketFilter>,
+    ) -> Result<Vec<MarketTypeResult>> {
+        self.rate_limiter.acquire_for_navigation().await?;
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listMarketTypes",
+            ListMarketTypesRequest {
+                filter: filter.unwrap_or_default(),
+                locale: Some("en".to_string()),
+            },
+        )
+        .await
+    }
+
+    /// List time ranges containing markets matching the filter
+    ///
+    /// # Arguments
+    /// * `filter` - Optional market filter. If None, covers all markets.
+    /// * `granularity` - Width of each returned time range
+    pub async fn list_time_ranges(
+        &self,
+        filter: Option<MarketFilter>,
+        granularity: TimeGranularity,
+    ) -> Result<Vec<TimeRangeResult>> {
+        self.rate_limiter.acquire_for_navigation().await?;
+        self.make_json_rpc_request(
+            &self.endpoints.betting_url(),
+            "SportsAPING/v1.0/listTimeRanges",
+            ListTimeRangesRequest {
+                filter: filter.unwrap_or_default(),
+                granularity,
+            },
+        )
+        .await
+    }
+
     /// List runners for a specific market
     ///
     /// # Arguments
@@ -757,6 +1568,9 @@ mod tests {
                 password: "test_pass".to_string(),
                 api_key: "test_key".to_string(),
                 pem_path: "/tmp/test.pem".to_string(),
+                stream_url: None,
+                jurisdiction: Default::default(),
+                endpoints: None,
             },
         }
     }
@@ -766,7 +1580,7 @@ mod tests {
         let config = create_test_config();
         let client = RestClient::new(config);
 
-        assert!(client.session_token.is_none());
+        assert!(client.session.get().is_none());
         assert!(client.get_session_token().is_none());
     }
 
@@ -781,12 +1595,118 @@ mod tests {
         assert_eq!(client.get_session_token(), Some(token));
     }
 
+    #[test]
+    fn test_json_rpc_result_maps_api_exceptions() {
+        let ok = reqwest::StatusCode::OK;
+
+        let betting = r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"ANGX-0003","data":{"APINGException":{"requestUUID":"ie2-ang29b-prd-1","errorCode":"INVALID_SESSION_INFORMATION","errorDetails":""},"exceptionname":"APINGException"}},"id":1}"#;
+        let err = json_rpc_result::<Vec<MarketBook>>(ok, betting).unwrap_err();
+        assert!(err.is_session_error());
+        assert!(!err.is_transient());
+        match err {
+            BetfairError::Api {
+                code,
+                request_uuid,
+                details,
+            } => {
+                assert_eq!(code, ApiErrorCode::InvalidSessionInformation);
+                assert_eq!(request_uuid.as_deref(), Some("ie2-ang29b-prd-1"));
+                assert_eq!(details, None);
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        let account = r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"AANGX-0010","data":{"AccountAPINGException":{"requestUUID":"ie2-ang29b-prd-2","errorCode":"NO_SESSION","errorDetails":""},"exceptionname":"AccountAPINGException"}},"id":1}"#;
+        let err = json_rpc_result::<AccountFundsResponse>(ok, account).unwrap_err();
+        assert!(err.is_session_error());
+
+        let too_much_data = r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"ANGX-0001","data":{"APINGException":{"errorCode":"TOO_MUCH_DATA","errorDetails":"weight 400"},"exceptionname":"APINGException"}},"id":1}"#;
+        let err =
+            json_rpc_result::<Vec<MarketBook>>(reqwest::StatusCode::BAD_REQUEST, too_much_data)
+                .unwrap_err();
+        assert_eq!(err.api_error_code(), Some(&ApiErrorCode::TooMuchData));
+        assert!(!err.is_session_error());
+        assert!(!err.is_transient());
+        assert_eq!(err.to_string(), "API error TOO_MUCH_DATA: weight 400");
+
+        let busy = r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"ANGX-0008","data":{"APINGException":{"errorCode":"TOO_MANY_REQUESTS"},"exceptionname":"APINGException"}},"id":1}"#;
+        assert!(json_rpc_result::<Vec<MarketBook>>(ok, busy)
+            .unwrap_err()
+            .is_transient());
+
+        let unknown_method =
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}"#;
+        assert!(matches!(
+            json_rpc_result::<Vec<MarketBook>>(ok, unknown_method).unwrap_err(),
+            BetfairError::Rpc { code: -32601, .. }
+        ));
+
+        let books: Vec<MarketBook> =
+            json_rpc_result(ok, r#"{"jsonrpc":"2.0","result":[],"id":1}"#).unwrap();
+        assert!(books.is_empty());
+    }
+
+    #[test]
+    fn test_json_rpc_result_without_json_rpc_body() {
+        let err = json_rpc_result::<Vec<MarketBook>>(
+            reqwest::StatusCode::BAD_GATEWAY,
+            "<html>Bad Gateway</html>",
+        )
+        .unwrap_err();
+        assert!(matches!(err, BetfairError::Http { status: 502, .. }));
+        assert!(err.is_transient());
+
+        let err = json_rpc_result::<Vec<MarketBook>>(
+            reqwest::StatusCode::FORBIDDEN,
+            "<html>Forbidden</html>",
+        )
+        .unwrap_err();
+        assert!(!err.is_transient());
+
+        let err =
+            json_rpc_result::<Vec<MarketBook>>(reqwest::StatusCode::OK, "not json").unwrap_err();
+        assert!(matches!(err, BetfairError::InvalidResponse(_)));
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_session_and_relogin_needs_a_login() {
+        let mut client = RestClient::new(create_test_config());
+        let clone = client.clone();
+
+        client.set_session_token("expired".to_string());
+        assert_eq!(clone.get_session_token().as_deref(), Some("expired"));
+
+        // A session set from outside has no credentials to log in again with
+        assert!(clone.relogin("expired").await.is_err());
+
+        // Another caller already replaced the expired token
+        client.set_session_token("fresh".to_string());
+        assert!(clone.relogin("expired").await.is_ok());
+    }
+
+    #[test]
+    fn test_session_response() {
+        let ok: SessionResponse = serde_json::from_str(
+            r#"{"token":"abc","product":"app_key","status":"SUCCESS","error":""}"#,
+        )
+        .unwrap();
+        assert!(ok.is_success());
+        assert!(!is_session_expired(&ok));
+
+        let expired: SessionResponse = serde_json::from_str(
+            r#"{"token":"","product":"app_key","status":"FAIL","error":"NO_SESSION"}"#,
+        )
+        .unwrap();
+        assert!(!expired.is_success());
+        assert!(is_session_expired(&expired));
+    }
+
     #[test]
     fn test_client_has_session_token_field() {
         let config = create_test_config();
         let client = RestClient::new(config);
 
-        assert!(client.session_token.is_none());
+        assert!(client.session.get().is_none());
     }
 
     #[test]
@@ -1047,6 +1967,110 @@ mod tests {
         );
     }
 
+    fn limit_instruction(selection_id: i64, customer_order_ref: Option<&str>) -> PlaceInstruction {
+        PlaceInstruction {
+            order_type: OrderType::Limit,
+            selection_id,
+            handicap: None,
+            side: Side::Lay,
+            limit_order: Some(LimitOrder {
+                size: dec!(2),
+                price: dec!(3.5),
+                persistence_type: PersistenceType::Lapse,
+                time_in_force: None,
+                min_fill_size: None,
+                bet_target_type: None,
+                bet_target_size: None,
+            }),
+            limit_on_close_order: None,
+            market_on_close_order: None,
+            customer_order_ref: customer_order_ref.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_place_orders_get_unique_refs() {
+        let mut request = PlaceOrdersRequest {
+            market_id: "1.1".to_string(),
+            instructions: vec![
+                limit_instruction(1, Some("mine")),
+                limit_instruction(2, None),
+            ],
+            customer_ref: None,
+            market_version: None,
+            customer_strategy_ref: None,
+            async_: None,
+        };
+        assign_place_refs(&mut request);
+
+        let customer_ref = request.customer_ref.clone().unwrap();
+        assert_eq!(customer_ref.len(), 32);
+        assert_eq!(
+            request.instructions[0].customer_order_ref.as_deref(),
+            Some("mine")
+        );
+        let generated = request.instructions[1].customer_order_ref.clone().unwrap();
+        assert_eq!(generated.len(), 32);
+        assert_ne!(generated, customer_ref);
+
+        // Refs that are already set are kept, so a resent request is recognised
+        assign_place_refs(&mut request);
+        assert_eq!(request.customer_ref.as_deref(), Some(customer_ref.as_str()));
+        assert_eq!(
+            request.instructions[1].customer_order_ref.as_deref(),
+            Some(generated.as_str())
+        );
+    }
+
+    #[test]
+    fn test_transaction_lookup() {
+        let lookup = transaction_lookup("1.1", ["b1".to_string(), "b2".to_string()].into_iter());
+        assert_eq!(
+            lookup.bet_ids,
+            Some(vec!["b1".to_string(), "b2".to_string()])
+        );
+        assert_eq!(lookup.market_ids, Some(vec!["1.1".to_string()]));
+
+        // Cancelling without instructions cancels every bet on the market
+        let lookup = transaction_lookup("1.1", std::iter::empty());
+        assert_eq!(lookup.bet_ids, None);
+        assert_eq!(lookup.market_ids, Some(vec!["1.1".to_string()]));
+    }
+
+    #[test]
+    fn test_recovered_place_report() {
+        let request = PlaceOrdersRequest {
+            market_id: "1.1".to_string(),
+            instructions: vec![
+                limit_instruction(1, Some("ref1")),
+                limit_instruction(2, Some("ref2")),
+            ],
+            customer_ref: Some("batch".to_string()),
+            market_version: None,
+            customer_strategy_ref: None,
+            async_: None,
+        };
+        let current_orders: ListCurrentOrdersResponse = serde_json::from_str(
+            r#"{"currentOrders":[
+                {"betId":"b1","marketId":"1.1","selectionId":1,"priceSize":{"price":3.5,"size":2},"side":"LAY","status":"EXECUTABLE","persistenceType":"LAPSE","orderType":"LIMIT","placedDate":"2025-10-05T00:58:23.000Z","averagePriceMatched":0,"sizeMatched":0,"handicap":0,"bspLiability":0,"sizeRemaining":2,"sizeLapsed":0,"sizeCancelled":0,"sizeVoided":0,"customerOrderRef":"ref1"},
+                {"betId":"b2","marketId":"1.1","selectionId":2,"priceSize":{"price":3.5,"size":2},"side":"LAY","status":"EXECUTION_COMPLETE","persistenceType":"LAPSE","orderType":"LIMIT","averagePriceMatched":3.5,"sizeMatched":2,"handicap":0,"bspLiability":0,"sizeRemaining":0,"sizeLapsed":0,"sizeCancelled":0,"sizeVoided":0,"customerOrderRef":"ref2"}
+            ],"moreAvailable":false}"#,
+        )
+        .unwrap();
+
+        let response = recovered_place_report(&request, &current_orders.current_orders).unwrap();
+        assert_eq!(response.status, "SUCCESS");
+        assert_eq!(response.customer_ref.as_deref(), Some("batch"));
+        let reports = response.instruction_reports.unwrap();
+        assert_eq!(reports[0].bet_id.as_deref(), Some("b1"));
+        assert_eq!(reports[1].bet_id.as_deref(), Some("b2"));
+        assert_eq!(reports[1].size_matched, Some(dec!(2)));
+        assert_eq!(reports[1].instruction.selection_id, 2);
+
+        // Without every order the outcome is still unknown
+        assert!(recovered_place_report(&request, &current_orders.current_orders[..1]).is_none());
+    }
+
     #[test]
     fn test_cancel_orders_request_builder() {
         let request = CancelOrdersRequest {
@@ -1063,6 +2087,37 @@ mod tests {
         assert_eq!(request.instructions[0].size_reduction.unwrap(), dec!(5.0));
     }
 
+    #[test]
+    fn test_replace_and_update_orders_requests() {
+        let request = ReplaceOrdersRequest {
+            market_id: "1.123456".to_string(),
+            instructions: vec![ReplaceInstruction {
+                bet_id: "12345".to_string(),
+                new_price: dec!(2.5),
+            }],
+            customer_ref: None,
+            market_version: None,
+            async_: Some(false),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["instructions"][0]["betId"], "12345");
+        assert_eq!(json["instructions"][0]["newPrice"], 2.5);
+        assert_eq!(json["async"], false);
+        assert!(json.get("customerRef").is_none());
+
+        let request = UpdateOrdersRequest {
+            market_id: "1.123456".to_string(),
+            instructions: vec![UpdateInstruction {
+                bet_id: "12345".to_string(),
+                new_persistence_type: PersistenceType::Persist,
+            }],
+            customer_ref: Some("ref".to_string()),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["instructions"][0]["newPersistenceType"], "PERSIST");
+        assert_eq!(json["customerRef"], "ref");
+    }
+
     #[test]
     fn test_list_market_catalogue_request() {
         let request = ListMarketCatalogueRequest {
@@ -1127,6 +2182,22 @@ mod tests {
         assert_eq!(request.record_count, Some(100));
     }
 
+    #[test]
+    fn test_list_market_profit_and_loss_request() {
+        let request = ListMarketProfitAndLossRequest {
+            market_ids: vec!["1.123456".to_string()],
+            include_settled_bets: Some(true),
+            net_of_commission: Some(true),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["marketIds"][0], "1.123456");
+        assert_eq!(json["includeSettledBets"], true);
+        assert_eq!(json["netOfCommission"], true);
+        assert!(json.get("includeBspBets").is_none());
+    }
+
     #[test]
     fn test_get_account_funds_request() {
         let request = GetAccountFundsRequest {
@@ -1136,6 +2207,27 @@ mod tests {
         assert!(request.wallet.is_some());
     }
 
+    #[test]
+    fn test_get_account_statement_request() {
+        let request = GetAccountStatementRequest {
+            record_count: Some(100),
+            item_date_range: Some(TimeRange {
+                from: Some("2025-10-01T00:00:00Z".to_string()),
+                to: None,
+            }),
+            include_item: Some(IncludeItem::DepositsWithdrawals),
+            wallet: Some(Wallet::Uk),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["recordCount"], 100);
+        assert_eq!(json["itemDateRange"]["from"], "2025-10-01T00:00:00Z");
+        assert_eq!(json["includeItem"], "DEPOSITS_WITHDRAWALS");
+        assert_eq!(json["wallet"], "UK");
+        assert!(json.get("fromRecord").is_none());
+    }
+
     #[test]
     fn test_list_currency_rates_request() {
         let request = ListCurrencyRatesRequest {
@@ -51,6 +51,7 @@ mod dashboard {
 // Streaming module for console output
 mod streaming {
     use anyhow::Result;
+    use betfair_rs::dto::RunnerKey;
     use betfair_rs::orderbook::{Orderbook, PriceLevel};
     use betfair_rs::{BetfairClient, Config, StreamingClient};
     use rust_decimal::Decimal;
@@ -117,7 +118,7 @@ mod streaming {
         }
     }
 
-    fn print_market_summary(market_id: &str, market_books: &HashMap<String, Orderbook>) {
+    fn print_market_summary(market_id: &str, market_books: &HashMap<RunnerKey, Orderbook>) {
         println!("\n{}", "=".repeat(80));
         println!(
             "Market ID: {} | Time: {} | Selections: {}",
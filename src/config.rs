@@ -1,3 +1,5 @@
+pub use crate::dto::config::{Endpoints, Jurisdiction};
+use crate::stream_transport::StreamEndpoint;
 use anyhow::Result;
 use serde::Deserialize;
 use std::fs;
@@ -10,6 +12,17 @@ pub struct BetfairConfig {
     pub password: String,
     pub api_key: String,
     pub pem_path: String,
+    /// Stream endpoint as `host[:port]`, optionally `tls://` or `tcp://` prefixed.
+    ///
+    /// Overrides the stream endpoint of `jurisdiction` or `endpoints`.
+    #[serde(default)]
+    pub stream_url: Option<String>,
+    /// Exchange the account is registered with
+    #[serde(default)]
+    pub jurisdiction: Jurisdiction,
+    /// Custom base URLs, e.g. of a local mock server; replaces those of `jurisdiction`
+    #[serde(default)]
+    pub endpoints: Option<Endpoints>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -24,4 +37,20 @@ impl Config {
         info!("Config: {:?}", config);
         Ok(config)
     }
+
+    /// The custom endpoints if configured, otherwise those of the jurisdiction
+    pub fn endpoints(&self) -> Endpoints {
+        self.betfair
+            .endpoints
+            .clone()
+            .unwrap_or_else(|| Endpoints::for_jurisdiction(self.betfair.jurisdiction))
+    }
+
+    /// `stream_url` if set, otherwise the stream endpoint of `endpoints()`
+    pub fn stream_endpoint(&self) -> Result<StreamEndpoint> {
+        match &self.betfair.stream_url {
+            Some(url) => StreamEndpoint::parse(url),
+            None => StreamEndpoint::parse(&self.endpoints().stream_url),
+        }
+    }
 }
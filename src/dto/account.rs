@@ -1,6 +1,7 @@
-use super::common::Wallet;
+use super::common::{TimeRange, Wallet};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // Legacy response format for account funds
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -113,3 +114,126 @@ pub struct CurrencyRate {
     #[serde(with = "super::decimal_serde")]
     pub rate: Decimal,
 }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IncludeItem {
+    All,
+    DepositsWithdrawals,
+    Exchange,
+    PokerRoom,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetAccountStatementRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_record: Option<i32>,
+    /// Items per page, at most 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record_count: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_date_range: Option<TimeRange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_item: Option<IncludeItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<Wallet>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStatementReport {
+    #[serde(default)]
+    pub account_statement: Vec<StatementItem>,
+    pub more_available: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ItemClass {
+    Exchange,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementItem {
+    pub ref_id: String,
+    pub item_date: String,
+    #[serde(with = "super::decimal_serde")]
+    pub amount: Decimal,
+    #[serde(with = "super::decimal_serde")]
+    pub balance: Decimal,
+    pub item_class: ItemClass,
+    /// Item details keyed by class, e.g. `unknownStatementItem` holding a JSON string
+    #[serde(default)]
+    pub item_class_data: HashMap<String, String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub legacy_data: Option<StatementLegacyData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementLegacyData {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "super::decimal_serde::option")]
+    pub avg_price: Option<Decimal>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "super::decimal_serde::option")]
+    pub bet_size: Option<Decimal>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bet_type: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bet_category_type: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commission_rate: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<i64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_type_id: Option<i64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_market_name: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "super::decimal_serde::option")]
+    pub gross_bet_amount: Option<Decimal>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_name: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_type: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placed_date: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_id: Option<i64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_name: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_type: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<i64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub win_lose: Option<String>,
+}
@@ -50,6 +50,8 @@ pub enum MarketStatus {
     Open,
     Suspended,
     Closed,
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -63,6 +65,8 @@ pub enum RunnerStatus {
     RemovedVacant,
     Hidden,
     Placed,
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,7 +143,7 @@ pub enum InstructionReportStatus {
     Timeout,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum InstructionReportErrorCode {
     #[serde(rename = "INVALID_BET_SIZE")]
@@ -182,6 +186,16 @@ pub enum InstructionReportErrorCode {
     RelatedActionFailed,
     #[serde(rename = "NO_ACTION_REQUIRED")]
     NoActionRequired,
+    #[serde(rename = "INVALID_MARKET_VERSION")]
+    InvalidMarketVersion,
+    #[serde(rename = "INVALID_CUSTOMER_ORDER_REF")]
+    InvalidCustomerOrderRef,
+    #[serde(rename = "INVALID_MIN_FILL_SIZE")]
+    InvalidMinFillSize,
+    #[serde(rename = "DUPLICATE_BETIDS")]
+    DuplicateBetIds,
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 // Note: These are DTO versions of config structures
-// The actual config implementation is in src/config.rs
+// The actual config implementation is in src/config.rs, which re-exports
+// `Jurisdiction` and `Endpoints` from here
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BetfairCredentialsDto {
@@ -18,9 +19,11 @@ pub struct BetfairConfigDto {
     pub api_key: String,
     pub pem_path: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub api_url: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub stream_url: Option<String>,
+    #[serde(default)]
+    pub jurisdiction: Jurisdiction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoints: Option<Endpoints>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_secs: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -31,3 +34,102 @@ pub struct BetfairConfigDto {
 pub struct ConfigDto {
     pub betfair: BetfairConfigDto,
 }
+
+/// Betfair exchanges with their own API endpoints
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Jurisdiction {
+    /// betfair.com, including UK and Irish accounts
+    #[default]
+    Global,
+    /// Australian accounts and the Australian wallet
+    #[serde(alias = "au")]
+    Australia,
+    #[serde(alias = "it")]
+    Italy,
+    #[serde(alias = "es")]
+    Spain,
+    #[serde(alias = "ro")]
+    Romania,
+    #[serde(alias = "se")]
+    Sweden,
+}
+
+/// Base URLs of the Betfair services, without trailing slashes
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    /// Interactive login, keep-alive and logout, e.g. `https://identitysso.betfair.com`
+    pub identity_url: String,
+    /// Certificate login, e.g. `https://identitysso-cert.betfair.com`
+    pub cert_identity_url: String,
+    /// JSON-RPC APIs, e.g. `https://api.betfair.com/exchange`
+    pub exchange_url: String,
+    /// Stream API in the format of `BetfairConfig::stream_url`
+    pub stream_url: String,
+}
+
+impl Endpoints {
+    /// The production endpoints of an exchange
+    pub fn for_jurisdiction(jurisdiction: Jurisdiction) -> Self {
+        let (identity_domain, exchange_domain) = match jurisdiction {
+            Jurisdiction::Global => ("betfair.com", "betfair.com"),
+            Jurisdiction::Australia => ("betfair.com.au", "betfair.com"),
+            Jurisdiction::Italy => ("betfair.it", "betfair.it"),
+            Jurisdiction::Spain => ("betfair.es", "betfair.es"),
+            Jurisdiction::Romania => ("betfair.ro", "betfair.com"),
+            Jurisdiction::Sweden => ("betfair.se", "betfair.com"),
+        };
+
+        Self {
+            identity_url: format!("https://identitysso.{identity_domain}"),
+            cert_identity_url: format!("https://identitysso-cert.{identity_domain}"),
+            exchange_url: format!("https://api.{exchange_domain}/exchange"),
+            stream_url: format!("stream-api.{exchange_domain}:443"),
+        }
+    }
+
+    /// Every service at one base URL, as served by a mock server
+    pub fn local(base_url: &str, stream_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        Self {
+            identity_url: base_url.to_string(),
+            cert_identity_url: base_url.to_string(),
+            exchange_url: base_url.to_string(),
+            stream_url: stream_url.to_string(),
+        }
+    }
+
+    pub fn cert_login_url(&self) -> String {
+        format!("{}/api/certlogin", self.cert_identity_url)
+    }
+
+    pub fn login_url(&self) -> String {
+        format!("{}/api/login", self.identity_url)
+    }
+
+    pub fn keep_alive_url(&self) -> String {
+        format!("{}/api/keepAlive", self.identity_url)
+    }
+
+    pub fn logout_url(&self) -> String {
+        format!("{}/api/logout", self.identity_url)
+    }
+
+    pub fn betting_url(&self) -> String {
+        format!("{}/betting/json-rpc/v1", self.exchange_url)
+    }
+
+    pub fn account_url(&self) -> String {
+        format!("{}/account/json-rpc/v1", self.exchange_url)
+    }
+
+    pub fn heartbeat_url(&self) -> String {
+        format!("{}/heartbeat/json-rpc/v1", self.exchange_url)
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self::for_jurisdiction(Jurisdiction::Global)
+    }
+}
@@ -46,6 +46,78 @@ pub mod option {
     }
 }
 
+/// Optional decimal that may also arrive as a string such as `"NaN"` or `"Infinity"`
+/// (e.g. projected starting prices); non-numeric values deserialize to `None`.
+pub mod option_lenient {
+    use rust_decimal::Decimal;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S>(value: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        super::option::serialize(value, serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let opt = Option::<serde_json::Value>::deserialize(deserializer)?;
+        Ok(match opt {
+            Some(serde_json::Value::Number(num)) => Decimal::from_str(&num.to_string()).ok(),
+            Some(serde_json::Value::String(s)) => Decimal::from_str(&s).ok(),
+            _ => None,
+        })
+    }
+}
+
+pub mod option_vec_array2 {
+    use rust_decimal::Decimal;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S>(value: &Option<Vec<[Decimal; 2]>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(v) => {
+                let f64_vec: Vec<[f64; 2]> = v
+                    .iter()
+                    .map(|[a, b]| {
+                        [
+                            a.to_string().parse::<f64>().unwrap_or(0.0),
+                            b.to_string().parse::<f64>().unwrap_or(0.0),
+                        ]
+                    })
+                    .collect();
+                serializer.serialize_some(&f64_vec)
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<[Decimal; 2]>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let opt = Option::<Vec<[serde_json::Number; 2]>>::deserialize(deserializer)?;
+        opt.map(|vec| {
+            vec.into_iter()
+                .map(|[a, b]| {
+                    Ok([
+                        Decimal::from_str(&a.to_string()).map_err(serde::de::Error::custom)?,
+                        Decimal::from_str(&b.to_string()).map_err(serde::de::Error::custom)?,
+                    ])
+                })
+                .collect()
+        })
+        .transpose()
+    }
+}
+
 pub mod vec_array3 {
     use rust_decimal::Decimal;
     use serde::{Deserialize, Deserializer, Serializer};
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// Parameters of `HeartbeatAPING/v1.0/heartbeat`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatParams {
+    /// Seconds without a heartbeat before unmatched bets are cancelled; 0 disarms
+    pub preferred_timeout_seconds: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ActionPerformed {
+    None,
+    CancellationRequestSubmitted,
+    AllBetsCancelled,
+    SomeBetsNotCancelled,
+    CancellationRequestError,
+    CancellationStatusUnknown,
+}
+
+/// Response to a heartbeat; `action_performed` says what happened if the previous one timed out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartbeatReport {
+    pub action_performed: ActionPerformed,
+    /// The timeout Betfair applies, the preferred one clamped to its limits
+    pub actual_timeout_seconds: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_serialization() {
+        let params = HeartbeatParams {
+            preferred_timeout_seconds: 30,
+        };
+        assert_eq!(
+            serde_json::to_string(&params).unwrap(),
+            r#"{"preferredTimeoutSeconds":30}"#
+        );
+
+        let report: HeartbeatReport = serde_json::from_str(
+            r#"{"actionPerformed":"ALL_BETS_CANCELLED","actualTimeoutSeconds":10}"#,
+        )
+        .unwrap();
+        assert_eq!(report.action_performed, ActionPerformed::AllBetsCancelled);
+        assert_eq!(report.actual_timeout_seconds, 10);
+    }
+}
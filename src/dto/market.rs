@@ -472,10 +472,114 @@ pub struct CompetitionResult {
     pub competition_region: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListCountriesRequest {
+    pub filter: MarketFilter,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountryCodeResult {
+    pub country_code: String,
+    pub market_count: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListVenuesRequest {
+    pub filter: MarketFilter,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VenueResult {
+    pub venue: String,
+    pub market_count: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListMarketTypesRequest {
+    pub filter: MarketFilter,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketTypeResult {
+    pub market_type: String,
+    pub market_count: i32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TimeGranularity {
+    Days,
+    Hours,
+    Minutes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTimeRangesRequest {
+    pub filter: MarketFilter,
+    pub granularity: TimeGranularity,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeRangeResult {
+    pub time_range: TimeRange,
+    pub market_count: i32,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_navigation_results() {
+        let countries: Vec<CountryCodeResult> =
+            serde_json::from_str(r#"[{"countryCode": "GB", "marketCount": 412}]"#).unwrap();
+        assert_eq!(countries[0].country_code, "GB");
+        assert_eq!(countries[0].market_count, 412);
+
+        let venues: Vec<VenueResult> =
+            serde_json::from_str(r#"[{"venue": "Ascot", "marketCount": 24}]"#).unwrap();
+        assert_eq!(venues[0].venue, "Ascot");
+
+        let market_types: Vec<MarketTypeResult> =
+            serde_json::from_str(r#"[{"marketType": "WIN", "marketCount": 7}]"#).unwrap();
+        assert_eq!(market_types[0].market_type, "WIN");
+
+        let time_ranges: Vec<TimeRangeResult> = serde_json::from_str(
+            r#"[{"timeRange": {"from": "2025-10-05T00:00:00.000Z", "to": "2025-10-06T00:00:00.000Z"}, "marketCount": 3}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            time_ranges[0].time_range.from.as_deref(),
+            Some("2025-10-05T00:00:00.000Z")
+        );
+        assert_eq!(time_ranges[0].market_count, 3);
+    }
+
+    #[test]
+    fn test_list_time_ranges_request() {
+        let request = ListTimeRangesRequest {
+            filter: MarketFilter::default(),
+            granularity: TimeGranularity::Hours,
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["granularity"], "HOURS");
+        assert_eq!(json["filter"], serde_json::json!({}));
+    }
+
     #[test]
     fn test_market_description_with_each_way_divisor() {
         let json = r#"{
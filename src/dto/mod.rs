@@ -2,6 +2,7 @@ pub mod account;
 pub mod common;
 pub mod config;
 pub mod decimal_serde;
+pub mod heartbeat;
 pub mod market;
 pub mod misc;
 pub mod order;
@@ -11,17 +12,22 @@ pub mod streaming;
 // Re-export commonly used types for convenience
 pub use account::*;
 pub use common::*;
+pub use heartbeat::*;
 pub use market::*;
 pub use order::*;
 // Selective exports from streaming to avoid conflicts
 pub use streaming::{
-    HeartbeatMessage, HeartbeatRequest, MarketChange, MarketChangeMessage, MarketDefinition,
-    OrderChange, OrderChangeMessage, OrderRunnerChange, RunnerChange, UnmatchedOrder,
+    HeartbeatMessage, HeartbeatRequest, KeyLineDefinition, KeyLineSelection, MarketChange,
+    MarketChangeMessage, MarketDataField, MarketDataFilter, MarketDefinition, OrderChange,
+    OrderChangeMessage, OrderRunnerChange, RunnerChange, RunnerDefinition, RunnerKey,
+    StatusMessage, StreamErrorCode, StreamStatusCode, SubscriptionOptions, UnmatchedOrder,
 };
 // Use fully qualified path for LoginResponse to avoid conflict
 pub use config::*;
 pub use misc::*;
 pub use rpc::{
     ApiError, JsonRpcRequest, JsonRpcResponse, LoginRequest, LoginResponse as RpcLoginResponse,
+    SessionResponse,
 };
 pub use streaming::LoginResponse as StreamingLoginResponse;
+pub use streaming::MarketFilter as StreamingMarketFilter;
@@ -131,6 +131,47 @@ pub struct MarketOnCloseOrder {
     pub liability: Decimal,
 }
 
+/// The execution report returned by `placeOrders`, `cancelOrders`, `replaceOrders` and
+/// `updateOrders`; `BetfairError::from_execution_report` turns a failed one into an error
+pub trait ExecutionReport {
+    fn status(&self) -> &str;
+
+    fn error_code(&self) -> Option<&str>;
+
+    /// The `errorCode` of each instruction report
+    fn instruction_error_codes(&self) -> Vec<Option<&str>>;
+}
+
+/// Implement `ExecutionReport` for responses with `status`, `error_code` and `instruction_reports`
+macro_rules! impl_execution_report {
+    ($($response:ty),+ $(,)?) => {$(
+        impl ExecutionReport for $response {
+            fn status(&self) -> &str {
+                &self.status
+            }
+
+            fn error_code(&self) -> Option<&str> {
+                self.error_code.as_deref()
+            }
+
+            fn instruction_error_codes(&self) -> Vec<Option<&str>> {
+                self.instruction_reports
+                    .iter()
+                    .flatten()
+                    .map(|report| report.error_code.as_deref())
+                    .collect()
+            }
+        }
+    )+};
+}
+
+impl_execution_report!(
+    PlaceOrdersResponse,
+    CancelOrdersResponse,
+    ReplaceOrdersResponse,
+    UpdateOrdersResponse,
+);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaceOrdersResponse {
@@ -207,6 +248,7 @@ pub struct CancelInstructionReport {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_code: Option<String>,
     pub instruction: CancelInstruction,
+    #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(with = "super::decimal_serde::option")]
     pub size_cancelled: Option<Decimal>,
@@ -214,8 +256,95 @@ pub struct CancelInstructionReport {
     pub cancelled_date: Option<String>,
 }
 
+/// Cancel bets and place them again at a new price, keeping the size that was unmatched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceOrdersRequest {
+    pub market_id: String,
+    pub instructions: Vec<ReplaceInstruction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_version: Option<MarketVersion>,
+    #[serde(rename = "async", skip_serializing_if = "Option::is_none")]
+    pub async_: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceInstruction {
+    pub bet_id: String,
+    #[serde(with = "super::decimal_serde")]
+    pub new_price: Decimal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct ReplaceOrdersResponse {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    pub market_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction_reports: Option<Vec<ReplaceInstructionReport>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_ref: Option<String>,
+}
+
+/// Outcome of one replace: the cancellation of the old bet and the placement of the new one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceInstructionReport {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_instruction_report: Option<CancelInstructionReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place_instruction_report: Option<PlaceInstructionReport>,
+}
+
+/// Change non-exposure attributes of bets, currently only their persistence type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOrdersRequest {
+    pub market_id: String,
+    pub instructions: Vec<UpdateInstruction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_ref: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInstruction {
+    pub bet_id: String,
+    pub new_persistence_type: PersistenceType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOrdersResponse {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    pub market_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction_reports: Option<Vec<UpdateInstructionReport>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_ref: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInstructionReport {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    pub instruction: UpdateInstruction,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ListCurrentOrdersRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bet_ids: Option<Vec<String>>,
@@ -443,3 +572,45 @@ pub struct ItemDescription {
     #[serde(with = "super::decimal_serde::option")]
     pub each_way_divisor: Option<Decimal>,
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListMarketProfitAndLossRequest {
+    pub market_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_settled_bets: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_bsp_bets: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net_of_commission: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketProfitAndLoss {
+    pub market_id: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "super::decimal_serde::option")]
+    pub commission_applied: Option<Decimal>,
+    #[serde(default)]
+    pub profit_and_losses: Vec<RunnerProfitAndLoss>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnerProfitAndLoss {
+    pub selection_id: i64,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "super::decimal_serde::option")]
+    pub if_win: Option<Decimal>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "super::decimal_serde::option")]
+    pub if_lose: Option<Decimal>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "super::decimal_serde::option")]
+    pub if_place: Option<Decimal>,
+}
@@ -55,9 +55,30 @@ pub struct InteractiveLoginResponse {
     pub error_details: Option<String>,
 }
 
+/// Response to keepAlive and logout requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionResponse {
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub product: Option<String>,
+    pub status: String,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl SessionResponse {
+    pub fn is_success(&self) -> bool {
+        self.status == "SUCCESS"
+    }
+}
+
+/// A JSON-RPC error; Betfair puts the `APINGException` details in `data`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiError {
-    pub code: String,
+    pub code: i64,
     pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
 }
@@ -1,6 +1,9 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
+
+use super::common::{MarketStatus, OrderStatus, RunnerStatus};
 
 #[derive(Debug, Deserialize)]
 pub struct LoginResponse {
@@ -52,7 +55,7 @@ pub struct MarketChange {
 #[derive(Debug, Deserialize, Clone)]
 pub struct MarketDefinition {
     #[serde(default)]
-    pub status: Option<String>,
+    pub status: Option<MarketStatus>,
     #[serde(rename = "inPlay", default)]
     pub in_play: bool,
     #[serde(default)]
@@ -90,7 +93,7 @@ pub struct MarketDefinition {
     #[serde(rename = "betDelay", default)]
     pub bet_delay: Option<i32>,
     #[serde(default)]
-    pub runners: Option<serde_json::Value>,
+    pub runners: Option<Vec<RunnerDefinition>>,
     #[serde(default)]
     pub regulators: Option<Vec<String>>,
     #[serde(rename = "countryCode", default)]
@@ -107,18 +110,411 @@ pub struct MarketDefinition {
     pub price_ladder_definition: Option<serde_json::Value>,
     #[serde(rename = "eachWayDivisor", default)]
     pub eachway_divisor: Option<f64>,
+    /// Current key (most balanced) line of an Asian handicap or line market
+    #[serde(rename = "keyLineDefinition", default)]
+    pub key_line_definition: Option<KeyLineDefinition>,
+    #[serde(rename = "lineMinUnit", default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub line_min_unit: Option<Decimal>,
+    #[serde(rename = "lineMaxUnit", default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub line_max_unit: Option<Decimal>,
+    #[serde(rename = "lineInterval", default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub line_interval: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyLineDefinition {
+    #[serde(default)]
+    pub kl: Vec<KeyLineSelection>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyLineSelection {
+    pub id: u64,
+    #[serde(default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub hc: Option<Decimal>,
+}
+
+impl KeyLineDefinition {
+    pub fn runner_keys(&self) -> Vec<RunnerKey> {
+        self.kl
+            .iter()
+            .map(|selection| RunnerKey::new(selection.id, selection.hc))
+            .collect()
+    }
+}
+
+/// Identifies a runner within a market.
+///
+/// Asian handicap and line markets list the same selection once per handicap, so the
+/// handicap is part of the key; it is zero for runners without one. It keys the
+/// per-market orderbook maps and displays as `"47972"` or `"47972:-1.5"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RunnerKey {
+    pub selection_id: u64,
+    pub handicap: Decimal,
+}
+
+impl RunnerKey {
+    pub fn new(selection_id: u64, handicap: Option<Decimal>) -> Self {
+        Self {
+            selection_id,
+            handicap: handicap.unwrap_or_default().normalize(),
+        }
+    }
+}
+
+impl From<u64> for RunnerKey {
+    fn from(selection_id: u64) -> Self {
+        Self::new(selection_id, None)
+    }
+}
+
+impl fmt::Display for RunnerKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.handicap.is_zero() {
+            write!(f, "{}", self.selection_id)
+        } else {
+            write!(f, "{}:{}", self.selection_id, self.handicap)
+        }
+    }
+}
+
+impl FromStr for RunnerKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (selection_id, handicap) = match s.split_once(':') {
+            Some((selection_id, handicap)) => (selection_id, Some(handicap.parse::<Decimal>()?)),
+            None => (s, None),
+        };
+        Ok(Self::new(selection_id.parse()?, handicap))
+    }
+}
+
+/// Runner entry of a stream market definition
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnerDefinition {
+    pub id: u64,
+    /// Handicap line, set on Asian handicap and line markets
+    #[serde(default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub hc: Option<Decimal>,
+    #[serde(default)]
+    pub status: Option<RunnerStatus>,
+    #[serde(default)]
+    pub sort_priority: Option<i32>,
+    /// Reconciled starting price, published once the market turns in play
+    #[serde(default)]
+    #[serde(with = "super::decimal_serde::option_lenient")]
+    pub bsp: Option<Decimal>,
+    #[serde(default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub adjustment_factor: Option<Decimal>,
+    #[serde(default)]
+    pub removal_date: Option<String>,
+}
+
+impl RunnerDefinition {
+    pub fn key(&self) -> RunnerKey {
+        RunnerKey::new(self.id, self.hc)
+    }
 }
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 pub struct RunnerChange {
     pub id: u64,
+    /// Handicap, present on Asian handicap and line markets
+    #[serde(default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub hc: Option<Decimal>,
+    /// Best available to back, as `[level, price, size]` (EX_BEST_OFFERS)
     #[serde(rename = "batb", default)]
     #[serde(with = "super::decimal_serde::option_vec_array3")]
     pub available_to_back: Option<Vec<[Decimal; 3]>>,
+    /// Best available to lay, as `[level, price, size]` (EX_BEST_OFFERS)
     #[serde(rename = "batl", default)]
     #[serde(with = "super::decimal_serde::option_vec_array3")]
     pub available_to_lay: Option<Vec<[Decimal; 3]>>,
+    /// Best display available to back including virtual prices (EX_BEST_OFFERS_DISP)
+    #[serde(rename = "bdatb", default)]
+    #[serde(with = "super::decimal_serde::option_vec_array3")]
+    pub best_display_available_to_back: Option<Vec<[Decimal; 3]>>,
+    /// Best display available to lay including virtual prices (EX_BEST_OFFERS_DISP)
+    #[serde(rename = "bdatl", default)]
+    #[serde(with = "super::decimal_serde::option_vec_array3")]
+    pub best_display_available_to_lay: Option<Vec<[Decimal; 3]>>,
+    /// Full depth available to back, as `[price, size]` (EX_ALL_OFFERS)
+    #[serde(rename = "atb", default)]
+    #[serde(with = "super::decimal_serde::option_vec_array2")]
+    pub all_available_to_back: Option<Vec<[Decimal; 2]>>,
+    /// Full depth available to lay, as `[price, size]` (EX_ALL_OFFERS)
+    #[serde(rename = "atl", default)]
+    #[serde(with = "super::decimal_serde::option_vec_array2")]
+    pub all_available_to_lay: Option<Vec<[Decimal; 2]>>,
+    /// Traded volume by price, as `[price, size]` (EX_TRADED)
+    #[serde(rename = "trd", default)]
+    #[serde(with = "super::decimal_serde::option_vec_array2")]
+    pub traded: Option<Vec<[Decimal; 2]>>,
+    /// Last traded price (EX_LTP)
+    #[serde(rename = "ltp", default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub last_traded_price: Option<Decimal>,
+    /// Total traded volume on the runner (EX_TRADED_VOL)
+    #[serde(rename = "tv", default)]
+    #[serde(with = "super::decimal_serde::option")]
+    pub traded_volume: Option<Decimal>,
+    /// Starting price near projection (SP_PROJECTED)
+    #[serde(rename = "spn", default)]
+    #[serde(with = "super::decimal_serde::option_lenient")]
+    pub sp_near: Option<Decimal>,
+    /// Starting price far projection (SP_PROJECTED)
+    #[serde(rename = "spf", default)]
+    #[serde(with = "super::decimal_serde::option_lenient")]
+    pub sp_far: Option<Decimal>,
+    /// Starting price back ladder, as `[price, size]` (SP_TRADED)
+    #[serde(rename = "spb", default)]
+    #[serde(with = "super::decimal_serde::option_vec_array2")]
+    pub sp_back: Option<Vec<[Decimal; 2]>>,
+    /// Starting price lay ladder, as `[price, size]` (SP_TRADED)
+    #[serde(rename = "spl", default)]
+    #[serde(with = "super::decimal_serde::option_vec_array2")]
+    pub sp_lay: Option<Vec<[Decimal; 2]>>,
+}
+
+impl RunnerChange {
+    pub fn key(&self) -> RunnerKey {
+        RunnerKey::new(self.id, self.hc)
+    }
+}
+
+/// Market data fields that can be requested on a market subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MarketDataField {
+    ExBestOffersDisp,
+    ExBestOffers,
+    ExAllOffers,
+    ExTraded,
+    ExTradedVol,
+    ExLtp,
+    ExMarketDef,
+    SpTraded,
+    SpProjected,
+}
+
+/// Selects which market data Betfair sends for a market subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketDataFilter {
+    pub fields: Vec<MarketDataField>,
+    #[serde(rename = "ladderLevels", skip_serializing_if = "Option::is_none")]
+    pub ladder_levels: Option<usize>,
+}
+
+impl MarketDataFilter {
+    pub fn new(fields: Vec<MarketDataField>, ladder_levels: Option<usize>) -> Self {
+        Self {
+            fields,
+            ladder_levels,
+        }
+    }
+
+    /// Best offers to `levels` depth plus the market definition
+    pub fn best_offers(levels: usize) -> Self {
+        Self::new(
+            vec![MarketDataField::ExBestOffers, MarketDataField::ExMarketDef],
+            Some(levels),
+        )
+    }
+
+    /// Every market data field Betfair offers
+    pub fn all(levels: usize) -> Self {
+        Self::new(
+            vec![
+                MarketDataField::ExBestOffersDisp,
+                MarketDataField::ExBestOffers,
+                MarketDataField::ExAllOffers,
+                MarketDataField::ExTraded,
+                MarketDataField::ExTradedVol,
+                MarketDataField::ExLtp,
+                MarketDataField::ExMarketDef,
+                MarketDataField::SpTraded,
+                MarketDataField::SpProjected,
+            ],
+            Some(levels),
+        )
+    }
+
+    /// Ladder depth for level-based fields, defaulting to Betfair's 3 levels
+    pub fn levels(&self) -> usize {
+        self.ladder_levels.unwrap_or(3)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StreamStatusCode {
+    Success,
+    Failure,
+}
+
+/// Error codes carried by a failed stream status message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StreamErrorCode {
+    NoAppKey,
+    InvalidAppKey,
+    NoSession,
+    InvalidSessionInformation,
+    NotAuthorized,
+    InvalidInput,
+    InvalidClock,
+    UnexpectedError,
+    Timeout,
+    SubscriptionLimitExceeded,
+    InvalidRequest,
+    ConnectionFailed,
+    MaxConnectionLimitExceeded,
+    TooManyRequests,
+    #[serde(other)]
+    Unknown,
+}
+
+impl StreamErrorCode {
+    /// The code as Betfair sends it, e.g. `NO_APP_KEY`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamErrorCode::NoAppKey => "NO_APP_KEY",
+            StreamErrorCode::InvalidAppKey => "INVALID_APP_KEY",
+            StreamErrorCode::NoSession => "NO_SESSION",
+            StreamErrorCode::InvalidSessionInformation => "INVALID_SESSION_INFORMATION",
+            StreamErrorCode::NotAuthorized => "NOT_AUTHORIZED",
+            StreamErrorCode::InvalidInput => "INVALID_INPUT",
+            StreamErrorCode::InvalidClock => "INVALID_CLOCK",
+            StreamErrorCode::UnexpectedError => "UNEXPECTED_ERROR",
+            StreamErrorCode::Timeout => "TIMEOUT",
+            StreamErrorCode::SubscriptionLimitExceeded => "SUBSCRIPTION_LIMIT_EXCEEDED",
+            StreamErrorCode::InvalidRequest => "INVALID_REQUEST",
+            StreamErrorCode::ConnectionFailed => "CONNECTION_FAILED",
+            StreamErrorCode::MaxConnectionLimitExceeded => "MAX_CONNECTION_LIMIT_EXCEEDED",
+            StreamErrorCode::TooManyRequests => "TOO_MANY_REQUESTS",
+            StreamErrorCode::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// The session token was rejected, so reconnecting needs a new one
+    pub fn is_session_error(&self) -> bool {
+        matches!(
+            self,
+            StreamErrorCode::NoSession | StreamErrorCode::InvalidSessionInformation
+        )
+    }
+
+    /// Errors that reconnecting with the same credentials and subscriptions cannot fix
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            StreamErrorCode::NoAppKey
+                | StreamErrorCode::InvalidAppKey
+                | StreamErrorCode::NoSession
+                | StreamErrorCode::InvalidSessionInformation
+                | StreamErrorCode::NotAuthorized
+                | StreamErrorCode::SubscriptionLimitExceeded
+                | StreamErrorCode::MaxConnectionLimitExceeded
+        )
+    }
+}
+
+impl From<&str> for StreamErrorCode {
+    fn from(code: &str) -> Self {
+        serde_json::from_value(serde_json::Value::String(code.to_string()))
+            .unwrap_or(StreamErrorCode::Unknown)
+    }
+}
+
+impl fmt::Display for StreamErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Status message sent in reply to authentication, subscriptions and on errors
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "StatusMessageWire")]
+pub struct StatusMessage {
+    pub id: Option<i64>,
+    pub status_code: StreamStatusCode,
+    pub error_code: Option<StreamErrorCode>,
+    /// `errorCode` as sent, so codes this crate doesn't know can still be reported
+    pub raw_error_code: Option<String>,
+    pub error_message: Option<String>,
+    pub connection_closed: bool,
+    pub connection_id: Option<String>,
+    pub connections_available: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct StatusMessageWire {
+    #[serde(default)]
+    id: Option<i64>,
+    #[serde(rename = "statusCode")]
+    status_code: StreamStatusCode,
+    #[serde(rename = "errorCode", default)]
+    error_code: Option<String>,
+    #[serde(rename = "errorMessage", default)]
+    error_message: Option<String>,
+    #[serde(rename = "connectionClosed", default)]
+    connection_closed: bool,
+    #[serde(rename = "connectionId", default)]
+    connection_id: Option<String>,
+    #[serde(rename = "connectionsAvailable", default)]
+    connections_available: Option<i64>,
+}
+
+impl From<StatusMessageWire> for StatusMessage {
+    fn from(wire: StatusMessageWire) -> Self {
+        Self {
+            id: wire.id,
+            status_code: wire.status_code,
+            error_code: wire.error_code.as_deref().map(StreamErrorCode::from),
+            raw_error_code: wire.error_code,
+            error_message: wire.error_message,
+            connection_closed: wire.connection_closed,
+            connection_id: wire.connection_id,
+            connections_available: wire.connections_available,
+        }
+    }
+}
+
+impl StatusMessage {
+    pub fn is_success(&self) -> bool {
+        self.status_code == StreamStatusCode::Success
+    }
+
+    /// A failure that should stop the client rather than trigger a reconnect
+    pub fn is_fatal(&self) -> bool {
+        !self.is_success() && self.error_code.is_some_and(|code| code.is_fatal())
+    }
+}
+
+impl fmt::Display for StatusMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match (self.error_code, &self.raw_error_code) {
+            (Some(StreamErrorCode::Unknown), Some(raw)) => Some(raw.as_str()),
+            (code, _) => code.map(|code| code.as_str()),
+        };
+        match (code, &self.error_message) {
+            (Some(code), Some(message)) => write!(f, "{code}: {message}"),
+            (Some(code), None) => write!(f, "{code}"),
+            (None, Some(message)) => write!(f, "{:?}: {message}", self.status_code),
+            (None, None) => write!(f, "{:?}", self.status_code),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -153,6 +549,14 @@ pub struct OrderChangeMessage {
     #[serde(rename = "clk")]
     pub clock: String,
     pub pt: i64,
+    #[serde(default)]
+    pub ct: Option<String>,
+    #[serde(rename = "initialClk", default)]
+    pub initial_clock: Option<String>,
+    #[serde(rename = "conflateMs", default)]
+    pub conflate_ms: Option<i64>,
+    #[serde(rename = "heartbeatMs", default)]
+    pub heartbeat_ms: Option<i64>,
     #[serde(rename = "oc", default)]
     pub order_changes: Vec<OrderChange>,
 }
@@ -193,6 +597,12 @@ pub struct OrderRunnerChange {
     pub strategy_matches: Option<std::collections::HashMap<String, StrategyMatchChange>>,
 }
 
+impl OrderRunnerChange {
+    pub fn key(&self) -> RunnerKey {
+        RunnerKey::new(self.id, self.handicap)
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct UnmatchedOrder {
     pub id: String,
@@ -247,16 +657,93 @@ pub struct UnmatchedOrder {
     pub rfs: Option<String>,
 }
 
+impl UnmatchedOrder {
+    /// The stream's `E`/`EC` status as the REST order status
+    pub fn order_status(&self) -> Option<OrderStatus> {
+        match self.status.as_str() {
+            "E" => Some(OrderStatus::Executable),
+            "EC" => Some(OrderStatus::ExecutionComplete),
+            _ => None,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.status == "EC"
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct StrategyMatchChange {
     #[serde(rename = "mb")]
+    #[serde(default)]
     #[serde(with = "super::decimal_serde::option_vec_vec_decimal")]
     pub matched_backs: Option<Vec<Vec<Decimal>>>,
     #[serde(rename = "ml")]
+    #[serde(default)]
     #[serde(with = "super::decimal_serde::option_vec_vec_decimal")]
     pub matched_lays: Option<Vec<Vec<Decimal>>>,
 }
 
+/// Market filter for stream subscriptions.
+///
+/// Unlike the REST `MarketFilter`, this is evaluated continuously by Betfair so
+/// markets that start matching after subscribing are delivered automatically.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_type_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_codes: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_types: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub betting_types: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub venues: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_in_play_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bsp_market: Option<bool>,
+}
+
+impl MarketFilter {
+    pub fn with_market_ids(market_ids: Vec<String>) -> Self {
+        Self {
+            market_ids: Some(market_ids),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&super::market::MarketFilter> for MarketFilter {
+    /// Map the overlapping fields of a REST filter; REST-only fields such as
+    /// `textQuery` or `marketStartTime` have no streaming equivalent and are dropped.
+    fn from(filter: &super::market::MarketFilter) -> Self {
+        Self {
+            market_ids: filter.market_ids.clone(),
+            event_type_ids: filter.event_type_ids.clone(),
+            event_ids: filter.event_ids.clone(),
+            country_codes: filter.market_countries.clone(),
+            market_types: filter.market_type_codes.clone(),
+            betting_types: filter.market_betting_types.clone(),
+            venues: filter.venues.clone(),
+            turn_in_play_enabled: filter.turn_in_play_enabled,
+            bsp_market: filter.bsp_only,
+        }
+    }
+}
+
+impl From<super::market::MarketFilter> for MarketFilter {
+    fn from(filter: super::market::MarketFilter) -> Self {
+        Self::from(&filter)
+    }
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct OrderFilter {
     #[serde(
@@ -286,6 +773,41 @@ impl Default for OrderFilter {
     }
 }
 
+/// Delivery options for a market or order subscription.
+///
+/// `conflate_ms` (0-120000) batches updates so at most one message is sent per
+/// interval; `heartbeat_ms` (500-5000) is how long the stream may stay silent
+/// before Betfair sends a heartbeat. Unset values use Betfair's defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflate_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heartbeat_ms: Option<u64>,
+}
+
+impl SubscriptionOptions {
+    pub fn new(conflate_ms: Option<u64>, heartbeat_ms: Option<u64>) -> Self {
+        Self {
+            conflate_ms,
+            heartbeat_ms,
+        }
+    }
+
+    /// Render the options as additional subscription message fields
+    pub fn subscription_fields(&self) -> String {
+        let mut fields = String::new();
+        if let Some(conflate_ms) = self.conflate_ms {
+            fields.push_str(&format!(",\"conflateMs\":{conflate_ms}"));
+        }
+        if let Some(heartbeat_ms) = self.heartbeat_ms {
+            fields.push_str(&format!(",\"heartbeatMs\":{heartbeat_ms}"));
+        }
+        fields
+    }
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct OrderSubscriptionMessage {
     pub op: String,
@@ -301,6 +823,115 @@ pub struct OrderSubscriptionMessage {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_runner_key_handicap() {
+        let change: RunnerChange =
+            serde_json::from_str(r#"{"id":47972,"hc":-1.50,"batb":[[0,2.0,5.0]]}"#).unwrap();
+        let key = change.key();
+        assert_eq!(key, RunnerKey::new(47972, Some(Decimal::new(-15, 1))));
+        assert_eq!(key.to_string(), "47972:-1.5");
+        assert_eq!("47972:-1.5".parse::<RunnerKey>().unwrap(), key);
+
+        // Without a handicap the key renders as the plain selection ID
+        let plain = RunnerKey::from(47972);
+        assert_eq!(plain, RunnerKey::new(47972, Some(Decimal::ZERO)));
+        assert_eq!(plain.to_string(), "47972");
+        assert_eq!("47972".parse::<RunnerKey>().unwrap(), plain);
+        assert_ne!(plain, key);
+    }
+
+    #[test]
+    fn test_market_definition_key_line() {
+        let json = r#"{"status":"OPEN","inPlay":false,"marketTime":null,"lineInterval":0.5,
+            "keyLineDefinition":{"kl":[{"id":1,"hc":-0.25},{"id":2,"hc":0.25}]}}"#;
+        let definition: MarketDefinition = serde_json::from_str(json).unwrap();
+
+        assert_eq!(definition.line_interval, Some(Decimal::new(5, 1)));
+        assert_eq!(
+            definition.key_line_definition.unwrap().runner_keys(),
+            vec![
+                RunnerKey::new(1, Some(Decimal::new(-25, 2))),
+                RunnerKey::new(2, Some(Decimal::new(25, 2))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_market_definition_typed_runners() {
+        let json = r#"{
+            "status": "OPEN",
+            "inPlay": false,
+            "betDelay": 0,
+            "version": 4290217863,
+            "runners": [
+                {"status": "ACTIVE", "sortPriority": 1, "id": 47972, "adjustmentFactor": 52.6},
+                {"status": "REMOVED", "sortPriority": 2, "id": 47973, "adjustmentFactor": 8.1,
+                 "removalDate": "2024-01-01T12:00:00.000Z"},
+                {"status": "WINNER", "sortPriority": 3, "id": 47974, "hc": -1.5, "bsp": "NaN"}
+            ]
+        }"#;
+
+        let definition: MarketDefinition = serde_json::from_str(json).unwrap();
+        assert_eq!(definition.status, Some(MarketStatus::Open));
+        assert_eq!(definition.version, Some(4290217863));
+
+        let runners = definition.runners.unwrap();
+        assert_eq!(runners.len(), 3);
+        assert_eq!(runners[0].status, Some(RunnerStatus::Active));
+        assert_eq!(runners[0].adjustment_factor, Some(Decimal::new(526, 1)));
+        assert_eq!(runners[1].status, Some(RunnerStatus::Removed));
+        assert!(runners[1].removal_date.is_some());
+        assert_eq!(runners[2].hc, Some(Decimal::new(-15, 1)));
+        assert_eq!(runners[2].bsp, None);
+    }
+
+    #[test]
+    fn test_market_definition_unknown_statuses() {
+        let json = r#"{
+            "status": "SOMETHING_NEW",
+            "inPlay": false,
+            "runners": [{"status": "ALSO_NEW", "id": 47972}]
+        }"#;
+
+        let definition: MarketDefinition = serde_json::from_str(json).unwrap();
+        assert_eq!(definition.status, Some(MarketStatus::Unknown));
+        assert_eq!(
+            definition.runners.unwrap()[0].status,
+            Some(RunnerStatus::Unknown)
+        );
+    }
+
+    #[test]
+    fn test_status_message_error_codes() {
+        let status: StatusMessage = serde_json::from_str(
+            r#"{"op":"status","id":2,"statusCode":"FAILURE","errorCode":"SUBSCRIPTION_LIMIT_EXCEEDED","errorMessage":"trying to subscribe to 1001 markets","connectionClosed":false}"#,
+        )
+        .unwrap();
+        assert!(!status.is_success());
+        assert_eq!(
+            status.error_code,
+            Some(StreamErrorCode::SubscriptionLimitExceeded)
+        );
+        assert!(status.is_fatal());
+        assert_eq!(
+            status.to_string(),
+            "SUBSCRIPTION_LIMIT_EXCEEDED: trying to subscribe to 1001 markets"
+        );
+
+        let status: StatusMessage = serde_json::from_str(
+            r#"{"op":"status","statusCode":"FAILURE","errorCode":"SOMETHING_NEW","connectionClosed":true}"#,
+        )
+        .unwrap();
+        assert_eq!(status.error_code, Some(StreamErrorCode::Unknown));
+        assert!(!status.is_fatal());
+        assert_eq!(status.to_string(), "SOMETHING_NEW");
+
+        let status: StatusMessage =
+            serde_json::from_str(r#"{"op":"status","id":1,"statusCode":"SUCCESS"}"#).unwrap();
+        assert!(status.is_success());
+        assert!(!status.is_fatal());
+    }
+
     #[test]
     fn test_market_definition_deserialize_with_eachway_divisor() {
         let json = r#"{
@@ -331,4 +962,110 @@ mod tests {
         let market_def = result.unwrap();
         assert_eq!(market_def.eachway_divisor, None);
     }
+
+    #[test]
+    fn test_runner_change_deserialize_all_fields() {
+        use rust_decimal_macros::dec;
+
+        let json = r#"{
+            "id": 12345,
+            "batb": [[0, 2.0, 10.5]],
+            "bdatl": [[0, 2.1, 4.0]],
+            "atb": [[2.0, 10.5], [1.99, 3.0]],
+            "atl": [[2.1, 7.25]],
+            "trd": [[2.02, 150.0]],
+            "ltp": 2.02,
+            "tv": 1500.75,
+            "spn": 2.05,
+            "spf": "NaN",
+            "spb": [[1000, 20]],
+            "spl": [[1.01, 5]]
+        }"#;
+
+        let rc: RunnerChange = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            rc.available_to_back.unwrap()[0],
+            [dec!(0), dec!(2.0), dec!(10.5)]
+        );
+        assert_eq!(rc.best_display_available_to_lay.unwrap().len(), 1);
+        assert_eq!(
+            rc.all_available_to_back.unwrap()[1],
+            [dec!(1.99), dec!(3.0)]
+        );
+        assert_eq!(rc.all_available_to_lay.unwrap()[0], [dec!(2.1), dec!(7.25)]);
+        assert_eq!(rc.traded.unwrap()[0], [dec!(2.02), dec!(150.0)]);
+        assert_eq!(rc.last_traded_price, Some(dec!(2.02)));
+        assert_eq!(rc.traded_volume, Some(dec!(1500.75)));
+        assert_eq!(rc.sp_near, Some(dec!(2.05)));
+        assert_eq!(rc.sp_far, None);
+        assert_eq!(rc.sp_back.unwrap()[0], [dec!(1000), dec!(20)]);
+        assert_eq!(rc.sp_lay.unwrap()[0], [dec!(1.01), dec!(5)]);
+        assert!(rc.best_display_available_to_back.is_none());
+    }
+
+    #[test]
+    fn test_streaming_market_filter_from_rest_filter() {
+        let rest = super::super::market::MarketFilter {
+            event_type_ids: Some(vec!["7".to_string()]),
+            market_countries: Some(vec!["GB".to_string(), "IE".to_string()]),
+            market_type_codes: Some(vec!["WIN".to_string()]),
+            text_query: Some("ignored".to_string()),
+            turn_in_play_enabled: Some(true),
+            ..Default::default()
+        };
+
+        let filter = MarketFilter::from(&rest);
+        assert_eq!(filter.event_type_ids, Some(vec!["7".to_string()]));
+        assert_eq!(
+            filter.country_codes,
+            Some(vec!["GB".to_string(), "IE".to_string()])
+        );
+        assert_eq!(filter.market_types, Some(vec!["WIN".to_string()]));
+        assert_eq!(filter.turn_in_play_enabled, Some(true));
+        assert!(filter.market_ids.is_none());
+
+        let json = serde_json::to_string(&filter).unwrap();
+        assert_eq!(
+            json,
+            r#"{"eventTypeIds":["7"],"countryCodes":["GB","IE"],"marketTypes":["WIN"],"turnInPlayEnabled":true}"#
+        );
+    }
+
+    #[test]
+    fn test_subscription_options_fields() {
+        assert_eq!(SubscriptionOptions::default().subscription_fields(), "");
+        assert_eq!(
+            SubscriptionOptions::new(Some(500), Some(1000)).subscription_fields(),
+            r#","conflateMs":500,"heartbeatMs":1000"#
+        );
+        assert_eq!(
+            SubscriptionOptions::new(None, Some(5000)).subscription_fields(),
+            r#","heartbeatMs":5000"#
+        );
+    }
+
+    #[test]
+    fn test_market_data_filter_serialize() {
+        let filter = MarketDataFilter::new(
+            vec![
+                MarketDataField::ExAllOffers,
+                MarketDataField::ExTraded,
+                MarketDataField::ExLtp,
+                MarketDataField::SpProjected,
+                MarketDataField::ExMarketDef,
+            ],
+            None,
+        );
+        let json = serde_json::to_string(&filter).unwrap();
+        assert_eq!(
+            json,
+            r#"{"fields":["EX_ALL_OFFERS","EX_TRADED","EX_LTP","SP_PROJECTED","EX_MARKET_DEF"]}"#
+        );
+
+        let json = serde_json::to_string(&MarketDataFilter::best_offers(5)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"fields":["EX_BEST_OFFERS","EX_MARKET_DEF"],"ladderLevels":5}"#
+        );
+    }
 }
@@ -0,0 +1,521 @@
+use crate::dto::common::InstructionReportErrorCode;
+use crate::dto::order::{CurrentOrderSummary, ExecutionReport};
+use crate::dto::rpc::ApiError;
+use std::fmt;
+
+/// Error codes of `APINGException` and `AccountAPINGException`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiErrorCode {
+    TooMuchData,
+    InvalidInputData,
+    InvalidSessionInformation,
+    NoAppKey,
+    NoSession,
+    UnexpectedError,
+    InvalidAppKey,
+    TooManyRequests,
+    ServiceBusy,
+    TimeoutError,
+    RequestSizeExceedsLimit,
+    AccessDenied,
+    /// A code this crate does not know about yet
+    Other(String),
+}
+
+impl ApiErrorCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ApiErrorCode::TooMuchData => "TOO_MUCH_DATA",
+            ApiErrorCode::InvalidInputData => "INVALID_INPUT_DATA",
+            ApiErrorCode::InvalidSessionInformation => "INVALID_SESSION_INFORMATION",
+            ApiErrorCode::NoAppKey => "NO_APP_KEY",
+            ApiErrorCode::NoSession => "NO_SESSION",
+            ApiErrorCode::UnexpectedError => "UNEXPECTED_ERROR",
+            ApiErrorCode::InvalidAppKey => "INVALID_APP_KEY",
+            ApiErrorCode::TooManyRequests => "TOO_MANY_REQUESTS",
+            ApiErrorCode::ServiceBusy => "SERVICE_BUSY",
+            ApiErrorCode::TimeoutError => "TIMEOUT_ERROR",
+            ApiErrorCode::RequestSizeExceedsLimit => "REQUEST_SIZE_EXCEEDS_LIMIT",
+            ApiErrorCode::AccessDenied => "ACCESS_DENIED",
+            ApiErrorCode::Other(code) => code,
+        }
+    }
+
+    /// Whether the session token was rejected, e.g. because it expired
+    pub fn is_session_error(&self) -> bool {
+        matches!(
+            self,
+            ApiErrorCode::InvalidSessionInformation | ApiErrorCode::NoSession
+        )
+    }
+}
+
+impl From<&str> for ApiErrorCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "TOO_MUCH_DATA" => ApiErrorCode::TooMuchData,
+            "INVALID_INPUT_DATA" => ApiErrorCode::InvalidInputData,
+            "INVALID_SESSION_INFORMATION" => ApiErrorCode::InvalidSessionInformation,
+            "NO_APP_KEY" => ApiErrorCode::NoAppKey,
+            "NO_SESSION" => ApiErrorCode::NoSession,
+            "UNEXPECTED_ERROR" => ApiErrorCode::UnexpectedError,
+            "INVALID_APP_KEY" => ApiErrorCode::InvalidAppKey,
+            "TOO_MANY_REQUESTS" => ApiErrorCode::TooManyRequests,
+            "SERVICE_BUSY" => ApiErrorCode::ServiceBusy,
+            "TIMEOUT_ERROR" => ApiErrorCode::TimeoutError,
+            "REQUEST_SIZE_EXCEEDS_LIMIT" => ApiErrorCode::RequestSizeExceedsLimit,
+            "ACCESS_DENIED" => ApiErrorCode::AccessDenied,
+            other => ApiErrorCode::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ApiErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Everything that can go wrong talking to Betfair.
+///
+/// `RestClient` methods return `anyhow::Result`; failures from Betfair carry a
+/// `BetfairError` that can be recovered with `err.downcast_ref::<BetfairError>()`.
+#[derive(Debug)]
+pub enum BetfairError {
+    /// The request could not be sent or the response could not be read
+    Transport(reqwest::Error),
+    /// A non-success HTTP status without a JSON-RPC error in the body
+    Http { status: u16, body: String },
+    /// A login was refused, e.g. with `INVALID_USERNAME_OR_PASSWORD`
+    Login {
+        status: String,
+        message: Option<String>,
+    },
+    /// An `APINGException` or `AccountAPINGException`
+    Api {
+        code: ApiErrorCode,
+        request_uuid: Option<String>,
+        details: Option<String>,
+    },
+    /// A JSON-RPC error without exception data, e.g. an unknown method
+    Rpc { code: i64, message: String },
+    /// An order call was not executed; `error_code` is the `ExecutionReportErrorCode`
+    Execution {
+        status: String,
+        error_code: Option<String>,
+        instruction_errors: Vec<InstructionReportErrorCode>,
+    },
+    /// The response body was not what the called operation returns
+    InvalidResponse(String),
+    /// A transaction failed without a clear outcome; `current_orders` is what
+    /// `listCurrentOrders` reported afterwards for the bets it concerned.
+    ///
+    /// Resending the request with the same `customer_ref` soon after is safe, Betfair
+    /// ignores it if the first one was executed. `customer_order_refs` are those sent
+    /// with each place instruction, in order, and empty for other transactions.
+    Unconfirmed {
+        source: Box<BetfairError>,
+        customer_ref: String,
+        customer_order_refs: Vec<String>,
+        current_orders: Vec<CurrentOrderSummary>,
+    },
+}
+
+impl BetfairError {
+    /// Convert a JSON-RPC error, picking up the exception data Betfair attaches to it
+    pub fn from_api_error(error: ApiError) -> Self {
+        let exception = error
+            .data
+            .as_ref()
+            .and_then(|data| data.as_object())
+            .and_then(|data| data.values().find(|value| value.get("errorCode").is_some()));
+
+        let Some(exception) = exception else {
+            return BetfairError::Rpc {
+                code: error.code,
+                message: error.message,
+            };
+        };
+
+        let field = |name: &str| {
+            exception
+                .get(name)
+                .and_then(|value| value.as_str())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        BetfairError::Api {
+            code: ApiErrorCode::from(field("errorCode").unwrap_or_default().as_str()),
+            request_uuid: field("requestUUID"),
+            details: field("errorDetails"),
+        }
+    }
+
+    /// An `Execution` error, with the instruction error codes, for an execution report that
+    /// did not succeed
+    pub fn from_execution_report(report: &impl ExecutionReport) -> Option<Self> {
+        let status = report.status();
+        if status == "SUCCESS" {
+            return None;
+        }
+
+        let instruction_errors = report
+            .instruction_error_codes()
+            .into_iter()
+            .flatten()
+            .filter_map(|code| serde_json::from_value(code.into()).ok())
+            .collect();
+
+        Some(BetfairError::Execution {
+            status: status.to_string(),
+            error_code: report.error_code().map(str::to_string),
+            instruction_errors,
+        })
+    }
+
+    /// The API error code, if Betfair rejected the request with one
+    pub fn api_error_code(&self) -> Option<&ApiErrorCode> {
+        match self {
+            BetfairError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Whether the session token was rejected, so logging in again may help
+    pub fn is_session_error(&self) -> bool {
+        self.api_error_code()
+            .is_some_and(ApiErrorCode::is_session_error)
+    }
+
+    /// Whether a failed transaction may still have been executed.
+    ///
+    /// The request reached Betfair but no answer came back, e.g. after a timeout.
+    pub fn is_ambiguous(&self) -> bool {
+        match self {
+            BetfairError::Transport(e) => !e.is_connect() && !e.is_builder(),
+            BetfairError::Http { status, .. } => *status >= 500,
+            BetfairError::Api { code, .. } => {
+                matches!(
+                    code,
+                    ApiErrorCode::TimeoutError | ApiErrorCode::UnexpectedError
+                )
+            }
+            BetfairError::InvalidResponse(_) | BetfairError::Unconfirmed { .. } => true,
+            BetfairError::Login { .. }
+            | BetfairError::Rpc { .. }
+            | BetfairError::Execution { .. } => false,
+        }
+    }
+
+    /// Whether the same request may succeed if it is sent again later
+    pub fn is_transient(&self) -> bool {
+        match self {
+            BetfairError::Transport(e) => is_transient_transport_error(e),
+            BetfairError::Http { status, .. } => *status == 429 || *status >= 500,
+            BetfairError::Api { code, .. } => matches!(
+                code,
+                ApiErrorCode::TooManyRequests
+                    | ApiErrorCode::ServiceBusy
+                    | ApiErrorCode::TimeoutError
+                    | ApiErrorCode::UnexpectedError
+            ),
+            BetfairError::Login { .. }
+            | BetfairError::Rpc { .. }
+            | BetfairError::Execution { .. }
+            | BetfairError::InvalidResponse(_)
+            | BetfairError::Unconfirmed { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for BetfairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BetfairError::Transport(e) => write!(f, "Request failed: {e}"),
+            BetfairError::Http { status, body } => {
+                write!(f, "Request failed with status {status}: {}", body.trim())
+            }
+            BetfairError::Login { status, message } => match message {
+                Some(message) => write!(f, "Login failed with {status}: {message}"),
+                None => write!(f, "Login failed with {status}"),
+            },
+            BetfairError::Api {
+                code,
+                request_uuid,
+                details,
+            } => {
+                write!(f, "API error {code}")?;
+                if let Some(details) = details {
+                    write!(f, ": {details}")?;
+                }
+                if let Some(request_uuid) = request_uuid {
+                    write!(f, " (request {request_uuid})")?;
+                }
+                Ok(())
+            }
+            BetfairError::Rpc { code, message } => write!(f, "JSON-RPC error {code}: {message}"),
+            BetfairError::Execution {
+                status,
+                error_code,
+                instruction_errors,
+            } => {
+                write!(f, "Order execution {status}")?;
+                if let Some(error_code) = error_code {
+                    write!(f, " with {error_code}")?;
+                }
+                if !instruction_errors.is_empty() {
+                    write!(f, ": {instruction_errors:?}")?;
+                }
+                Ok(())
+            }
+            BetfairError::InvalidResponse(message) => write!(f, "Invalid response: {message}"),
+            BetfairError::Unconfirmed {
+                source,
+                current_orders,
+                ..
+            } => write!(
+                f,
+                "Outcome unknown after {source}; {} matching current orders",
+                current_orders.len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BetfairError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BetfairError::Transport(e) => Some(e),
+            BetfairError::Unconfirmed { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for BetfairError {
+    fn from(e: reqwest::Error) -> Self {
+        BetfairError::Transport(e)
+    }
+}
+
+/// Whether retrying `err` may help; local failures, like an unreadable certificate, never do
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    if let Some(e) = err.downcast_ref::<BetfairError>() {
+        return e.is_transient();
+    }
+    err.downcast_ref::<reqwest::Error>()
+        .is_some_and(is_transient_transport_error)
+}
+
+/// Failed connects, timeouts and connections dropped before any response are worth retrying;
+/// a request that cannot be built, or a response that cannot be read, is not
+fn is_transient_transport_error(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || (e.is_request() && e.status().is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Report(
+        &'static str,
+        Option<&'static str>,
+        Vec<Option<&'static str>>,
+    );
+
+    impl ExecutionReport for Report {
+        fn status(&self) -> &str {
+            self.0
+        }
+
+        fn error_code(&self) -> Option<&str> {
+            self.1
+        }
+
+        fn instruction_error_codes(&self) -> Vec<Option<&str>> {
+            self.2.clone()
+        }
+    }
+
+    #[test]
+    fn test_execution_error_from_failed_report() {
+        let success = Report("SUCCESS", None, vec![None]);
+        assert!(BetfairError::from_execution_report(&success).is_none());
+
+        let err = BetfairError::from_execution_report(&Report(
+            "FAILURE",
+            Some("BET_ACTION_ERROR"),
+            vec![Some("INSUFFICIENT_FUNDS"), None, Some("SOME_NEW_CODE")],
+        ))
+        .unwrap();
+        assert!(!err.is_transient());
+        match err {
+            BetfairError::Execution {
+                status,
+                error_code,
+                instruction_errors,
+            } => {
+                assert_eq!(status, "FAILURE");
+                assert_eq!(error_code.as_deref(), Some("BET_ACTION_ERROR"));
+                assert_eq!(
+                    instruction_errors,
+                    vec![
+                        InstructionReportErrorCode::InsufficientFunds,
+                        InstructionReportErrorCode::Unknown
+                    ]
+                );
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_errors() {
+        let timeout = BetfairError::Api {
+            code: ApiErrorCode::TimeoutError,
+            request_uuid: None,
+            details: None,
+        };
+        assert!(timeout.is_ambiguous());
+        assert!(BetfairError::Http {
+            status: 503,
+            body: String::new()
+        }
+        .is_ambiguous());
+        assert!(BetfairError::InvalidResponse("truncated".to_string()).is_ambiguous());
+
+        // Rejected requests were never executed
+        assert!(!BetfairError::Api {
+            code: ApiErrorCode::TooManyRequests,
+            request_uuid: None,
+            details: None,
+        }
+        .is_ambiguous());
+        assert!(!BetfairError::Http {
+            status: 400,
+            body: String::new()
+        }
+        .is_ambiguous());
+
+        let unconfirmed = BetfairError::Unconfirmed {
+            source: Box::new(timeout),
+            customer_ref: "ref".to_string(),
+            customer_order_refs: Vec::new(),
+            current_orders: Vec::new(),
+        };
+        assert!(!unconfirmed.is_transient());
+        assert_eq!(
+            unconfirmed.to_string(),
+            "Outcome unknown after API error TIMEOUT_ERROR; 0 matching current orders"
+        );
+    }
+
+    #[test]
+    fn test_unclassified_errors_are_not_retried() {
+        let header_error = "bad\nkey"
+            .parse::<reqwest::header::HeaderValue>()
+            .unwrap_err();
+        assert!(!is_transient(&anyhow::Error::new(header_error)));
+        assert!(!is_transient(&anyhow::anyhow!(
+            "Failed to read PEM file missing.pem"
+        )));
+        assert!(is_transient(&anyhow::Error::new(BetfairError::Http {
+            status: 503,
+            body: String::new(),
+        })));
+        assert!(!is_transient(&anyhow::Error::new(BetfairError::Login {
+            status: "INVALID_USERNAME_OR_PASSWORD".to_string(),
+            message: None,
+        })));
+        assert_eq!(
+            ApiErrorCode::from("DUPLICATE_APP_NAME"),
+            ApiErrorCode::Other("DUPLICATE_APP_NAME".to_string())
+        );
+    }
+
+    /// Accept connections on a local port, handing each one to `respond`
+    async fn serve<F>(respond: F) -> String
+    where
+        F: Fn(tokio::net::TcpStream) -> tokio::task::JoinHandle<()> + Send + 'static,
+    {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                respond(stream);
+            }
+        });
+        url
+    }
+
+    fn http_client() -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(200))
+            .build()
+            .unwrap()
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) {
+        use tokio::io::AsyncReadExt;
+        let mut buf = [0u8; 1024];
+        let _ = stream.read(&mut buf).await;
+    }
+
+    #[tokio::test]
+    async fn test_refused_connection_is_transient() {
+        let err = http_client()
+            .get("http://127.0.0.1:1/")
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_connect());
+        assert!(BetfairError::Transport(err).is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_is_transient() {
+        // Accept, then hold the connection without ever answering
+        let url = serve(|stream| {
+            tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                drop(stream);
+            })
+        })
+        .await;
+        let err = http_client().get(url).send().await.unwrap_err();
+        assert!(err.is_timeout());
+        assert!(BetfairError::Transport(err).is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_connection_dropped_before_response_is_transient() {
+        let url = serve(|mut stream| {
+            tokio::spawn(async move {
+                read_request(&mut stream).await;
+            })
+        })
+        .await;
+        let err = http_client().get(url).send().await.unwrap_err();
+        assert!(!err.is_connect() && !err.is_timeout());
+        assert!(BetfairError::Transport(err).is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_response_body_is_not_transient() {
+        // The request reached the server, so it may have been executed
+        let url = serve(|mut stream| {
+            tokio::spawn(async move {
+                use tokio::io::AsyncWriteExt;
+                read_request(&mut stream).await;
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\n{\"res")
+                    .await;
+            })
+        })
+        .await;
+        let response = http_client().get(url).send().await.unwrap();
+        let err = response.text().await.unwrap_err();
+        assert!(!BetfairError::Transport(err).is_transient());
+    }
+}
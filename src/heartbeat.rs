@@ -0,0 +1,92 @@
+use crate::api_client::RestClient;
+use crate::dto::heartbeat::HeartbeatReport;
+use anyhow::Result;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Seconds between heartbeats for a timeout, leaving room for two to fail
+pub(crate) fn heartbeat_interval(timeout_seconds: u32) -> Duration {
+    Duration::from_secs(u64::from((timeout_seconds / 3).max(1)))
+}
+
+/// What the background heartbeat task has seen so far
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeartbeatStatus {
+    /// The report of the last successful heartbeat
+    pub last_report: Option<HeartbeatReport>,
+    /// Why the latest heartbeat failed; cleared by the next success
+    pub last_error: Option<String>,
+    /// Heartbeats failed in a row since the last success
+    pub consecutive_failures: u32,
+}
+
+/// A background heartbeat task keeping Betfair's dead man's switch armed.
+///
+/// `stop` disarms the switch. Dropping the handle only ends the heartbeats, so Betfair
+/// cancels the unmatched bets once the timeout passes, as it would after a crash.
+pub struct HeartbeatHandle {
+    task: JoinHandle<()>,
+    reports: watch::Receiver<HeartbeatStatus>,
+    client: RestClient,
+}
+
+impl HeartbeatHandle {
+    pub(crate) fn new(
+        task: JoinHandle<()>,
+        reports: watch::Receiver<HeartbeatStatus>,
+        client: RestClient,
+    ) -> Self {
+        Self {
+            task,
+            reports,
+            client,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// The report of the last successful heartbeat
+    pub fn latest_report(&self) -> Option<HeartbeatReport> {
+        self.reports.borrow().last_report.clone()
+    }
+
+    /// The outcome of the heartbeats so far, including the latest failure
+    pub fn status(&self) -> HeartbeatStatus {
+        self.reports.borrow().clone()
+    }
+
+    /// A receiver notified of every heartbeat, successful or not
+    pub fn subscribe(&self) -> watch::Receiver<HeartbeatStatus> {
+        self.reports.clone()
+    }
+
+    /// Stop the heartbeats and disarm the switch, so unmatched bets stay open.
+    ///
+    /// The task is awaited first, so no heartbeat of its own can re-arm the switch after.
+    pub async fn stop(mut self) -> Result<HeartbeatReport> {
+        self.task.abort();
+        let _ = (&mut self.task).await;
+        self.client.heartbeat(Duration::ZERO).await
+    }
+}
+
+impl Drop for HeartbeatHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_interval() {
+        assert_eq!(heartbeat_interval(30), Duration::from_secs(10));
+        assert_eq!(heartbeat_interval(10), Duration::from_secs(3));
+        assert_eq!(heartbeat_interval(0), Duration::from_secs(1));
+    }
+}
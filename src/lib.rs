@@ -93,15 +93,22 @@ pub mod api_client;
 pub mod config;
 pub mod connection_state;
 pub mod dto;
+pub mod error;
+pub mod heartbeat;
+pub mod market_cache;
 pub mod msg_model;
 pub mod order;
 pub mod order_cache;
 pub mod orderbook;
 mod public_data;
 mod rate_limiter;
+mod request_weight;
 mod retry;
+pub mod session;
+pub mod stream_transport;
 mod streamer;
 pub mod streaming_client;
+mod subscriptions;
 pub mod unified_client;
 
 static CRYPTO_PROVIDER_INIT: Once = Once::new();
@@ -114,6 +121,7 @@ pub(crate) fn ensure_crypto_provider() {
 
 pub use api_client::RestClient;
 pub use config::Config;
+pub use error::BetfairError;
 pub use streaming_client::StreamingClient;
 pub use unified_client::BetfairClient;
 
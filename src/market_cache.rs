@@ -0,0 +1,464 @@
+use crate::dto::common::{MarketStatus, RunnerStatus};
+use crate::dto::streaming::{
+    MarketChange, MarketDefinition, RunnerChange, RunnerDefinition, RunnerKey,
+};
+use crate::orderbook::Orderbook;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Market caches keyed by market ID, updated one market change at a time
+pub type SharedMarketCaches = Arc<RwLock<HashMap<String, MarketCache>>>;
+
+/// Everything the stream has told us about one market
+#[derive(Debug, Clone)]
+pub struct MarketCache {
+    pub market_id: String,
+    pub definition: Option<MarketDefinition>,
+    pub runners: HashMap<RunnerKey, RunnerCache>,
+    /// Runners on the key line of an Asian handicap or line market
+    pub key_line: Vec<RunnerKey>,
+    pub last_update: i64,
+}
+
+/// One runner's definition and book; either may be missing until the stream sends it
+#[derive(Debug, Clone)]
+pub struct RunnerCache {
+    pub key: RunnerKey,
+    pub definition: Option<RunnerDefinition>,
+    pub book: Orderbook,
+}
+
+impl MarketCache {
+    pub fn new(market_id: String) -> Self {
+        Self {
+            market_id,
+            definition: None,
+            runners: HashMap::new(),
+            key_line: Vec::new(),
+            last_update: 0,
+        }
+    }
+
+    pub fn update_timestamp(&mut self, timestamp: i64) {
+        self.last_update = timestamp;
+    }
+
+    /// Apply one `mc` entry: the market definition first, then runner changes.
+    ///
+    /// With `img` set the change is the full market state, so every book is rebuilt
+    /// from it instead of merged. Runners it omits are dropped, unless the market
+    /// definition lists them, in which case they start again with empty books.
+    pub fn apply_market_change(&mut self, market_change: &MarketChange, publish_time: i64) {
+        if market_change.img.unwrap_or(false) {
+            self.runners.clear();
+            // Re-seed runner definitions when the image carries no market definition
+            if market_change.market_definition.is_none() {
+                if let Some(definition) = self.definition.take() {
+                    self.apply_market_definition(definition);
+                }
+            }
+        }
+
+        if let Some(definition) = &market_change.market_definition {
+            self.apply_market_definition(definition.clone());
+        }
+
+        for runner_change in market_change.runner_changes.iter().flatten() {
+            self.get_runner_mut(runner_change.key())
+                .apply_runner_change(runner_change, publish_time);
+        }
+
+        self.update_timestamp(publish_time);
+    }
+
+    /// Replace the market definition and hand each runner its own definition
+    pub fn apply_market_definition(&mut self, definition: MarketDefinition) {
+        for runner_definition in definition.runners.iter().flatten() {
+            let runner = self.get_runner_mut(runner_definition.key());
+            runner.definition = Some(runner_definition.clone());
+            if runner.is_removed() {
+                // No more prices arrive for a removed runner, so drop whatever was on offer
+                runner.clear_offers();
+            }
+        }
+        self.key_line = definition
+            .key_line_definition
+            .as_ref()
+            .map(|key_line| key_line.runner_keys())
+            .unwrap_or_default();
+        self.definition = Some(definition);
+    }
+
+    pub fn get_runner(&self, key: RunnerKey) -> Option<&RunnerCache> {
+        self.runners.get(&key)
+    }
+
+    pub fn get_runner_mut(&mut self, key: RunnerKey) -> &mut RunnerCache {
+        self.runners
+            .entry(key)
+            .or_insert_with(|| RunnerCache::new(key))
+    }
+
+    /// Every handicap line listed for one selection, lowest handicap first
+    pub fn runner_lines(&self, selection_id: u64) -> Vec<&RunnerCache> {
+        let mut lines: Vec<&RunnerCache> = self
+            .runners
+            .values()
+            .filter(|runner| runner.key.selection_id == selection_id)
+            .collect();
+        lines.sort_by_key(|runner| runner.key.handicap);
+        lines
+    }
+
+    pub fn is_key_line(&self, key: &RunnerKey) -> bool {
+        self.key_line.contains(key)
+    }
+
+    pub fn status(&self) -> Option<&MarketStatus> {
+        self.definition.as_ref()?.status.as_ref()
+    }
+
+    pub fn is_in_play(&self) -> bool {
+        self.definition.as_ref().is_some_and(|d| d.in_play)
+    }
+
+    pub fn bet_delay(&self) -> Option<i32> {
+        self.definition.as_ref()?.bet_delay
+    }
+
+    pub fn version(&self) -> Option<i64> {
+        self.definition.as_ref()?.version
+    }
+
+    /// Runners still taking part, ordered by sort priority
+    pub fn active_runners(&self) -> Vec<&RunnerCache> {
+        let mut runners: Vec<&RunnerCache> = self
+            .runners
+            .values()
+            .filter(|runner| !runner.is_removed())
+            .collect();
+        runners.sort_by_key(|runner| (runner.sort_priority().unwrap_or(i32::MAX), runner.key));
+        runners
+    }
+
+    /// Books keyed by runner, as passed to orderbook callbacks
+    pub fn orderbooks(&self) -> HashMap<RunnerKey, Orderbook> {
+        self.runners
+            .iter()
+            .map(|(key, runner)| (*key, runner.book.clone()))
+            .collect()
+    }
+}
+
+impl RunnerCache {
+    pub fn new(key: RunnerKey) -> Self {
+        Self {
+            key,
+            definition: None,
+            book: Orderbook::new(),
+        }
+    }
+
+    pub fn apply_runner_change(&mut self, runner_change: &RunnerChange, publish_time: i64) {
+        let book = &mut self.book;
+
+        for [level, price, size] in runner_change.available_to_back.iter().flatten() {
+            book.add_bid(level_index(level), *price, *size);
+        }
+        for [level, price, size] in runner_change.available_to_lay.iter().flatten() {
+            book.add_ask(level_index(level), *price, *size);
+        }
+        for [level, price, size] in runner_change
+            .best_display_available_to_back
+            .iter()
+            .flatten()
+        {
+            book.add_display_bid(level_index(level), *price, *size);
+        }
+        for [level, price, size] in runner_change.best_display_available_to_lay.iter().flatten() {
+            book.add_display_ask(level_index(level), *price, *size);
+        }
+
+        let price_ladders = [
+            (
+                &runner_change.all_available_to_back,
+                &mut book.available_to_back,
+            ),
+            (
+                &runner_change.all_available_to_lay,
+                &mut book.available_to_lay,
+            ),
+            (&runner_change.traded, &mut book.traded),
+            (&runner_change.sp_back, &mut book.sp_back),
+            (&runner_change.sp_lay, &mut book.sp_lay),
+        ];
+        for (updates, ladder) in price_ladders {
+            for [price, size] in updates.iter().flatten() {
+                Orderbook::apply_price_size(ladder, *price, *size);
+            }
+        }
+
+        if runner_change.last_traded_price.is_some() {
+            book.last_traded_price = runner_change.last_traded_price;
+        }
+        if runner_change.traded_volume.is_some() {
+            book.traded_volume = runner_change.traded_volume;
+        }
+        if runner_change.sp_near.is_some() {
+            book.sp_near = runner_change.sp_near;
+        }
+        if runner_change.sp_far.is_some() {
+            book.sp_far = runner_change.sp_far;
+        }
+
+        book.set_ts(publish_time);
+    }
+
+    /// Drop all available-to-back/lay prices, keeping traded volume and starting prices
+    pub fn clear_offers(&mut self) {
+        let book = &mut self.book;
+        book.bids.clear();
+        book.asks.clear();
+        book.display_bids.clear();
+        book.display_asks.clear();
+        book.available_to_back.clear();
+        book.available_to_lay.clear();
+    }
+
+    pub fn selection_id(&self) -> u64 {
+        self.key.selection_id
+    }
+
+    pub fn status(&self) -> Option<&RunnerStatus> {
+        self.definition.as_ref()?.status.as_ref()
+    }
+
+    /// Withdrawn from the market, as opposed to active with an empty book
+    pub fn is_removed(&self) -> bool {
+        matches!(
+            self.status(),
+            Some(RunnerStatus::Removed) | Some(RunnerStatus::RemovedVacant)
+        )
+    }
+
+    pub fn handicap(&self) -> Decimal {
+        self.key.handicap
+    }
+
+    pub fn sort_priority(&self) -> Option<i32> {
+        self.definition.as_ref()?.sort_priority
+    }
+
+    pub fn bsp(&self) -> Option<Decimal> {
+        self.definition.as_ref()?.bsp
+    }
+
+    pub fn adjustment_factor(&self) -> Option<Decimal> {
+        self.definition.as_ref()?.adjustment_factor
+    }
+
+    pub fn removal_date(&self) -> Option<&str> {
+        self.definition.as_ref()?.removal_date.as_deref()
+    }
+
+    pub fn last_traded_price(&self) -> Option<Decimal> {
+        self.book.last_traded_price
+    }
+
+    pub fn traded_volume(&self) -> Option<Decimal> {
+        self.book.traded_volume
+    }
+}
+
+fn level_index(level: &Decimal) -> usize {
+    level.to_usize().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market_change(json: &str) -> MarketChange {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_apply_definition_and_runner_changes() {
+        let mut cache = MarketCache::new("1.1".to_string());
+        cache.apply_market_change(
+            &market_change(
+                r#"{"id":"1.1","marketDefinition":{"status":"OPEN","inPlay":true,"betDelay":5,"version":7,"marketTime":null,
+                    "runners":[{"id":10,"status":"ACTIVE","sortPriority":2},{"id":20,"status":"ACTIVE","sortPriority":1}]},
+                    "rc":[{"id":10,"batb":[[0,2.5,10]],"ltp":2.52,"tv":150.5,"trd":[[2.52,150.5]]}]}"#,
+            ),
+            100,
+        );
+
+        assert_eq!(cache.status(), Some(&MarketStatus::Open));
+        assert!(cache.is_in_play());
+        assert_eq!(cache.bet_delay(), Some(5));
+        assert_eq!(cache.version(), Some(7));
+        assert_eq!(cache.last_update, 100);
+
+        let runner = cache.get_runner(RunnerKey::from(10)).unwrap();
+        assert_eq!(runner.book.bids[0].price, Decimal::new(25, 1));
+        assert_eq!(runner.last_traded_price(), Some(Decimal::new(252, 2)));
+        assert_eq!(runner.traded_volume(), Some(Decimal::new(1505, 1)));
+        assert_eq!(runner.book.ts, 100);
+
+        // Runner 20 is known from the definition even though it has no prices yet
+        let runner = cache.get_runner(RunnerKey::from(20)).unwrap();
+        assert!(runner.book.bids.is_empty());
+        assert_eq!(runner.status(), Some(&RunnerStatus::Active));
+
+        let order: Vec<u64> = cache
+            .active_runners()
+            .iter()
+            .map(|r| r.selection_id())
+            .collect();
+        assert_eq!(order, vec![20, 10]);
+    }
+
+    #[test]
+    fn test_removed_runner_distinct_from_empty_book() {
+        let mut cache = MarketCache::new("1.1".to_string());
+        cache.apply_market_change(
+            &market_change(
+                r#"{"id":"1.1","marketDefinition":{"status":"OPEN","inPlay":false,"marketTime":null,
+                    "runners":[{"id":10,"status":"ACTIVE"},{"id":20,"status":"REMOVED","adjustmentFactor":12.5,"removalDate":"2024-01-01T12:00:00.000Z"}]}}"#,
+            ),
+            1,
+        );
+
+        let active = cache.get_runner(RunnerKey::from(10)).unwrap();
+        let removed = cache.get_runner(RunnerKey::from(20)).unwrap();
+        assert!(!active.is_removed());
+        assert!(removed.is_removed());
+        assert_eq!(removed.adjustment_factor(), Some(Decimal::new(125, 1)));
+        assert!(removed.removal_date().is_some());
+        assert_eq!(cache.active_runners().len(), 1);
+    }
+
+    #[test]
+    fn test_handicap_lines_kept_apart() {
+        let mut cache = MarketCache::new("1.1".to_string());
+        cache.apply_market_change(
+            &market_change(
+                r#"{"id":"1.1","marketDefinition":{"status":"OPEN","inPlay":false,"marketTime":null,
+                    "keyLineDefinition":{"kl":[{"id":10,"hc":-0.5},{"id":20,"hc":0.5}]},
+                    "runners":[{"id":10,"hc":-0.5,"status":"ACTIVE"},{"id":10,"hc":-1.0,"status":"ACTIVE"},
+                               {"id":20,"hc":0.5,"status":"ACTIVE"},{"id":20,"hc":1.0,"status":"ACTIVE"}]},
+                    "rc":[{"id":10,"hc":-0.5,"batb":[[0,1.9,10]]},{"id":10,"hc":-1.0,"batb":[[0,2.4,7]]}]}"#,
+            ),
+            1,
+        );
+
+        assert_eq!(cache.runners.len(), 4);
+        let lines = cache.runner_lines(10);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].handicap(), Decimal::new(-1, 0));
+        assert_eq!(lines[0].book.bids[0].price, Decimal::new(24, 1));
+        assert_eq!(lines[1].book.bids[0].price, Decimal::new(19, 1));
+
+        let key_line = RunnerKey::new(10, Some(Decimal::new(-5, 1)));
+        assert!(cache.is_key_line(&key_line));
+        assert!(!cache.is_key_line(&RunnerKey::new(10, Some(Decimal::new(-1, 0)))));
+        assert!(cache.orderbooks().contains_key(&key_line));
+
+        // A new definition moves the key line
+        cache.apply_market_change(
+            &market_change(
+                r#"{"id":"1.1","marketDefinition":{"status":"OPEN","inPlay":false,"marketTime":null,
+                    "keyLineDefinition":{"kl":[{"id":10,"hc":-1.0},{"id":20,"hc":1.0}]}}}"#,
+            ),
+            2,
+        );
+        assert!(!cache.is_key_line(&key_line));
+        assert_eq!(cache.key_line.len(), 2);
+    }
+
+    #[test]
+    fn test_image_delta_image_replaces_books() {
+        let mut cache = MarketCache::new("1.1".to_string());
+
+        let image = r#"{"id":"1.1","img":true,"rc":[
+            {"id":10,"batb":[[0,2.0,5],[1,1.99,8]],"atb":[[2.0,5],[1.99,8]],"trd":[[2.0,20]],"ltp":2.0,"tv":20},
+            {"id":20,"batb":[[0,4.0,3]]}]}"#;
+        cache.apply_market_change(&market_change(image), 1);
+
+        let delta = r#"{"id":"1.1","rc":[{"id":10,"batb":[[2,1.98,4]],"atb":[[1.98,4]]},{"id":30,"batl":[[0,9.0,1]]}]}"#;
+        cache.apply_market_change(&market_change(delta), 2);
+        let runner = cache.get_runner(RunnerKey::from(10)).unwrap();
+        assert_eq!(runner.book.bids.len(), 3);
+        assert_eq!(runner.book.available_to_back.len(), 3);
+        assert_eq!(cache.runners.len(), 3);
+
+        // A later image is the whole state: levels, ladders and runners it omits are gone
+        let image = r#"{"id":"1.1","img":true,"rc":[{"id":10,"batb":[[0,2.02,6]],"atb":[[2.02,6]],"ltp":2.02}]}"#;
+        cache.apply_market_change(&market_change(image), 3);
+
+        assert_eq!(cache.runners.len(), 1);
+        let runner = cache.get_runner(RunnerKey::from(10)).unwrap();
+        assert_eq!(runner.book.bids.len(), 1);
+        assert_eq!(runner.book.bids[0].price, Decimal::new(202, 2));
+        assert_eq!(runner.book.available_to_back.len(), 1);
+        assert!(runner.book.traded.is_empty());
+        assert_eq!(runner.traded_volume(), None);
+        assert_eq!(runner.last_traded_price(), Some(Decimal::new(202, 2)));
+        assert!(cache.get_runner(RunnerKey::from(20)).is_none());
+    }
+
+    #[test]
+    fn test_image_without_definition_keeps_runner_definitions() {
+        let mut cache = MarketCache::new("1.1".to_string());
+        cache.apply_market_change(
+            &market_change(
+                r#"{"id":"1.1","img":true,"marketDefinition":{"status":"OPEN","inPlay":false,"marketTime":null,
+                    "runners":[{"id":10,"status":"ACTIVE"},{"id":20,"status":"ACTIVE"}]},
+                    "rc":[{"id":10,"batb":[[0,2.0,5]]},{"id":20,"batb":[[0,4.0,3]]}]}"#,
+            ),
+            1,
+        );
+
+        cache.apply_market_change(
+            &market_change(r#"{"id":"1.1","img":true,"rc":[{"id":10,"batb":[[0,2.1,5]]}]}"#),
+            2,
+        );
+
+        // Runner 20 is still listed by the definition but its stale book is gone
+        assert_eq!(cache.status(), Some(&MarketStatus::Open));
+        let runner = cache.get_runner(RunnerKey::from(20)).unwrap();
+        assert_eq!(runner.status(), Some(&RunnerStatus::Active));
+        assert!(runner.book.bids.is_empty());
+    }
+
+    #[test]
+    fn test_removed_runner_offers_cleared() {
+        let mut cache = MarketCache::new("1.1".to_string());
+        cache.apply_market_change(
+            &market_change(
+                r#"{"id":"1.1","img":true,"marketDefinition":{"status":"OPEN","inPlay":false,"marketTime":null,
+                    "runners":[{"id":10,"status":"ACTIVE"}]},
+                    "rc":[{"id":10,"batb":[[0,2.0,5]],"atl":[[2.1,3]],"trd":[[2.0,50]],"tv":50}]}"#,
+            ),
+            1,
+        );
+
+        cache.apply_market_change(
+            &market_change(
+                r#"{"id":"1.1","marketDefinition":{"status":"SUSPENDED","inPlay":false,"marketTime":null,
+                    "runners":[{"id":10,"status":"REMOVED","adjustmentFactor":20.0}]}}"#,
+            ),
+            2,
+        );
+
+        let runner = cache.get_runner(RunnerKey::from(10)).unwrap();
+        assert!(runner.is_removed());
+        assert!(runner.book.bids.is_empty());
+        assert!(runner.book.available_to_lay.is_empty());
+        assert_eq!(runner.book.traded.len(), 1);
+        assert_eq!(runner.traded_volume(), Some(Decimal::new(50, 0)));
+    }
+}
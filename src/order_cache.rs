@@ -1,12 +1,26 @@
-use crate::dto::streaming::UnmatchedOrder;
+use crate::dto::common::OrderStatus;
+use crate::dto::streaming::{
+    OrderChange, OrderRunnerChange, RunnerKey, StrategyMatchChange, UnmatchedOrder,
+};
 use rust_decimal::Decimal;
 use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long execution-complete orders stay cached unless configured otherwise
+pub const DEFAULT_COMPLETED_ORDER_RETENTION: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone)]
 pub struct OrderCache {
     pub market_id: String,
-    pub runners: HashMap<u64, RunnerOrders>,
+    pub runners: HashMap<RunnerKey, RunnerOrders>,
     pub last_update: i64,
+    /// Set once the stream reports the market closed; no further changes follow
+    pub closed: bool,
+    /// How long execution-complete orders, and closed markets, are kept in stream time
+    pub retention: Duration,
+    /// Publish time at which each bet id was first seen execution complete; the stream
+    /// carries no void date, so this, not the order's own dates, drives pruning
+    completed_at: HashMap<String, i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -16,14 +30,59 @@ pub struct RunnerOrders {
     pub orders: HashMap<String, UnmatchedOrder>,
     pub matched_backs: HashMap<String, Decimal>,
     pub matched_lays: HashMap<String, Decimal>,
+    /// Matched ladders per customer strategy ref, from `smc`
+    pub strategy_matches: HashMap<String, StrategyMatches>,
+}
+
+/// What happened to an order between two order stream updates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderEventKind {
+    OrderAccepted,
+    PartiallyMatched,
+    FullyMatched,
+    Lapsed,
+    Cancelled,
+    Voided,
+    PriceChanged,
+}
+
+/// One change to one order, derived by diffing its successive stream updates
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderEvent {
+    pub kind: OrderEventKind,
+    pub market_id: String,
+    pub runner: RunnerKey,
+    pub bet_id: String,
+    pub customer_order_ref: Option<String>,
+    pub customer_strategy_ref: Option<String>,
+    pub side: String,
+    pub price: Decimal,
+    /// Size this event accounts for: the placed size when accepted, the newly matched,
+    /// lapsed, cancelled or voided size, or the remaining size after a price change
+    pub size: Decimal,
+    pub publish_time: i64,
+}
+
+/// Matched volume by price for one customer strategy ref on one runner
+#[derive(Debug, Clone, Default)]
+pub struct StrategyMatches {
+    pub matched_backs: HashMap<String, Decimal>,
+    pub matched_lays: HashMap<String, Decimal>,
 }
 
 impl OrderCache {
     pub fn new(market_id: String) -> Self {
+        Self::with_retention(market_id, DEFAULT_COMPLETED_ORDER_RETENTION)
+    }
+
+    pub fn with_retention(market_id: String, retention: Duration) -> Self {
         Self {
             market_id,
             runners: HashMap::new(),
             last_update: 0,
+            closed: false,
+            retention,
+            completed_at: HashMap::new(),
         }
     }
 
@@ -31,14 +90,107 @@ impl OrderCache {
         self.last_update = timestamp;
     }
 
-    pub fn get_runner(&self, selection_id: u64) -> Option<&RunnerOrders> {
-        self.runners.get(&selection_id)
+    /// Apply one `oc` entry, then drop completed orders older than the retention window.
+    ///
+    /// Returns an event for every change to an order the cache already knew about, and
+    /// `OrderAccepted` plus any fills for new ones. The first image of a market is the
+    /// baseline and produces no events.
+    pub fn apply_order_change(
+        &mut self,
+        order_change: &OrderChange,
+        publish_time: i64,
+    ) -> Vec<OrderEvent> {
+        let emit_events = !(order_change.full_image && self.last_update == 0);
+        let previous_runners = if order_change.full_image {
+            self.closed = false;
+            std::mem::take(&mut self.runners)
+        } else {
+            HashMap::new()
+        };
+
+        let mut events = Vec::new();
+        for runner_change in order_change.order_runner_change.iter().flatten() {
+            let key = runner_change.key();
+            let known = previous_runners
+                .get(&key)
+                .or_else(|| self.runners.get(&key));
+            let previous: HashMap<String, UnmatchedOrder> = runner_change
+                .unmatched_orders
+                .iter()
+                .flatten()
+                .filter_map(|order| {
+                    let previous = known?.get_order(&order.id)?;
+                    Some((order.id.clone(), previous.clone()))
+                })
+                .collect();
+
+            self.get_runner_mut(key).apply_runner_change(runner_change);
+            for order in runner_change.unmatched_orders.iter().flatten() {
+                if order.is_complete() {
+                    self.completed_at
+                        .entry(order.id.clone())
+                        .or_insert(publish_time);
+                }
+            }
+
+            if emit_events {
+                for order in runner_change.unmatched_orders.iter().flatten() {
+                    events.extend(order_events(
+                        &self.market_id,
+                        key,
+                        previous.get(&order.id),
+                        order,
+                        publish_time,
+                    ));
+                }
+            }
+        }
+
+        if order_change.closed {
+            self.closed = true;
+        }
+
+        self.update_timestamp(publish_time);
+        self.prune_completed(publish_time);
+        events
+    }
+
+    /// Drop execution-complete orders first seen complete more than the retention window
+    /// before `now`; completed orders the cache has no record of count as completing at `now`
+    pub fn prune_completed(&mut self, now: i64) {
+        let cutoff = now.saturating_sub(self.retention_ms());
+        let completed_at = &mut self.completed_at;
+        for runner in self.runners.values_mut() {
+            runner.orders.retain(|id, order| {
+                !order.is_complete() || *completed_at.entry(id.clone()).or_insert(now) >= cutoff
+            });
+        }
+        let runners = &self.runners;
+        completed_at.retain(|id, _| runners.values().any(|r| r.orders.contains_key(id)));
+    }
+
+    /// A closed market is kept for the retention window after its last update, then dropped
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.closed && now.saturating_sub(self.last_update) > self.retention_ms()
     }
 
-    pub fn get_runner_mut(&mut self, selection_id: u64) -> &mut RunnerOrders {
-        self.runners
-            .entry(selection_id)
-            .or_insert_with(|| RunnerOrders::new(selection_id))
+    fn retention_ms(&self) -> i64 {
+        i64::try_from(self.retention.as_millis()).unwrap_or(i64::MAX)
+    }
+
+    pub fn get_runner(&self, key: RunnerKey) -> Option<&RunnerOrders> {
+        self.runners.get(&key)
+    }
+
+    /// Get or create a runner; different handicaps of one selection are separate runners
+    pub fn get_runner_mut(&mut self, key: RunnerKey) -> &mut RunnerOrders {
+        self.runners.entry(key).or_insert_with(|| {
+            let mut runner = RunnerOrders::new(key.selection_id);
+            if !key.handicap.is_zero() {
+                runner.set_handicap(Some(key.handicap));
+            }
+            runner
+        })
     }
 
     pub fn get_all_orders(&self) -> Vec<&UnmatchedOrder> {
@@ -49,14 +201,56 @@ impl OrderCache {
     }
 
     pub fn get_active_orders(&self) -> Vec<&UnmatchedOrder> {
+        self.get_orders_by_status(&OrderStatus::Executable)
+    }
+
+    /// Orders that fully matched, lapsed, were cancelled or voided within the retention window
+    pub fn get_completed_orders(&self) -> Vec<&UnmatchedOrder> {
+        self.get_orders_by_status(&OrderStatus::ExecutionComplete)
+    }
+
+    pub fn get_orders_by_status(&self, status: &OrderStatus) -> Vec<&UnmatchedOrder> {
+        self.get_all_orders()
+            .into_iter()
+            .filter(|o| o.order_status().as_ref() == Some(status))
+            .collect()
+    }
+
+    /// Orders placed with this customer order ref (`rfo`)
+    pub fn get_orders_by_customer_ref(&self, customer_order_ref: &str) -> Vec<&UnmatchedOrder> {
+        self.get_all_orders()
+            .into_iter()
+            .filter(|o| o.rfo.as_deref() == Some(customer_order_ref))
+            .collect()
+    }
+
+    /// Orders placed with this customer strategy ref (`rfs`)
+    pub fn get_orders_by_strategy(&self, customer_strategy_ref: &str) -> Vec<&UnmatchedOrder> {
         self.get_all_orders()
             .into_iter()
-            .filter(|o| o.status == "E")
+            .filter(|o| o.rfs.as_deref() == Some(customer_strategy_ref))
+            .collect()
+    }
+
+    /// Matched ladders of one customer strategy ref on every runner it has matched on
+    pub fn get_strategy_matches(
+        &self,
+        customer_strategy_ref: &str,
+    ) -> Vec<(RunnerKey, &StrategyMatches)> {
+        self.runners
+            .iter()
+            .filter_map(|(key, runner)| {
+                runner
+                    .strategy_matches
+                    .get(customer_strategy_ref)
+                    .map(|matches| (*key, matches))
+            })
             .collect()
     }
 
     pub fn clear(&mut self) {
         self.runners.clear();
+        self.completed_at.clear();
     }
 }
 
@@ -68,6 +262,7 @@ impl RunnerOrders {
             orders: HashMap::new(),
             matched_backs: HashMap::new(),
             matched_lays: HashMap::new(),
+            strategy_matches: HashMap::new(),
         }
     }
 
@@ -75,6 +270,38 @@ impl RunnerOrders {
         self.handicap = handicap;
     }
 
+    /// Apply one `orc` entry; with `fullImage` set it replaces the runner's orders and ladders
+    pub fn apply_runner_change(&mut self, runner_change: &OrderRunnerChange) {
+        self.set_handicap(runner_change.handicap);
+
+        if runner_change.full_image {
+            // The image also carries the full matched ladders
+            self.clear_matched_backs();
+            self.clear_matched_lays();
+            self.strategy_matches.clear();
+            self.apply_full_image(runner_change.unmatched_orders.clone().unwrap_or_default());
+        } else if let Some(ref orders) = runner_change.unmatched_orders {
+            for order in orders {
+                self.update_order(order.clone());
+            }
+        }
+
+        if let Some(ref matched_backs) = runner_change.matched_backs {
+            self.update_matched_backs(matched_backs.clone());
+        }
+
+        if let Some(ref matched_lays) = runner_change.matched_lays {
+            self.update_matched_lays(matched_lays.clone());
+        }
+
+        for (strategy, change) in runner_change.strategy_matches.iter().flatten() {
+            self.strategy_matches
+                .entry(strategy.clone())
+                .or_default()
+                .apply_change(change);
+        }
+    }
+
     pub fn apply_full_image(&mut self, orders: Vec<UnmatchedOrder>) {
         self.orders.clear();
         for order in orders {
@@ -82,40 +309,17 @@ impl RunnerOrders {
         }
     }
 
+    /// Insert or replace an order; execution-complete orders stay until pruned
     pub fn update_order(&mut self, order: UnmatchedOrder) {
-        if order.status == "EC" {
-            self.orders.remove(&order.id);
-        } else {
-            self.orders.insert(order.id.clone(), order);
-        }
+        self.orders.insert(order.id.clone(), order);
     }
 
     pub fn update_matched_backs(&mut self, matched_backs: Vec<Vec<Decimal>>) {
-        for entry in matched_backs {
-            if entry.len() >= 2 {
-                let price = entry[0].to_string();
-                let size = entry[1];
-                if size.is_zero() {
-                    self.matched_backs.remove(&price);
-                } else {
-                    self.matched_backs.insert(price, size);
-                }
-            }
-        }
+        apply_matched_ladder(&mut self.matched_backs, matched_backs);
     }
 
     pub fn update_matched_lays(&mut self, matched_lays: Vec<Vec<Decimal>>) {
-        for entry in matched_lays {
-            if entry.len() >= 2 {
-                let price = entry[0].to_string();
-                let size = entry[1];
-                if size.is_zero() {
-                    self.matched_lays.remove(&price);
-                } else {
-                    self.matched_lays.insert(price, size);
-                }
-            }
-        }
+        apply_matched_ladder(&mut self.matched_lays, matched_lays);
     }
 
     pub fn clear_matched_backs(&mut self) {
@@ -139,11 +343,121 @@ impl RunnerOrders {
     }
 }
 
+impl StrategyMatches {
+    pub fn apply_change(&mut self, change: &StrategyMatchChange) {
+        if let Some(ref matched_backs) = change.matched_backs {
+            apply_matched_ladder(&mut self.matched_backs, matched_backs.clone());
+        }
+        if let Some(ref matched_lays) = change.matched_lays {
+            apply_matched_ladder(&mut self.matched_lays, matched_lays.clone());
+        }
+    }
+
+    pub fn get_total_back_matched(&self) -> Decimal {
+        self.matched_backs.values().sum()
+    }
+
+    pub fn get_total_lay_matched(&self) -> Decimal {
+        self.matched_lays.values().sum()
+    }
+}
+
+/// Events explaining how `order` differs from its `previous` state, in the order they happened
+fn order_events(
+    market_id: &str,
+    runner: RunnerKey,
+    previous: Option<&UnmatchedOrder>,
+    order: &UnmatchedOrder,
+    publish_time: i64,
+) -> Vec<OrderEvent> {
+    let mut changes = Vec::new();
+    match previous {
+        None => changes.push((OrderEventKind::OrderAccepted, order.s)),
+        Some(previous) if previous.p != order.p => {
+            changes.push((OrderEventKind::PriceChanged, order.sr.unwrap_or(order.s)))
+        }
+        Some(_) => {}
+    }
+
+    let matched = size_delta(previous, order, |o| o.sm);
+    let lapsed = size_delta(previous, order, |o| o.sl);
+    let cancelled = size_delta(previous, order, |o| o.sc);
+    let voided = size_delta(previous, order, |o| o.sv);
+
+    if matched > Decimal::ZERO {
+        // Completed by this fill rather than by lapsing, cancelling or voiding the rest
+        let fully_matched = order.is_complete()
+            && order.sr.unwrap_or_default().is_zero()
+            && lapsed <= Decimal::ZERO
+            && cancelled <= Decimal::ZERO
+            && voided <= Decimal::ZERO;
+        let kind = if fully_matched {
+            OrderEventKind::FullyMatched
+        } else {
+            OrderEventKind::PartiallyMatched
+        };
+        changes.push((kind, matched));
+    }
+    for (kind, size) in [
+        (OrderEventKind::Lapsed, lapsed),
+        (OrderEventKind::Cancelled, cancelled),
+        (OrderEventKind::Voided, voided),
+    ] {
+        if size > Decimal::ZERO {
+            changes.push((kind, size));
+        }
+    }
+
+    changes
+        .into_iter()
+        .map(|(kind, size)| OrderEvent {
+            kind,
+            market_id: market_id.to_string(),
+            runner,
+            bet_id: order.id.clone(),
+            customer_order_ref: order.rfo.clone(),
+            customer_strategy_ref: order.rfs.clone(),
+            side: order.side.clone(),
+            price: order.p,
+            size,
+            publish_time,
+        })
+        .collect()
+}
+
+/// Growth of one cumulative size field (`sm`, `sl`, `sc`, `sv`) since the previous update
+fn size_delta(
+    previous: Option<&UnmatchedOrder>,
+    order: &UnmatchedOrder,
+    field: fn(&UnmatchedOrder) -> Option<Decimal>,
+) -> Decimal {
+    field(order).unwrap_or_default() - previous.and_then(field).unwrap_or_default()
+}
+
+/// Merge `[price, size]` pairs into a matched ladder; a zero size removes the price
+fn apply_matched_ladder(ladder: &mut HashMap<String, Decimal>, updates: Vec<Vec<Decimal>>) {
+    for entry in updates {
+        if entry.len() >= 2 {
+            let price = entry[0].to_string();
+            let size = entry[1];
+            if size.is_zero() {
+                ladder.remove(&price);
+            } else {
+                ladder.insert(price, size);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rust_decimal_macros::dec;
 
+    fn order_change(json: &str) -> OrderChange {
+        serde_json::from_str(json).unwrap()
+    }
+
     fn create_test_order(id: &str, price: Decimal, size: Decimal, status: &str) -> UnmatchedOrder {
         UnmatchedOrder {
             id: id.to_string(),
@@ -201,7 +515,7 @@ mod tests {
     }
 
     #[test]
-    fn test_remove_completed_order() {
+    fn test_completed_order_retained() {
         let mut runner = RunnerOrders::new(12345);
         let order1 = create_test_order("bet1", dec!(2.0), dec!(10.0), "E");
         let order2 = create_test_order("bet1", dec!(2.0), dec!(10.0), "EC");
@@ -210,7 +524,8 @@ mod tests {
         assert_eq!(runner.orders.len(), 1);
 
         runner.update_order(order2);
-        assert_eq!(runner.orders.len(), 0);
+        assert_eq!(runner.orders.len(), 1);
+        assert!(runner.get_order("bet1").unwrap().is_complete());
     }
 
     #[test]
@@ -273,8 +588,12 @@ mod tests {
         let order1 = create_test_order("bet1", dec!(2.0), dec!(10.0), "E");
         let order2 = create_test_order("bet2", dec!(3.0), dec!(20.0), "E");
 
-        cache.get_runner_mut(12345).update_order(order1);
-        cache.get_runner_mut(12346).update_order(order2);
+        cache
+            .get_runner_mut(RunnerKey::from(12345))
+            .update_order(order1);
+        cache
+            .get_runner_mut(RunnerKey::from(12346))
+            .update_order(order2);
 
         let all_orders = cache.get_all_orders();
         assert_eq!(all_orders.len(), 2);
@@ -286,8 +605,12 @@ mod tests {
         let order1 = create_test_order("bet1", dec!(2.0), dec!(10.0), "E");
         let order2 = create_test_order("bet2", dec!(3.0), dec!(20.0), "EC");
 
-        cache.get_runner_mut(12345).update_order(order1);
-        cache.get_runner_mut(12345).update_order(order2);
+        cache
+            .get_runner_mut(RunnerKey::from(12345))
+            .update_order(order1);
+        cache
+            .get_runner_mut(RunnerKey::from(12345))
+            .update_order(order2);
 
         let active_orders = cache.get_active_orders();
         assert_eq!(active_orders.len(), 1);
@@ -313,10 +636,281 @@ mod tests {
         let mut cache = OrderCache::new("1.123456".to_string());
         let order = create_test_order("bet1", dec!(2.0), dec!(10.0), "E");
 
-        cache.get_runner_mut(12345).update_order(order);
+        cache
+            .get_runner_mut(RunnerKey::from(12345))
+            .update_order(order);
         assert_eq!(cache.runners.len(), 1);
 
         cache.clear();
         assert_eq!(cache.runners.len(), 0);
     }
+
+    #[test]
+    fn test_handicap_lines_are_separate_runners() {
+        let mut cache = OrderCache::new("1.123456".to_string());
+        let minus = RunnerKey::new(12345, Some(dec!(-1.5)));
+        let plus = RunnerKey::new(12345, Some(dec!(1.5)));
+
+        cache.get_runner_mut(minus).update_order(create_test_order(
+            "bet1",
+            dec!(2.0),
+            dec!(10.0),
+            "E",
+        ));
+        cache.get_runner_mut(plus).update_order(create_test_order(
+            "bet2",
+            dec!(1.8),
+            dec!(5.0),
+            "E",
+        ));
+
+        assert_eq!(cache.runners.len(), 2);
+        assert!(cache.get_runner(minus).unwrap().get_order("bet1").is_some());
+        assert!(cache.get_runner(plus).unwrap().get_order("bet1").is_none());
+        assert_eq!(cache.get_runner(plus).unwrap().handicap, Some(dec!(1.5)));
+        assert!(cache.get_runner(RunnerKey::from(12345)).is_none());
+    }
+
+    #[test]
+    fn test_completed_orders_pruned_after_retention() {
+        let mut cache = OrderCache::with_retention("1.1".to_string(), Duration::from_secs(10));
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"B","status":"E","pt":"L","ot":"L","pd":1000,"sr":10,"rfo":"ref1","rfs":"alpha"},
+                    {"id":"bet2","p":3.0,"s":5,"side":"L","status":"E","pt":"L","ot":"L","pd":1000,"sr":5,"rfo":"ref2","rfs":"beta"}]}]}"#,
+            ),
+            1000,
+        );
+
+        // bet1 fully matches at t=2000
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"B","status":"EC","pt":"L","ot":"L","pd":1000,"md":2000,"sm":10,"sr":0,"rfo":"ref1","rfs":"alpha"}]}]}"#,
+            ),
+            2000,
+        );
+
+        assert_eq!(cache.get_active_orders().len(), 1);
+        let completed = cache.get_completed_orders();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].id, "bet1");
+        assert_eq!(cache.get_orders_by_customer_ref("ref1")[0].id, "bet1");
+        assert_eq!(cache.get_orders_by_strategy("beta")[0].id, "bet2");
+        assert!(cache.get_orders_by_customer_ref("missing").is_empty());
+
+        cache.prune_completed(12_000);
+        assert_eq!(cache.get_completed_orders().len(), 1);
+
+        cache.prune_completed(12_001);
+        assert!(cache.get_completed_orders().is_empty());
+        assert_eq!(cache.get_all_orders().len(), 1);
+    }
+
+    #[test]
+    fn test_voided_orders_retained_from_when_first_seen_complete() {
+        let mut cache = OrderCache::with_retention("1.1".to_string(), Duration::from_secs(10));
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"B","status":"E","pt":"L","ot":"L","pd":1000,"sr":10},
+                    {"id":"bet2","p":3.0,"s":5,"side":"L","status":"E","pt":"L","ot":"L","pd":1000,"md":2000,"sm":2,"sr":3}]}]}"#,
+            ),
+            2000,
+        );
+
+        // Long after placement and the partial fill, both are voided; the stream has no void date
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"B","status":"EC","pt":"L","ot":"L","pd":1000,"sv":10,"sr":0},
+                    {"id":"bet2","p":3.0,"s":5,"side":"L","status":"EC","pt":"L","ot":"L","pd":1000,"md":2000,"sm":2,"sv":3,"sr":0}]}]}"#,
+            ),
+            60_000,
+        );
+        assert_eq!(cache.get_completed_orders().len(), 2);
+
+        // A later update repeating a completed order does not restart its window
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"B","status":"EC","pt":"L","ot":"L","pd":1000,"sv":10,"sr":0}]}]}"#,
+            ),
+            65_000,
+        );
+
+        cache.prune_completed(70_000);
+        assert_eq!(cache.get_completed_orders().len(), 2);
+
+        cache.prune_completed(70_001);
+        assert!(cache.get_all_orders().is_empty());
+    }
+
+    #[test]
+    fn test_strategy_matches_applied() {
+        let mut cache = OrderCache::new("1.1".to_string());
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"mb":[[2.0,15]],"smc":{"alpha":{"mb":[[2.0,10]]},"beta":{"mb":[[2.0,5]],"ml":[[3.0,4]]}}}]}"#,
+            ),
+            1,
+        );
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"smc":{"alpha":{"mb":[[2.0,0],[2.2,6]]}}}]}"#,
+            ),
+            2,
+        );
+
+        let alpha = cache.get_strategy_matches("alpha");
+        assert_eq!(alpha.len(), 1);
+        assert_eq!(alpha[0].0, RunnerKey::from(10));
+        assert_eq!(alpha[0].1.matched_backs.len(), 1);
+        assert_eq!(alpha[0].1.get_total_back_matched(), dec!(6));
+
+        let beta = cache.get_strategy_matches("beta");
+        assert_eq!(beta[0].1.get_total_back_matched(), dec!(5));
+        assert_eq!(beta[0].1.get_total_lay_matched(), dec!(4));
+
+        // A runner image replaces the strategy ladders too
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"fullImage":true,"smc":{"beta":{"ml":[[3.0,4]]}}}]}"#,
+            ),
+            3,
+        );
+        assert!(cache.get_strategy_matches("alpha").is_empty());
+        assert_eq!(
+            cache.get_strategy_matches("beta")[0]
+                .1
+                .get_total_back_matched(),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_closed_market_expires_after_retention() {
+        let mut cache = OrderCache::with_retention("1.1".to_string(), Duration::from_secs(5));
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"B","status":"EC","pt":"L","ot":"L","pd":1000,"ld":1500,"sl":10,"sr":0}]}]}"#,
+            ),
+            1500,
+        );
+        assert!(!cache.closed);
+
+        cache.apply_order_change(&order_change(r#"{"id":"1.1","closed":true}"#), 2000);
+        assert!(cache.closed);
+        assert_eq!(cache.get_completed_orders().len(), 1);
+        assert!(!cache.is_expired(7000));
+        assert!(cache.is_expired(7001));
+
+        // A fresh image reopens the market
+        cache.apply_order_change(&order_change(r#"{"id":"1.1","fullImage":true}"#), 3000);
+        assert!(!cache.closed);
+        assert!(cache.runners.is_empty());
+    }
+
+    #[test]
+    fn test_order_events_from_deltas() {
+        let mut cache = OrderCache::new("1.1".to_string());
+        let events = cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","fullImage":true,"orc":[{"id":10,"uo":[
+                    {"id":"old","p":5.0,"s":2,"side":"B","status":"E","pt":"L","ot":"L","pd":1,"sr":2}]}]}"#,
+            ),
+            1,
+        );
+        assert!(events.is_empty(), "the first image is only a baseline");
+
+        let events = cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"B","status":"E","pt":"L","ot":"L","pd":2,"sm":4,"sr":6,"rfo":"ref1","rfs":"alpha"}]}]}"#,
+            ),
+            2,
+        );
+        let kinds: Vec<_> = events.iter().map(|e| (e.kind, e.size)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (OrderEventKind::OrderAccepted, dec!(10)),
+                (OrderEventKind::PartiallyMatched, dec!(4))
+            ]
+        );
+        assert_eq!(events[0].bet_id, "bet1");
+        assert_eq!(events[0].customer_order_ref.as_deref(), Some("ref1"));
+        assert_eq!(events[0].customer_strategy_ref.as_deref(), Some("alpha"));
+        assert_eq!(events[0].runner, RunnerKey::from(10));
+        assert_eq!(events[0].market_id, "1.1");
+
+        let events = cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"B","status":"EC","pt":"L","ot":"L","pd":2,"md":3,"sm":10,"sr":0,"rfo":"ref1"},
+                    {"id":"old","p":5.0,"s":2,"side":"B","status":"EC","pt":"L","ot":"L","pd":1,"cd":3,"sm":0.5,"sc":1.5,"sr":0}]}]}"#,
+            ),
+            3,
+        );
+        let kinds: Vec<_> = events
+            .iter()
+            .map(|e| (e.bet_id.as_str(), e.kind, e.size))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("bet1", OrderEventKind::FullyMatched, dec!(6)),
+                ("old", OrderEventKind::PartiallyMatched, dec!(0.5)),
+                ("old", OrderEventKind::Cancelled, dec!(1.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_order_events_lapse_void_and_price_change() {
+        let mut cache = OrderCache::new("1.1".to_string());
+        cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"hc":-1.5,"uo":[
+                    {"id":"bet1","p":2.0,"s":10,"side":"L","status":"E","pt":"L","ot":"L","pd":1,"sr":10},
+                    {"id":"bet2","p":3.0,"s":4,"side":"B","status":"E","pt":"L","ot":"L","pd":1,"sm":4,"sr":0}]}]}"#,
+            ),
+            1,
+        );
+
+        // A runner image is diffed against what was cached before it
+        let events = cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"hc":-1.5,"fullImage":true,"uo":[
+                    {"id":"bet1","p":2.2,"s":10,"side":"L","status":"E","pt":"L","ot":"L","pd":1,"sr":10},
+                    {"id":"bet2","p":3.0,"s":4,"side":"B","status":"EC","pt":"L","ot":"L","pd":1,"sm":0,"sv":4,"sr":0}]}]}"#,
+            ),
+            2,
+        );
+        let kinds: Vec<_> = events
+            .iter()
+            .map(|e| (e.bet_id.as_str(), e.kind, e.size))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("bet1", OrderEventKind::PriceChanged, dec!(10)),
+                ("bet2", OrderEventKind::Voided, dec!(4)),
+            ]
+        );
+        assert_eq!(events[0].runner, RunnerKey::new(10, Some(dec!(-1.5))));
+
+        let events = cache.apply_order_change(
+            &order_change(
+                r#"{"id":"1.1","orc":[{"id":10,"hc":-1.5,"uo":[
+                    {"id":"bet1","p":2.2,"s":10,"side":"L","status":"EC","pt":"L","ot":"L","pd":1,"ld":3,"sl":10,"sr":0}]}]}"#,
+            ),
+            3,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, OrderEventKind::Lapsed);
+        assert_eq!(events[0].size, dec!(10));
+    }
 }
@@ -1,10 +1,33 @@
 use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+/// Price-keyed ladder (price -> size), ascending by price
+pub type PriceLadder = BTreeMap<Decimal, Decimal>;
 
 #[derive(Debug, Clone, Default)]
 pub struct Orderbook {
     pub ts: i64,
     pub bids: Vec<PriceLevel>,
     pub asks: Vec<PriceLevel>,
+    /// Best display prices to back, including virtual bets (`bdatb`)
+    pub display_bids: Vec<PriceLevel>,
+    /// Best display prices to lay, including virtual bets (`bdatl`)
+    pub display_asks: Vec<PriceLevel>,
+    /// Full depth available to back (`atb`)
+    pub available_to_back: PriceLadder,
+    /// Full depth available to lay (`atl`)
+    pub available_to_lay: PriceLadder,
+    /// Matched volume by price (`trd`)
+    pub traded: PriceLadder,
+    pub last_traded_price: Option<Decimal>,
+    /// Total matched on the runner (`tv`)
+    pub traded_volume: Option<Decimal>,
+    pub sp_near: Option<Decimal>,
+    pub sp_far: Option<Decimal>,
+    /// Starting price back ladder (`spb`)
+    pub sp_back: PriceLadder,
+    /// Starting price lay ladder (`spl`)
+    pub sp_lay: PriceLadder,
 }
 
 #[derive(Debug, Clone)]
@@ -24,50 +47,27 @@ impl Orderbook {
     }
 
     pub fn add_bid(&mut self, level: usize, price: Decimal, size: Decimal) {
-        if size.is_zero() {
-            // Remove the level if size is 0
-            if let Some(index) = self.bids.iter().position(|l| l.level == level) {
-                self.bids.remove(index);
-            }
-        } else {
-            // Find if level exists
-            if let Some(index) = self.bids.iter().position(|l| l.level == level) {
-                // Update existing level
-                self.bids[index] = PriceLevel { level, price, size };
-            } else {
-                // Insert new level maintaining order
-                let insert_pos = self
-                    .bids
-                    .iter()
-                    .position(|l| l.level > level)
-                    .unwrap_or(self.bids.len());
-                self.bids
-                    .insert(insert_pos, PriceLevel { level, price, size });
-            }
-        }
+        apply_level(&mut self.bids, level, price, size);
     }
 
     pub fn add_ask(&mut self, level: usize, price: Decimal, size: Decimal) {
+        apply_level(&mut self.asks, level, price, size);
+    }
+
+    pub fn add_display_bid(&mut self, level: usize, price: Decimal, size: Decimal) {
+        apply_level(&mut self.display_bids, level, price, size);
+    }
+
+    pub fn add_display_ask(&mut self, level: usize, price: Decimal, size: Decimal) {
+        apply_level(&mut self.display_asks, level, price, size);
+    }
+
+    /// Apply a `[price, size]` update to a price-keyed ladder; a zero size removes the price
+    pub fn apply_price_size(ladder: &mut PriceLadder, price: Decimal, size: Decimal) {
         if size.is_zero() {
-            // Remove the level if size is 0
-            if let Some(index) = self.asks.iter().position(|l| l.level == level) {
-                self.asks.remove(index);
-            }
+            ladder.remove(&price);
         } else {
-            // Find if level exists
-            if let Some(index) = self.asks.iter().position(|l| l.level == level) {
-                // Update existing level
-                self.asks[index] = PriceLevel { level, price, size };
-            } else {
-                // Insert new level maintaining order
-                let insert_pos = self
-                    .asks
-                    .iter()
-                    .position(|l| l.level > level)
-                    .unwrap_or(self.asks.len());
-                self.asks
-                    .insert(insert_pos, PriceLevel { level, price, size });
-            }
+            ladder.insert(price, size);
         }
     }
 
@@ -99,6 +99,25 @@ impl Orderbook {
     }
 }
 
+/// Set a level of a level-keyed ladder, keeping levels in order; a zero size removes it
+fn apply_level(levels: &mut Vec<PriceLevel>, level: usize, price: Decimal, size: Decimal) {
+    let existing = levels.iter().position(|l| l.level == level);
+    match existing {
+        Some(index) if size.is_zero() => {
+            levels.remove(index);
+        }
+        Some(index) => levels[index] = PriceLevel { level, price, size },
+        None if size.is_zero() => {}
+        None => {
+            let insert_pos = levels
+                .iter()
+                .position(|l| l.level > level)
+                .unwrap_or(levels.len());
+            levels.insert(insert_pos, PriceLevel { level, price, size });
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -279,6 +298,36 @@ mod tests {
         assert_eq!(ob.asks.len(), 0);
     }
 
+    #[test]
+    fn test_apply_price_size() {
+        use rust_decimal_macros::dec;
+        let mut ob = Orderbook::new();
+
+        Orderbook::apply_price_size(&mut ob.available_to_back, dec!(2.0), dec!(10.0));
+        Orderbook::apply_price_size(&mut ob.available_to_back, dec!(1.98), dec!(5.0));
+        Orderbook::apply_price_size(&mut ob.available_to_back, dec!(2.0), dec!(12.5));
+        assert_eq!(ob.available_to_back.len(), 2);
+        assert_eq!(ob.available_to_back[&dec!(2.0)], dec!(12.5));
+
+        Orderbook::apply_price_size(&mut ob.available_to_back, dec!(1.98), dec!(0));
+        assert_eq!(ob.available_to_back.len(), 1);
+        assert!(!ob.available_to_back.contains_key(&dec!(1.98)));
+    }
+
+    #[test]
+    fn test_orderbook_display_levels() {
+        use rust_decimal_macros::dec;
+        let mut ob = Orderbook::new();
+
+        ob.add_display_bid(1, dec!(1.9), dec!(20.0));
+        ob.add_display_bid(0, dec!(2.0), dec!(10.0));
+        ob.add_display_ask(0, dec!(2.1), dec!(0.0));
+
+        assert_eq!(ob.display_bids[0].price, dec!(2.0));
+        assert_eq!(ob.display_bids[1].price, dec!(1.9));
+        assert!(ob.display_asks.is_empty());
+    }
+
     #[test]
     fn test_orderbook_maintain_order() {
         use rust_decimal_macros::dec;
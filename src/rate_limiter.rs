@@ -47,6 +47,11 @@ impl RateLimiter {
         Self::new(60, 1.0) // 60 tokens, 1 token per second
     }
 
+    /// Create rate limiter for heartbeats, kept apart so other traffic cannot delay them
+    pub fn for_heartbeat_requests() -> Self {
+        Self::new(10, 1.0) // 10 tokens, 1 token per second
+    }
+
     /// Acquire a token, waiting if necessary
     pub async fn acquire(&self) -> Result<()> {
         self.acquire_tokens(1.0).await
@@ -165,6 +170,7 @@ pub struct BetfairRateLimiter {
     data_limiter: RateLimiter,
     navigation_limiter: RateLimiter,
     transaction_limiter: RateLimiter,
+    heartbeat_limiter: RateLimiter,
 }
 
 impl BetfairRateLimiter {
@@ -173,6 +179,7 @@ impl BetfairRateLimiter {
             data_limiter: RateLimiter::for_data_requests(),
             navigation_limiter: RateLimiter::for_navigation_requests(),
             transaction_limiter: RateLimiter::for_transaction_requests(),
+            heartbeat_limiter: RateLimiter::for_heartbeat_requests(),
         }
     }
 
@@ -187,6 +194,10 @@ impl BetfairRateLimiter {
     pub async fn acquire_for_transaction(&self) -> Result<()> {
         self.transaction_limiter.acquire().await
     }
+
+    pub async fn acquire_for_heartbeat(&self) -> Result<()> {
+        self.heartbeat_limiter.acquire().await
+    }
 }
 
 impl Default for BetfairRateLimiter {
@@ -245,6 +256,7 @@ mod tests {
         assert!(limiter.acquire_for_data().await.is_ok());
         assert!(limiter.acquire_for_navigation().await.is_ok());
         assert!(limiter.acquire_for_transaction().await.is_ok());
+        assert!(limiter.acquire_for_heartbeat().await.is_ok());
     }
 
     #[tokio::test]
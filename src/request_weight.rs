@@ -0,0 +1,260 @@
+use crate::dto::common::{MarketProjection, PriceData};
+use crate::dto::market::{ListMarketBookRequest, ListMarketCatalogueRequest, PriceProjectionDto};
+
+/// Betfair rejects requests weighing more than this with TOO_MUCH_DATA
+pub const MAX_REQUEST_WEIGHT: u32 = 200;
+
+/// Most markets `listMarketCatalogue` returns for one request
+pub const MAX_CATALOGUE_RESULTS: usize = 1000;
+
+/// Weight of one market in `listMarketBook` with this price projection
+pub fn market_book_weight(projection: Option<&PriceProjectionDto>) -> u32 {
+    let price_data = projection
+        .and_then(|projection| projection.price_data.as_deref())
+        .unwrap_or_default();
+    let has = |wanted: fn(&PriceData) -> bool| price_data.iter().any(wanted);
+
+    let mut weight = 0;
+    if has(|d| matches!(d, PriceData::SpAvailable)) {
+        weight += 3;
+    }
+    if has(|d| matches!(d, PriceData::SpTraded)) {
+        weight += 7;
+    }
+
+    // EX_ALL_OFFERS includes the best offers; deeper best offers weigh proportionally more
+    let all_offers = has(|d| matches!(d, PriceData::ExAllOffers));
+    let best_offers = !all_offers && has(|d| matches!(d, PriceData::ExBestOffers));
+    let traded = has(|d| matches!(d, PriceData::ExTraded));
+    let depth = projection
+        .and_then(|projection| projection.ex_best_offers_overrides.as_ref())
+        .and_then(|overrides| overrides.best_prices_depth)
+        .unwrap_or(3)
+        .max(3) as u32;
+    let best_offers_weight = (5 * depth).div_ceil(3);
+
+    weight += match (all_offers, best_offers, traded) {
+        (true, _, true) => 32,
+        (true, _, false) => 17,
+        (false, true, true) => best_offers_weight + 15,
+        (false, true, false) => best_offers_weight,
+        (false, false, true) => 17,
+        (false, false, false) => 0,
+    };
+
+    // Requests without price data still return the market status
+    if weight == 0 {
+        2
+    } else {
+        weight
+    }
+}
+
+/// Weight of one market in `listMarketCatalogue` with these projections
+pub fn market_catalogue_weight(projection: Option<&[MarketProjection]>) -> u32 {
+    projection
+        .unwrap_or_default()
+        .iter()
+        .map(|projection| match projection {
+            MarketProjection::MarketDescription | MarketProjection::RunnerMetadata => 1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// How many markets of `weight` fit in one request
+pub fn markets_per_request(weight: u32) -> usize {
+    match weight {
+        0 => usize::MAX,
+        weight => (MAX_REQUEST_WEIGHT / weight).max(1) as usize,
+    }
+}
+
+/// Split a request into ones within the weight limit, in market order
+pub fn split_market_book_request(request: ListMarketBookRequest) -> Vec<ListMarketBookRequest> {
+    let chunk_size = markets_per_request(market_book_weight(request.price_projection.as_ref()));
+    if request.market_ids.len() <= chunk_size {
+        return vec![request];
+    }
+
+    request
+        .market_ids
+        .chunks(chunk_size)
+        .map(|market_ids| ListMarketBookRequest {
+            market_ids: market_ids.to_vec(),
+            ..request.clone()
+        })
+        .collect()
+}
+
+/// Whether a catalogue request is too heavy to send in one go
+pub fn is_market_catalogue_request_too_heavy(request: &ListMarketCatalogueRequest) -> bool {
+    let markets = request
+        .max_results
+        .map_or(MAX_CATALOGUE_RESULTS, |max_results| {
+            max_results.max(0) as usize
+        });
+    let weight = market_catalogue_weight(request.market_projection.as_deref()) as usize;
+    weight * markets > MAX_REQUEST_WEIGHT as usize
+}
+
+/// Requests for the projections of `market_ids`, each within the weight limit
+pub fn market_catalogue_requests(
+    request: &ListMarketCatalogueRequest,
+    market_ids: &[String],
+) -> Vec<ListMarketCatalogueRequest> {
+    let chunk_size = markets_per_request(market_catalogue_weight(
+        request.market_projection.as_deref(),
+    ));
+
+    market_ids
+        .chunks(chunk_size)
+        .map(|market_ids| {
+            let mut chunk = request.clone();
+            chunk.filter.market_ids = Some(market_ids.to_vec());
+            chunk.max_results = Some(market_ids.len() as i32);
+            chunk
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::market::{ExBestOffersOverrides, MarketFilter};
+
+    fn projection(price_data: Vec<PriceData>, depth: Option<i32>) -> PriceProjectionDto {
+        PriceProjectionDto {
+            price_data: Some(price_data),
+            ex_best_offers_overrides: depth.map(|depth| ExBestOffersOverrides {
+                best_prices_depth: Some(depth),
+                rollup_model: None,
+                rollup_limit: None,
+                rollup_liability_threshold: None,
+                rollup_liability_factor: None,
+            }),
+            virtualise: None,
+            rollover_stakes: None,
+        }
+    }
+
+    fn book_request(
+        markets: usize,
+        price_projection: Option<PriceProjectionDto>,
+    ) -> ListMarketBookRequest {
+        ListMarketBookRequest {
+            market_ids: (0..markets).map(|i| format!("1.{i}")).collect(),
+            price_projection,
+            order_projection: None,
+            match_projection: None,
+            include_overall_position: None,
+            partition_matched_by_strategy_ref: None,
+            customer_strategy_refs: None,
+            currency_code: None,
+            locale: None,
+            matched_since: None,
+            bet_ids: None,
+        }
+    }
+
+    #[test]
+    fn test_market_book_weight() {
+        assert_eq!(market_book_weight(None), 2);
+        assert_eq!(market_book_weight(Some(&projection(vec![], None))), 2);
+        assert_eq!(
+            market_book_weight(Some(&projection(vec![PriceData::ExBestOffers], None))),
+            5
+        );
+        assert_eq!(
+            market_book_weight(Some(&projection(vec![PriceData::ExBestOffers], Some(6)))),
+            10
+        );
+        assert_eq!(
+            market_book_weight(Some(&projection(vec![PriceData::ExAllOffers], None))),
+            17
+        );
+        assert_eq!(
+            market_book_weight(Some(&projection(
+                vec![PriceData::ExBestOffers, PriceData::ExTraded],
+                None
+            ))),
+            20
+        );
+        assert_eq!(
+            market_book_weight(Some(&projection(
+                vec![
+                    PriceData::ExAllOffers,
+                    PriceData::ExBestOffers,
+                    PriceData::ExTraded
+                ],
+                None
+            ))),
+            32
+        );
+        assert_eq!(
+            market_book_weight(Some(&projection(
+                vec![
+                    PriceData::SpAvailable,
+                    PriceData::SpTraded,
+                    PriceData::ExBestOffers
+                ],
+                None
+            ))),
+            15
+        );
+    }
+
+    #[test]
+    fn test_split_market_book_request_keeps_market_order() {
+        let request = book_request(500, Some(projection(vec![PriceData::ExAllOffers], None)));
+        let chunks = split_market_book_request(request.clone());
+
+        // 200 / 17 = 11 markets per request
+        assert_eq!(chunks.len(), 46);
+        assert!(chunks.iter().all(|chunk| chunk.market_ids.len() <= 11));
+        assert!(chunks[0].price_projection.is_some());
+        let rejoined: Vec<String> = chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.market_ids)
+            .collect();
+        assert_eq!(rejoined, request.market_ids);
+
+        assert_eq!(split_market_book_request(book_request(100, None)).len(), 1);
+    }
+
+    #[test]
+    fn test_market_catalogue_requests() {
+        let mut request = ListMarketCatalogueRequest {
+            filter: MarketFilter {
+                event_type_ids: Some(vec!["7".to_string()]),
+                ..Default::default()
+            },
+            market_projection: Some(vec![
+                MarketProjection::Event,
+                MarketProjection::RunnerDescription,
+            ]),
+            sort: None,
+            max_results: Some(1000),
+            locale: None,
+        };
+        // Projections without weight can be requested for any number of markets
+        assert!(!is_market_catalogue_request_too_heavy(&request));
+
+        request.market_projection = Some(vec![
+            MarketProjection::MarketDescription,
+            MarketProjection::RunnerMetadata,
+        ]);
+        assert!(is_market_catalogue_request_too_heavy(&request));
+
+        let market_ids: Vec<String> = (0..250).map(|i| format!("1.{i}")).collect();
+        let chunks = market_catalogue_requests(&request, &market_ids);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].max_results, Some(100));
+        assert_eq!(chunks[2].max_results, Some(50));
+        assert_eq!(chunks[1].filter.market_ids.as_ref().unwrap()[0], "1.100");
+        assert_eq!(
+            chunks[1].filter.event_type_ids,
+            request.filter.event_type_ids
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::is_transient;
 use anyhow::Result;
 use std::future::Future;
 use std::time::Duration;
@@ -39,10 +40,23 @@ impl RetryPolicy {
         Self { config }
     }
 
+    /// Run `operation`, retrying failures that are transient.
+    ///
+    /// Errors Betfair will repeat, like `INVALID_APP_KEY`, are returned straight away.
     pub async fn retry<F, Fut, T>(&self, operation: F) -> Result<T>
     where
         F: Fn() -> Fut,
         Fut: Future<Output = Result<T>>,
+    {
+        self.retry_if(operation, is_transient).await
+    }
+
+    /// Like `retry`, but gives up immediately on errors `is_retryable` rejects
+    pub async fn retry_if<F, Fut, T, P>(&self, operation: F, is_retryable: P) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+        P: Fn(&anyhow::Error) -> bool,
     {
         let mut attempt = 0;
         let mut delay = self.config.initial_delay;
@@ -61,6 +75,9 @@ impl RetryPolicy {
                     warn!("Operation failed after {} attempts: {}", attempt, err);
                     return Err(err);
                 }
+                Err(err) if !is_retryable(&err) => {
+                    return Err(err);
+                }
                 Err(err) => {
                     warn!(
                         "Attempt {} failed: {}. Retrying in {:?}...",
@@ -101,6 +118,9 @@ impl RetryPolicy {
                     warn!("Operation failed after {} attempts: {}", attempt, err);
                     return Err(err);
                 }
+                Err(err) if !is_transient(&err) => {
+                    return Err(err);
+                }
                 Err(err) => {
                     // Add jitter to prevent thundering herd
                     let jitter = Duration::from_millis((rand::random::<f64>() * 1000.0) as u64);
@@ -126,10 +146,18 @@ impl RetryPolicy {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::{ApiErrorCode, BetfairError};
     use std::sync::atomic::{AtomicU32, Ordering};
     use std::sync::Arc;
     use tokio::time::{timeout, Duration as TokioDuration};
 
+    fn service_unavailable(body: &str) -> anyhow::Error {
+        anyhow::Error::new(BetfairError::Http {
+            status: 503,
+            body: body.to_string(),
+        })
+    }
+
     #[tokio::test]
     async fn test_retry_success_first_attempt() {
         let policy = RetryPolicy::default();
@@ -155,7 +183,7 @@ mod tests {
                 async move {
                     let count = counter.fetch_add(1, Ordering::SeqCst);
                     if count < 2 {
-                        Err(anyhow::anyhow!("Simulated failure"))
+                        Err(service_unavailable("Simulated failure"))
                     } else {
                         Ok(42)
                     }
@@ -184,7 +212,7 @@ mod tests {
                 let counter = counter_clone.clone();
                 async move {
                     counter.fetch_add(1, Ordering::SeqCst);
-                    Err::<i32, _>(anyhow::anyhow!("Simulated failure"))
+                    Err::<i32, _>(service_unavailable("Simulated failure"))
                 }
             })
             .await;
@@ -193,6 +221,79 @@ mod tests {
         assert_eq!(counter.load(Ordering::SeqCst), 2);
     }
 
+    #[tokio::test]
+    async fn test_retry_if_stops_on_non_retryable_error() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let counter_clone = counter.clone();
+
+        let policy = RetryPolicy::new(RetryConfig {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+            multiplier: 2.0,
+        });
+
+        let result = policy
+            .retry_if(
+                || {
+                    let counter = counter_clone.clone();
+                    async move {
+                        let attempt = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                        if attempt < 2 {
+                            Err::<i32, _>(anyhow::anyhow!("transient"))
+                        } else {
+                            Err(anyhow::anyhow!("permanent"))
+                        }
+                    }
+                },
+                |err| err.to_string() == "transient",
+            )
+            .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "permanent");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_only_repeats_transient_betfair_errors() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let counter_clone = counter.clone();
+
+        let policy = RetryPolicy::new(RetryConfig {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+            multiplier: 2.0,
+        });
+
+        let result = policy
+            .retry(|| {
+                let counter = counter_clone.clone();
+                async move {
+                    let attempt = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                    let code = if attempt < 3 {
+                        "SERVICE_BUSY"
+                    } else {
+                        "INVALID_APP_KEY"
+                    };
+                    Err::<i32, _>(anyhow::Error::new(BetfairError::Api {
+                        code: code.into(),
+                        request_uuid: None,
+                        details: None,
+                    }))
+                }
+            })
+            .await;
+
+        let err = result.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<BetfairError>()
+                .and_then(BetfairError::api_error_code),
+            Some(&ApiErrorCode::InvalidAppKey)
+        );
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+
     #[tokio::test]
     async fn test_retry_with_jitter_success() {
         let policy = RetryPolicy::default();
@@ -220,7 +321,7 @@ mod tests {
                 async move {
                     let count = counter.fetch_add(1, Ordering::SeqCst);
                     if count < 1 {
-                        Err(anyhow::anyhow!("Simulated failure"))
+                        Err(service_unavailable("Simulated failure"))
                     } else {
                         Ok(200)
                     }
@@ -249,7 +350,7 @@ mod tests {
                 let counter = counter_clone.clone();
                 async move {
                     counter.fetch_add(1, Ordering::SeqCst);
-                    Err::<i32, _>(anyhow::anyhow!("Always fails"))
+                    Err::<i32, _>(service_unavailable("Always fails"))
                 }
             })
             .await;
@@ -292,7 +393,7 @@ mod tests {
                 let counter = counter_clone.clone();
                 async move {
                     counter.fetch_add(1, Ordering::SeqCst);
-                    Err::<i32, _>(anyhow::anyhow!("Always fails"))
+                    Err::<i32, _>(service_unavailable("Always fails"))
                 }
             })
             .await;
@@ -320,7 +421,7 @@ mod tests {
                 let counter = counter_clone.clone();
                 async move {
                     counter.fetch_add(1, Ordering::SeqCst);
-                    Err::<i32, _>(anyhow::anyhow!("Always fails"))
+                    Err::<i32, _>(service_unavailable("Always fails"))
                 }
             }),
         )
@@ -347,7 +448,7 @@ mod tests {
                 let counter = counter_clone.clone();
                 async move {
                     counter.fetch_add(1, Ordering::SeqCst);
-                    Err::<i32, _>(anyhow::anyhow!("Fails"))
+                    Err::<i32, _>(service_unavailable("Fails"))
                 }
             })
             .await;
@@ -0,0 +1,114 @@
+use crate::error::BetfairError;
+use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
+
+/// Whether `err` was caused by an expired or invalid session
+pub fn is_session_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<BetfairError>()
+        .is_some_and(BetfairError::is_session_error)
+}
+
+/// Session token shared by a `RestClient` and the `StreamingClient`s attached to it.
+///
+/// Clones refer to the same token, so a re-login is seen by every holder.
+#[derive(Debug, Clone, Default)]
+pub struct SessionToken(Arc<RwLock<Option<String>>>);
+
+impl SessionToken {
+    pub fn new(token: String) -> Self {
+        Self(Arc::new(RwLock::new(Some(token))))
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.0.read().ok().and_then(|token| token.clone())
+    }
+
+    pub fn set(&self, token: String) {
+        if let Ok(mut current) = self.0.write() {
+            *current = Some(token);
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut current) = self.0.write() {
+            *current = None;
+        }
+    }
+}
+
+/// A background keep-alive task, stopped by `stop` or when dropped
+pub struct KeepAliveHandle {
+    task: JoinHandle<()>,
+}
+
+impl KeepAliveHandle {
+    pub(crate) fn new(task: JoinHandle<()>) -> Self {
+        Self { task }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    pub fn stop(self) {
+        // Dropping aborts the task
+    }
+}
+
+impl Drop for KeepAliveHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ApiErrorCode;
+    use std::time::Duration;
+
+    #[test]
+    fn test_session_token_is_shared_between_clones() {
+        let session = SessionToken::default();
+        let attached = session.clone();
+        assert_eq!(attached.get(), None);
+
+        session.set("token1".to_string());
+        assert_eq!(attached.get().as_deref(), Some("token1"));
+
+        attached.set("token2".to_string());
+        assert_eq!(session.get().as_deref(), Some("token2"));
+
+        session.clear();
+        assert_eq!(attached.get(), None);
+    }
+
+    #[test]
+    fn test_session_error_detection() {
+        assert!(ApiErrorCode::from("INVALID_SESSION_INFORMATION").is_session_error());
+        assert!(ApiErrorCode::from("NO_SESSION").is_session_error());
+        assert!(!ApiErrorCode::from("TOO_MUCH_DATA").is_session_error());
+
+        let err = anyhow::Error::new(BetfairError::Api {
+            code: "NO_SESSION".into(),
+            request_uuid: None,
+            details: None,
+        });
+        assert!(is_session_error(&err));
+        assert!(is_session_error(&err.context("listMarketBook failed")));
+        assert!(!is_session_error(&anyhow::anyhow!("NO_SESSION")));
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_handle_aborts_task_on_drop() {
+        let handle = KeepAliveHandle::new(tokio::spawn(async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }));
+        assert!(handle.is_running());
+
+        let abort = handle.task.abort_handle();
+        handle.stop();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(abort.is_finished());
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::Result;
+use rustls_pki_types::ServerName;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+const BETFAIR_STREAM_HOST: &str = "stream-api.betfair.com";
+const BETFAIR_STREAM_PORT: u16 = 443;
+
+/// A connected byte stream carrying the newline-delimited stream protocol
+pub trait StreamIo: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> StreamIo for T {}
+
+pub type BoxedStreamIo = Box<dyn StreamIo>;
+
+type ConnectFuture = Pin<Box<dyn Future<Output = Result<BoxedStreamIo>> + Send>>;
+type ConnectFn = Arc<dyn Fn() -> ConnectFuture + Send + Sync>;
+
+/// Network address of a stream server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl StreamEndpoint {
+    pub fn new(host: impl Into<String>, port: u16, tls: bool) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            tls,
+        }
+    }
+
+    /// The production Betfair stream endpoint
+    pub fn betfair() -> Self {
+        Self::new(BETFAIR_STREAM_HOST, BETFAIR_STREAM_PORT, true)
+    }
+
+    /// Parse `host[:port]`, optionally prefixed with `tls://`, `ssl://` or `tcp://`.
+    ///
+    /// Without a scheme TLS is used; only TLS endpoints default to port 443. IPv6
+    /// addresses must be bracketed, as in `tcp://[::1]:9000`.
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = url.trim().trim_end_matches('/');
+        let (tls, address) = match url.split_once("://") {
+            Some(("tls" | "ssl", address)) => (true, address),
+            Some(("tcp", address)) => (false, address),
+            Some((scheme, _)) => {
+                return Err(anyhow::anyhow!("Unsupported stream URL scheme: {scheme}"))
+            }
+            None => (true, url),
+        };
+
+        let (host, port) = match address.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, rest) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| anyhow::anyhow!("Unclosed bracket in stream URL {url}"))?;
+                match rest.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None if rest.is_empty() => (host, None),
+                    None => return Err(anyhow::anyhow!("Invalid stream URL {url}")),
+                }
+            }
+            None => match address.split_once(':') {
+                Some((_, port)) if port.contains(':') => {
+                    return Err(anyhow::anyhow!(
+                        "IPv6 address in stream URL {url} must be bracketed, e.g. [::1]:9000"
+                    ))
+                }
+                Some((host, port)) => (host, Some(port)),
+                None => (address, None),
+            },
+        };
+
+        let port = match port {
+            Some(port) => port
+                .parse::<u16>()
+                .map_err(|e| anyhow::anyhow!("Invalid port in stream URL {url}: {e}"))?,
+            None if tls => BETFAIR_STREAM_PORT,
+            None => return Err(anyhow::anyhow!("Stream URL {url} needs a port")),
+        };
+
+        if host.is_empty() {
+            return Err(anyhow::anyhow!("Stream URL {url} has no host"));
+        }
+
+        Ok(Self::new(host, port, tls))
+    }
+
+    pub async fn connect(&self) -> Result<BoxedStreamIo> {
+        let tcp_stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        if !self.tls {
+            return Ok(Box::new(tcp_stream));
+        }
+
+        crate::ensure_crypto_provider();
+        let mut root_store = RootCertStore::empty();
+        root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+        let config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+
+        let connector = TlsConnector::from(Arc::new(config));
+        let domain = ServerName::try_from(self.host.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid DNS name: {e}"))?;
+
+        let tls_stream = connector
+            .connect(domain, tcp_stream)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to establish TLS connection: {e}"))?;
+
+        Ok(Box::new(tls_stream))
+    }
+}
+
+impl Default for StreamEndpoint {
+    fn default() -> Self {
+        Self::betfair()
+    }
+}
+
+impl fmt::Display for StreamEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scheme = if self.tls { "tls" } else { "tcp" };
+        if self.host.contains(':') {
+            write!(f, "{scheme}://[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{scheme}://{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// How the streamer opens a connection, called again on every reconnect
+#[derive(Clone)]
+pub enum StreamTransport {
+    Endpoint(StreamEndpoint),
+    Custom(ConnectFn),
+}
+
+impl StreamTransport {
+    /// Use any connector, e.g. one handing out `tokio::io::duplex` halves in tests
+    pub fn custom<F, Fut, S>(connect: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<S>> + Send + 'static,
+        S: StreamIo,
+    {
+        StreamTransport::Custom(Arc::new(move || {
+            let connecting = connect();
+            Box::pin(async move { Ok(Box::new(connecting.await?) as BoxedStreamIo) })
+        }))
+    }
+
+    pub async fn connect(&self) -> Result<BoxedStreamIo> {
+        match self {
+            StreamTransport::Endpoint(endpoint) => endpoint.connect().await,
+            StreamTransport::Custom(connect) => connect().await,
+        }
+    }
+}
+
+impl Default for StreamTransport {
+    fn default() -> Self {
+        StreamTransport::Endpoint(StreamEndpoint::default())
+    }
+}
+
+impl From<StreamEndpoint> for StreamTransport {
+    fn from(endpoint: StreamEndpoint) -> Self {
+        StreamTransport::Endpoint(endpoint)
+    }
+}
+
+impl fmt::Debug for StreamTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamTransport::Endpoint(endpoint) => write!(f, "Endpoint({endpoint})"),
+            StreamTransport::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_parse_endpoint() {
+        assert_eq!(
+            StreamEndpoint::parse("stream-api.betfair.com:443").unwrap(),
+            StreamEndpoint::betfair()
+        );
+        assert_eq!(
+            StreamEndpoint::parse("tls://stream-api-integration.betfair.com").unwrap(),
+            StreamEndpoint::new("stream-api-integration.betfair.com", 443, true)
+        );
+        assert_eq!(
+            StreamEndpoint::parse("tcp://127.0.0.1:9000").unwrap(),
+            StreamEndpoint::new("127.0.0.1", 9000, false)
+        );
+        assert!(StreamEndpoint::parse("tcp://127.0.0.1").is_err());
+        assert!(StreamEndpoint::parse("https://stream-api.betfair.com").is_err());
+        assert!(StreamEndpoint::parse("localhost:port").is_err());
+    }
+
+    #[test]
+    fn test_parse_ipv6_endpoint() {
+        let endpoint = StreamEndpoint::parse("tcp://[::1]:9000").unwrap();
+        assert_eq!(endpoint, StreamEndpoint::new("::1", 9000, false));
+        assert_eq!(endpoint.to_string(), "tcp://[::1]:9000");
+        assert_eq!(
+            StreamEndpoint::parse("[2001:db8::1]").unwrap(),
+            StreamEndpoint::new("2001:db8::1", 443, true)
+        );
+
+        // Without brackets the port can't be told apart from the address
+        assert!(StreamEndpoint::parse("tcp://::1:9000").is_err());
+        assert!(StreamEndpoint::parse("tcp://[::1:9000").is_err());
+        assert!(StreamEndpoint::parse("tcp://[::1]9000").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport_connects_each_time() {
+        let transport = StreamTransport::custom(|| async {
+            let (client, mut server) = tokio::io::duplex(64);
+            server.write_all(b"hello\r\n").await?;
+            Ok(client)
+        });
+
+        for _ in 0..2 {
+            let mut io = transport.connect().await.unwrap();
+            let mut buf = [0u8; 7];
+            io.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello\r\n");
+        }
+    }
+}
@@ -1,46 +1,158 @@
 use crate::connection_state::{ConnectionManager, ConnectionState};
-use crate::dto::MarketDefinition;
+use crate::dto::{MarketDefinition, RunnerKey, StatusMessage};
+use crate::market_cache::{MarketCache, SharedMarketCaches};
 use crate::msg_model::HeartbeatMessage;
 use crate::msg_model::MarketChangeMessage;
 use crate::msg_model::OrderChangeMessage;
+use crate::order_cache::OrderEvent;
 use crate::orderbook::Orderbook;
 use crate::retry::{RetryConfig, RetryPolicy};
+use crate::session::SessionToken;
+use crate::stream_transport::StreamTransport;
+use crate::subscriptions::SharedSubscriptions;
 use anyhow::Result;
-use rust_decimal::prelude::ToPrimitive;
-use rustls_pki_types::ServerName;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
-use tokio::sync::mpsc;
-use tokio_rustls::rustls::{ClientConfig, RootCertStore};
-use tokio_rustls::TlsConnector;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::Instant;
 use tracing::{debug, error, info, warn};
 
-const STREAM_API_ENDPOINT: &str = "stream-api.betfair.com:443";
-const STREAM_API_HOST: &str = "stream-api.betfair.com";
+/// Request ID of the authentication message, echoed by the status that answers it
+const AUTHENTICATION_ID: i64 = 1;
+
+/// How long `wait_for_authentication` waits for the reply to the authentication request
+const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Silence allowed before the watchdog acts, until a heartbeat interval is negotiated
+const DEFAULT_HEARTBEAT_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// Slack on top of two heartbeat intervals so scheduling jitter doesn't trip the watchdog
+const HEARTBEAT_MARGIN: Duration = Duration::from_secs(5);
 
 type OrderbookCallback = Arc<
-    dyn Fn(String, HashMap<String, Orderbook>, Option<MarketDefinition>) + Send + Sync + 'static,
+    dyn Fn(String, HashMap<RunnerKey, Orderbook>, Option<MarketDefinition>) + Send + Sync + 'static,
 >;
 type OrderUpdateCallback = Arc<dyn Fn(OrderChangeMessage) + Send + Sync + 'static>;
 
+/// Typed stream event, delivered in the order messages arrive on the connection
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    /// Runner books of a market after applying one market change
+    MarketChange {
+        market_id: String,
+        orderbooks: HashMap<RunnerKey, Orderbook>,
+        publish_time: i64,
+    },
+    MarketDefinitionChange {
+        market_id: String,
+        market_definition: Box<MarketDefinition>,
+    },
+    /// The key line of an Asian handicap or line market moved
+    KeyLineChange {
+        market_id: String,
+        key_line: Vec<RunnerKey>,
+    },
+    OrderChange(OrderChangeMessage),
+    /// A typed order lifecycle change from the order cache, sent before its `OrderChange`
+    OrderEvent(OrderEvent),
+    ConnectionStateChange(ConnectionState),
+    Status(StatusMessage),
+    /// A `FAILURE` status; fatal ones (see `StatusMessage::is_fatal`) stop the stream
+    Error(StatusMessage),
+    /// Keep-alive on a subscription (`mcm`/`ocm`) or reply to a heartbeat request (`heartbeat`)
+    Heartbeat {
+        op: String,
+        publish_time: Option<i64>,
+    },
+}
+
+/// Markets currently subscribed by ID; `None` accepts every market (filter subscriptions)
+pub type SharedMarketScope = Arc<RwLock<Option<HashSet<String>>>>;
+
+/// Sender for the current connection, replaced whenever the streamer reconnects
+pub type SharedMessageSender = Arc<RwLock<Option<mpsc::Sender<String>>>>;
+
+/// Shared handle to the clocks of one subscription, kept across reconnections
+pub type SharedStreamClock = Arc<RwLock<StreamClock>>;
+
+/// Latest `initialClk`/`clk` received for a subscription.
+///
+/// Sending these back on resubscription lets Betfair answer with a `RESUB_DELTA`
+/// catch-up instead of a full image.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamClock {
+    pub initial_clk: Option<String>,
+    pub clk: Option<String>,
+}
+
+impl StreamClock {
+    pub fn update(&mut self, initial_clk: Option<&str>, clk: Option<&str>) {
+        if let Some(initial_clk) = initial_clk.filter(|c| !c.is_empty()) {
+            self.initial_clk = Some(initial_clk.to_string());
+        }
+        if let Some(clk) = clk.filter(|c| !c.is_empty()) {
+            self.clk = Some(clk.to_string());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.initial_clk = None;
+        self.clk = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.initial_clk.is_none() && self.clk.is_none()
+    }
+
+    /// Render the clocks as additional subscription message fields (empty if none received yet)
+    pub fn subscription_fields(&self) -> String {
+        let mut fields = String::new();
+        if let Some(initial_clk) = &self.initial_clk {
+            fields.push_str(&format!(
+                ", \"initialClk\": {}",
+                serde_json::Value::String(initial_clk.clone())
+            ));
+        }
+        if let Some(clk) = &self.clk {
+            fields.push_str(&format!(
+                ", \"clk\": {}",
+                serde_json::Value::String(clk.clone())
+            ));
+        }
+        fields
+    }
+}
+
 pub struct BetfairStreamer {
     app_key: String,
-    ssoid: String,
+    session: SessionToken,
+    /// Session token sent on the current connection
+    authenticated_token: Option<String>,
+    transport: StreamTransport,
     orderbook_callback: Option<OrderbookCallback>,
     orderupdate_callback: Option<OrderUpdateCallback>,
+    event_sender: Option<broadcast::Sender<StreamEvent>>,
     message_sender: Option<mpsc::Sender<String>>,
+    active_sender: SharedMessageSender,
     message_receiver: Option<mpsc::Receiver<String>>,
-    subscribed_markets: HashSet<(String, usize)>,
-    subscribed_to_orders: bool,
+    subscriptions: SharedSubscriptions,
     last_message_ts: Arc<Mutex<Instant>>,
-    heartbeat_threshold: Duration,
+    heartbeat_threshold: Arc<Mutex<Duration>>,
+    market_heartbeat: Option<Duration>,
+    order_heartbeat: Option<Duration>,
     is_resubscribing: Arc<Mutex<bool>>,
-    orderbooks: HashMap<String, HashMap<String, Orderbook>>,
-    market_definitions: HashMap<String, MarketDefinition>,
+    market_clock: SharedStreamClock,
+    order_clock: SharedStreamClock,
+    market_scope: SharedMarketScope,
+    /// Reply to the authentication request on the current connection
+    auth_status: Option<StatusMessage>,
+    fatal_status: Option<StatusMessage>,
+    /// Fragments of segmented messages awaiting `SEG_END`, keyed by op (`mcm`/`ocm`)
+    segment_buffers: HashMap<String, Vec<Value>>,
+    markets: SharedMarketCaches,
     connection_manager: ConnectionManager,
     _retry_policy: RetryPolicy,
 }
@@ -49,18 +161,28 @@ impl BetfairStreamer {
     pub fn new(app_key: String, ssoid: String) -> Self {
         Self {
             app_key,
-            ssoid,
+            session: SessionToken::new(ssoid),
+            authenticated_token: None,
+            transport: StreamTransport::default(),
             orderbook_callback: None,
             orderupdate_callback: None,
+            event_sender: None,
             message_sender: None,
+            active_sender: SharedMessageSender::default(),
             message_receiver: None,
-            subscribed_markets: HashSet::new(),
-            subscribed_to_orders: false,
-            last_message_ts: Arc::new(Mutex::new(Instant::now() + Duration::from_secs(10))),
-            heartbeat_threshold: Duration::from_secs(10),
+            subscriptions: SharedSubscriptions::default(),
+            last_message_ts: Arc::new(Mutex::new(Instant::now() + DEFAULT_HEARTBEAT_THRESHOLD)),
+            heartbeat_threshold: Arc::new(Mutex::new(DEFAULT_HEARTBEAT_THRESHOLD)),
+            market_heartbeat: None,
+            order_heartbeat: None,
             is_resubscribing: Arc::new(Mutex::new(false)),
-            orderbooks: HashMap::new(),
-            market_definitions: HashMap::new(),
+            market_clock: SharedStreamClock::default(),
+            order_clock: SharedStreamClock::default(),
+            market_scope: SharedMarketScope::default(),
+            auth_status: None,
+            fatal_status: None,
+            segment_buffers: HashMap::new(),
+            markets: SharedMarketCaches::default(),
             connection_manager: ConnectionManager::new(),
             _retry_policy: RetryPolicy::new(RetryConfig {
                 max_attempts: 5,
@@ -73,7 +195,10 @@ impl BetfairStreamer {
 
     pub fn set_orderbook_callback<F>(&mut self, callback: F)
     where
-        F: Fn(String, HashMap<String, Orderbook>, Option<MarketDefinition>) + Send + Sync + 'static,
+        F: Fn(String, HashMap<RunnerKey, Orderbook>, Option<MarketDefinition>)
+            + Send
+            + Sync
+            + 'static,
     {
         self.orderbook_callback = Some(Arc::new(callback));
     }
@@ -85,41 +210,91 @@ impl BetfairStreamer {
         self.orderupdate_callback = Some(Arc::new(callback));
     }
 
+    /// Publish typed events to this channel in addition to the callbacks
+    pub fn set_event_sender(&mut self, event_sender: broadcast::Sender<StreamEvent>) {
+        self.event_sender = Some(event_sender);
+    }
+
+    fn emit(&self, event: StreamEvent) {
+        if let Some(sender) = &self.event_sender {
+            // Only fails when nobody is listening, which is fine
+            let _ = sender.send(event);
+        }
+    }
+
+    /// Share clock state with the owner so it survives this streamer being recreated
+    pub fn set_clocks(&mut self, market_clock: SharedStreamClock, order_clock: SharedStreamClock) {
+        self.market_clock = market_clock;
+        self.order_clock = order_clock;
+    }
+
+    /// Connect through this transport instead of the production Betfair endpoint
+    pub fn set_transport(&mut self, transport: StreamTransport) {
+        self.transport = transport;
+    }
+
+    /// Publish the sender of every new connection
+    pub fn set_active_sender(&mut self, active_sender: SharedMessageSender) {
+        self.active_sender = active_sender;
+    }
+
+    /// Authenticate every new connection with the current token of a shared session
+    pub fn set_session(&mut self, session: SessionToken) {
+        self.session = session;
+    }
+
+    /// Share the subscription registry replayed after every reconnection
+    pub fn set_subscriptions(&mut self, subscriptions: SharedSubscriptions) {
+        self.subscriptions = subscriptions;
+    }
+
+    /// Keep market caches in this shared map so readers can take snapshots
+    pub fn set_market_caches(&mut self, markets: SharedMarketCaches) {
+        self.markets = markets;
+    }
+
+    /// Drop cached books for markets outside this scope and ignore their late updates
+    pub fn set_market_scope(&mut self, market_scope: SharedMarketScope) {
+        self.market_scope = market_scope;
+    }
+
+    pub fn get_market_clock(&self) -> StreamClock {
+        read_clock(&self.market_clock)
+    }
+
+    pub fn get_order_clock(&self) -> StreamClock {
+        read_clock(&self.order_clock)
+    }
+
     pub async fn connect_betfair_tls_stream(&mut self) -> Result<()> {
         self.connection_manager
             .set_state(ConnectionState::Connecting)
             .await;
-        info!("BETFAIR_RS_DEBUG: TLS connect starting - this is the NEW code with debug logging");
+        info!("Connecting to streaming service via {:?}", self.transport);
+
+        // Partial segments from a previous connection can never be completed
+        self.segment_buffers.clear();
+        self.auth_status = None;
+        self.fatal_status = None;
 
+        let session_token = self.session.get().unwrap_or_default();
         let auth_msg = format!(
-            "{{\"op\": \"authentication\",\"id\":1, \"appKey\": \"{}\", \"session\": \"{}\"}}\r\n",
-            self.app_key, self.ssoid
+            "{{\"op\": \"authentication\",\"id\":{}, \"appKey\": \"{}\", \"session\": \"{}\"}}\r\n",
+            AUTHENTICATION_ID, self.app_key, session_token
         );
+        self.authenticated_token = Some(session_token);
         info!("{auth_msg}");
-        let tcp_stream = TcpStream::connect(STREAM_API_ENDPOINT).await?;
-
-        let mut root_store = RootCertStore::empty();
-        root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
-
-        let config = ClientConfig::builder()
-            .with_root_certificates(root_store)
-            .with_no_client_auth();
-
-        let connector = TlsConnector::from(Arc::new(config));
-        let domain = ServerName::try_from(STREAM_API_HOST)
-            .map_err(|e| anyhow::anyhow!("Invalid DNS name: {e}"))?
-            .to_owned();
+        let stream = self.transport.connect().await?;
 
-        let tls_stream = connector
-            .connect(domain, tcp_stream)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to establish TLS connection: {e}"))?;
-
-        let (reader, mut writer) = tokio::io::split(tls_stream);
+        let (reader, mut writer) = tokio::io::split(stream);
 
         // Set up channels for message passing
         let (tx_write, mut rx_write) = mpsc::channel::<String>(100);
         let (tx_read, rx_read) = mpsc::channel::<String>(100);
+        // Published again once the new connection is authenticated
+        if let Ok(mut active) = self.active_sender.write() {
+            *active = None;
+        }
         self.message_sender = Some(tx_write);
         self.message_receiver = Some(rx_read);
 
@@ -180,108 +355,98 @@ impl BetfairStreamer {
         Ok(())
     }
 
-    pub async fn send_message(&self, message: String) -> Result<()> {
-        if let Some(sender) = &self.message_sender {
-            sender.send(message).await?;
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Message sender not initialized"))
+    /// Process incoming messages until the reply to the authentication request arrives.
+    ///
+    /// Fails if authentication is rejected, the connection closes first or no reply
+    /// arrives within `AUTHENTICATION_TIMEOUT`.
+    pub async fn wait_for_authentication(&mut self) -> Result<StatusMessage> {
+        let deadline = Instant::now() + AUTHENTICATION_TIMEOUT;
+
+        loop {
+            if let Some(status) = &self.auth_status {
+                return if status.is_success() {
+                    // Only now may the watchdog and commands send subscriptions on this connection
+                    if let Ok(mut active) = self.active_sender.write() {
+                        *active = self.message_sender.clone();
+                    }
+                    Ok(status.clone())
+                } else {
+                    Err(anyhow::anyhow!("Stream authentication failed: {status}"))
+                };
+            }
+
+            let receiver = self
+                .message_receiver
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("Message receiver not initialized"))?;
+            let message = tokio::time::timeout_at(deadline, receiver.recv())
+                .await
+                .map_err(|_| anyhow::anyhow!("Timed out waiting for stream authentication"))?
+                .ok_or_else(|| anyhow::anyhow!("Connection closed before authentication"))?;
+
+            if let Err(e) = self.handle_message(message).await {
+                error!("Error handling message: {}", e);
+            }
         }
     }
 
-    fn create_market_subscription_message(market_id: &str, levels: usize) -> String {
-        format!(
-            "{{\"op\": \"marketSubscription\", \"id\": 1, \"marketFilter\": {{ \"marketIds\":[\"{market_id}\"]}}, \"marketDataFilter\": {{ \"fields\": [\"EX_BEST_OFFERS\"], \"ladderLevels\": {levels}}}}}\r\n"
-        )
+    /// The failure that stopped the stream, if a fatal status was received
+    pub fn fatal_status(&self) -> Option<&StatusMessage> {
+        self.fatal_status.as_ref()
     }
 
-    pub fn create_order_subscription_message(filter_json: &str) -> String {
-        format!(
-            "{{\"op\":\"orderSubscription\",\"orderFilter\":{filter_json},\"segmentationEnabled\":true}}\r\n"
-        )
+    /// Whether the session was rejected but has since been replaced, e.g. by a re-login,
+    /// so reconnecting can succeed
+    pub fn session_refreshed(&self) -> bool {
+        let rejected = self
+            .fatal_status
+            .as_ref()
+            .and_then(|status| status.error_code)
+            .is_some_and(|code| code.is_session_error());
+        rejected && self.session.get() != self.authenticated_token
     }
 
-    pub async fn subscribe(&mut self, market_id: String, levels: usize) -> Result<()> {
-        let sub_msg = Self::create_market_subscription_message(&market_id, levels);
-        info!("Sending subscription: {}", sub_msg);
-
-        self.send_message(sub_msg).await?;
-        self.subscribed_markets.insert((market_id, levels));
-        Ok(())
+    pub async fn send_message(&self, message: String) -> Result<()> {
+        if let Some(sender) = &self.message_sender {
+            sender.send(message).await?;
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Message sender not initialized"))
+        }
     }
 
     pub fn get_message_sender(&self) -> Option<mpsc::Sender<String>> {
         self.message_sender.clone()
     }
 
-    pub async fn subscribe_to_orders(&mut self, filter_json: &str) -> Result<()> {
-        let order_sub_msg = Self::create_order_subscription_message(filter_json);
-        info!("Sending order subscription: {}", order_sub_msg);
-
-        self.send_message(order_sub_msg).await?;
-        self.subscribed_to_orders = true;
-        Ok(())
-    }
-
-    async fn reconnect(&mut self) -> Result<()> {
-        let attempts = self.connection_manager.get_reconnect_attempts().await;
-        if attempts >= 5 {
-            self.connection_manager
-                .set_state(ConnectionState::Failed(format!(
-                    "Failed to reconnect after {attempts} attempts"
-                )))
-                .await;
-            return Err(anyhow::anyhow!("Max reconnection attempts exceeded"));
-        }
-
-        self.connection_manager
-            .set_state(ConnectionState::Reconnecting)
-            .await;
-        warn!("Attempting to reconnect (attempt {})", attempts + 1);
-
-        // Try to reconnect
-        match self.connect_betfair_tls_stream().await {
-            Ok(_) => {
-                info!("Successfully reconnected to Betfair streaming service");
-
-                // Resubscribe to all markets
-                for (market_id, levels) in self.subscribed_markets.clone() {
-                    if let Err(e) = self.subscribe(market_id, levels).await {
-                        error!("Failed to resubscribe to market: {}", e);
-                    }
-                }
-
-                // Resubscribe to orders if needed
-                if self.subscribed_to_orders {
-                    if let Err(e) = self.subscribe_to_orders("{}").await {
-                        error!("Failed to resubscribe to orders: {}", e);
-                    }
-                }
-
-                Ok(())
-            }
-            Err(e) => {
-                error!("Reconnection failed: {}", e);
-                Err(e)
-            }
+    /// Restore every registered subscription, resuming from the last clocks we saw
+    pub async fn resubscribe(&self) -> Result<()> {
+        let messages = self
+            .subscriptions
+            .read()
+            .map(|registry| {
+                registry.resubscription_messages(&self.get_market_clock(), &self.get_order_clock())
+            })
+            .unwrap_or_default();
+        for message in messages {
+            info!("Sending subscription: {}", message);
+            self.send_message(message).await?;
         }
+        Ok(())
     }
 
-    pub async fn start(&mut self) -> Result<()> {
-        let receiver = self.message_receiver.take();
-        let Some(mut receiver) = receiver else {
-            return Err(anyhow::anyhow!("Message receiver not initialized"));
-        };
-
+    /// Resubscribe whenever the stream has been silent for longer than the heartbeat threshold
+    fn spawn_heartbeat_watchdog(&self) -> tokio::task::JoinHandle<()> {
         // Clone necessary components for the heartbeat task
         let last_heartbeat = Arc::clone(&self.last_message_ts);
-        let heartbeat_threshold = self.heartbeat_threshold;
+        let heartbeat_threshold = Arc::clone(&self.heartbeat_threshold);
         let is_resubscribing = Arc::clone(&self.is_resubscribing);
-        let message_sender = self.message_sender.clone();
-        let subscribed_markets = self.subscribed_markets.clone();
+        let active_sender = Arc::clone(&self.active_sender);
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let market_clock = Arc::clone(&self.market_clock);
+        let order_clock = Arc::clone(&self.order_clock);
 
-        // Spawn heartbeat monitoring task
-        let heartbeat_handle = tokio::spawn(async move {
+        tokio::spawn(async move {
             loop {
                 tokio::time::sleep(Duration::from_secs(1)).await;
                 let elapsed = {
@@ -294,7 +459,12 @@ impl BetfairStreamer {
                     }
                 };
 
-                if elapsed > heartbeat_threshold {
+                let threshold = heartbeat_threshold
+                    .lock()
+                    .map(|guard| *guard)
+                    .unwrap_or(DEFAULT_HEARTBEAT_THRESHOLD);
+
+                if elapsed > threshold {
                     let should_resubscribe = {
                         match is_resubscribing.lock() {
                             Ok(mut guard) => {
@@ -313,19 +483,24 @@ impl BetfairStreamer {
                     };
 
                     if should_resubscribe {
+                        let message_sender = active_sender.read().ok().and_then(|s| s.clone());
                         if let Some(sender) = &message_sender {
-                            // Resubscribe to all markets
-                            info!("Resubscribing to {} markets", subscribed_markets.len());
-                            for (market_id, levels) in &subscribed_markets {
-                                let subscription_message =
-                                    BetfairStreamer::create_market_subscription_message(
-                                        market_id, *levels,
-                                    );
+                            info!("Stream silent for {elapsed:?}, resubscribing");
+                            let messages = resubscription_messages(
+                                &subscriptions,
+                                &market_clock,
+                                &order_clock,
+                            );
+                            for subscription_message in messages {
                                 info!("Sending subscription: {}", subscription_message);
                                 if let Err(e) = sender.send(subscription_message).await {
                                     error!("Failed to send resubscription message: {}", e);
                                 }
                             }
+                            // Give the server a full threshold to answer before trying again
+                            if let Ok(mut ts) = last_heartbeat.lock() {
+                                *ts = Instant::now();
+                            }
                         }
 
                         match is_resubscribing.lock() {
@@ -339,7 +514,19 @@ impl BetfairStreamer {
                     }
                 }
             }
-        });
+        })
+    }
+
+    /// Process messages until the connection closes or a fatal status arrives.
+    ///
+    /// Does not reconnect; the caller opens a new connection and resubscribes.
+    pub async fn start(&mut self) -> Result<()> {
+        let receiver = self.message_receiver.take();
+        let Some(mut receiver) = receiver else {
+            return Err(anyhow::anyhow!("Message receiver not initialized"));
+        };
+
+        let heartbeat_handle = self.spawn_heartbeat_watchdog();
 
         loop {
             match receiver.recv().await {
@@ -348,32 +535,23 @@ impl BetfairStreamer {
                         error!("Error handling message: {}", e);
                         // Continue processing other messages even if one fails
                     }
+
+                    if let Some(status) = &self.fatal_status {
+                        error!("Stopping stream after fatal status: {status}");
+                        self.connection_manager
+                            .set_state(ConnectionState::Failed(status.to_string()))
+                            .await;
+                        heartbeat_handle.abort();
+                        return Err(anyhow::anyhow!("Stream failed: {status}"));
+                    }
                 }
                 None => {
-                    // Channel closed, indicating disconnection
+                    // The owner reconnects, so every reconnection goes through one path
                     warn!("Message channel closed, connection lost");
                     self.connection_manager
                         .set_state(ConnectionState::Disconnected)
                         .await;
-
-                    // Attempt reconnection
-                    match self.reconnect().await {
-                        Ok(_) => {
-                            info!("Reconnection successful, restarting message processing");
-                            // Get new receiver after reconnection
-                            if let Some(new_receiver) = self.message_receiver.take() {
-                                receiver = new_receiver;
-                                continue;
-                            } else {
-                                error!("Failed to get new receiver after reconnection");
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to reconnect: {}", e);
-                            break;
-                        }
-                    }
+                    break;
                 }
             }
         }
@@ -385,6 +563,67 @@ impl BetfairStreamer {
 
     async fn handle_message(&mut self, message: String) -> Result<()> {
         let parsed_message: Value = serde_json::from_str(&message)?;
+
+        if parsed_message.get("segmentType").is_some() {
+            if let Ok(mut ts) = self.last_message_ts.lock() {
+                *ts = Instant::now();
+            }
+            return match self.buffer_segment(parsed_message) {
+                Some(merged) => self.process_message(merged.to_string()).await,
+                None => Ok(()),
+            };
+        }
+
+        self.process_message(message).await
+    }
+
+    /// Buffer one fragment of a segmented message.
+    ///
+    /// Returns the reassembled message once `SEG_END` arrives, so large images are
+    /// applied in one go rather than fragment by fragment.
+    fn buffer_segment(&mut self, fragment: Value) -> Option<Value> {
+        let op = fragment.get("op").and_then(Value::as_str)?.to_string();
+        let segment_type = fragment
+            .get("segmentType")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        match segment_type.as_str() {
+            "SEG_START" => {
+                if self.segment_buffers.contains_key(&op) {
+                    warn!(
+                        "{op} SEG_START received before previous SEG_END, dropping partial segment"
+                    );
+                }
+                self.segment_buffers.insert(op, vec![fragment]);
+                None
+            }
+            "SEG" => {
+                match self.segment_buffers.get_mut(&op) {
+                    Some(buffer) => buffer.push(fragment),
+                    None => warn!("{op} SEG received without SEG_START, dropping fragment"),
+                }
+                None
+            }
+            "SEG_END" => {
+                let Some(mut fragments) = self.segment_buffers.remove(&op) else {
+                    warn!("{op} SEG_END received without SEG_START, dropping fragment");
+                    return None;
+                };
+                fragments.push(fragment);
+                debug!("{op} segment complete with {} fragments", fragments.len());
+                Some(merge_segments(fragments))
+            }
+            other => {
+                warn!("Unknown segmentType {other} on {op}, processing as a whole message");
+                Some(fragment)
+            }
+        }
+    }
+
+    async fn process_message(&mut self, message: String) -> Result<()> {
+        let parsed_message: Value = serde_json::from_str(&message)?;
         if let Some(op) = parsed_message.get("op").and_then(Value::as_str) {
             match op {
                 "mcm" => {
@@ -393,8 +632,27 @@ impl BetfairStreamer {
                         info!("Raw JSON has {} markets in mc array", mc_array.len());
                     }
 
+                    // Heartbeats carry clocks too, so read them before typed parsing
+                    self.update_clock(
+                        &self.market_clock,
+                        parsed_message.get("initialClk").and_then(Value::as_str),
+                        parsed_message.get("clk").and_then(Value::as_str),
+                    );
+
+                    let heartbeat_ms = parsed_message.get("heartbeatMs").and_then(Value::as_i64);
+                    let conflate_ms = parsed_message.get("conflateMs").and_then(Value::as_i64);
+                    if heartbeat_ms.is_some() || conflate_ms.is_some() {
+                        self.market_heartbeat = negotiated_interval(heartbeat_ms, conflate_ms);
+                        self.update_heartbeat_threshold();
+                    }
+
                     match serde_json::from_str::<MarketChangeMessage>(&message.to_string()) {
                         Ok(market_change_message) => {
+                            if market_change_message.ct.as_deref() == Some("RESUB_DELTA") {
+                                info!(
+                                    "MCM RESUB_DELTA: applying catch-up deltas to existing books"
+                                );
+                            }
                             info!(
                                 "MCM received: {} markets, {} changes",
                                 market_change_message.market_changes.len(),
@@ -416,6 +674,12 @@ impl BetfairStreamer {
                                 {
                                     info!("Heartbeat received (id: {})", heartbeat_message.id);
                                     debug!("HeartbeatMessage details: {:?}", heartbeat_message);
+                                    self.emit(StreamEvent::Heartbeat {
+                                        op: "mcm".to_string(),
+                                        publish_time: parsed_message
+                                            .get("pt")
+                                            .and_then(Value::as_i64),
+                                    });
                                 } else {
                                     info!("Unknown MCM message: {}", parsed_message);
                                 }
@@ -437,6 +701,19 @@ impl BetfairStreamer {
                     // Check change type (ct) field
                     let change_type = parsed_message.get("ct").and_then(Value::as_str);
 
+                    self.update_clock(
+                        &self.order_clock,
+                        parsed_message.get("initialClk").and_then(Value::as_str),
+                        parsed_message.get("clk").and_then(Value::as_str),
+                    );
+
+                    let heartbeat_ms = parsed_message.get("heartbeatMs").and_then(Value::as_i64);
+                    let conflate_ms = parsed_message.get("conflateMs").and_then(Value::as_i64);
+                    if heartbeat_ms.is_some() || conflate_ms.is_some() {
+                        self.order_heartbeat = negotiated_interval(heartbeat_ms, conflate_ms);
+                        self.update_heartbeat_threshold();
+                    }
+
                     match change_type {
                         Some("SUB_IMAGE") => {
                             // Initial subscription snapshot - parse as OrderChangeMessage
@@ -460,6 +737,18 @@ impl BetfairStreamer {
                                 self.parse_order_change_message(order_change_message);
                             }
                         }
+                        Some("RESUB_DELTA") => {
+                            // Catch-up deltas after resubscribing with our last clock
+                            if let Ok(order_change_message) =
+                                serde_json::from_str::<OrderChangeMessage>(&message.to_string())
+                            {
+                                info!(
+                                    "OCM RESUB_DELTA: {} order changes",
+                                    order_change_message.order_changes.len()
+                                );
+                                self.parse_order_change_message(order_change_message);
+                            }
+                        }
                         Some("UPDATE") => {
                             // Regular order updates
                             if let Ok(order_change_message) =
@@ -496,32 +785,18 @@ impl BetfairStreamer {
                 }
                 "heartbeat" => {
                     info!("Standalone heartbeat message received");
+                    self.emit(StreamEvent::Heartbeat {
+                        op: "heartbeat".to_string(),
+                        publish_time: None,
+                    });
                     if let Ok(mut ts) = self.last_message_ts.lock() {
                         *ts = Instant::now();
                     }
                 }
                 "status" => {
-                    // Parse status message for authentication response
-                    let status_code = parsed_message.get("statusCode").and_then(Value::as_str);
-                    let error_message = parsed_message.get("errorMessage").and_then(Value::as_str);
-                    let connection_id = parsed_message.get("connectionId").and_then(Value::as_str);
-
-                    match status_code {
-                        Some("SUCCESS") => {
-                            info!(
-                                "Authentication successful - Connection ID: {:?}",
-                                connection_id
-                            );
-                        }
-                        Some("FAILURE") => {
-                            error!("Authentication failed - Error: {:?}", error_message);
-                        }
-                        Some(code) => {
-                            warn!("Status message with code '{}': {}", code, parsed_message);
-                        }
-                        None => {
-                            info!("Status message (no code): {}", parsed_message);
-                        }
+                    match serde_json::from_value::<StatusMessage>(parsed_message.clone()) {
+                        Ok(status_message) => self.handle_status(status_message),
+                        Err(e) => warn!("Failed to parse status message {}: {}", parsed_message, e),
                     }
 
                     if let Ok(mut ts) = self.last_message_ts.lock() {
@@ -545,14 +820,98 @@ impl BetfairStreamer {
         Ok(())
     }
 
+    fn handle_status(&mut self, status: StatusMessage) {
+        if self.auth_status.is_none() && status.id == Some(AUTHENTICATION_ID) {
+            if status.is_success() {
+                info!(
+                    "Authentication successful - Connection ID: {:?}",
+                    status.connection_id
+                );
+            }
+            self.auth_status = Some(status.clone());
+        }
+
+        self.emit(StreamEvent::Status(status.clone()));
+
+        if !status.is_success() {
+            error!(
+                "Stream status failure (connection closed: {}): {status}",
+                status.connection_closed
+            );
+            if status.is_fatal() {
+                self.fatal_status = Some(status.clone());
+            }
+            self.emit(StreamEvent::Error(status));
+        }
+    }
+
+    /// Allow two missed heartbeats on the slowest subscription before the watchdog acts,
+    /// never going below the default threshold
+    fn update_heartbeat_threshold(&self) {
+        let threshold = self
+            .market_heartbeat
+            .into_iter()
+            .chain(self.order_heartbeat)
+            .max()
+            .map(|interval| (interval * 2 + HEARTBEAT_MARGIN).max(DEFAULT_HEARTBEAT_THRESHOLD))
+            .unwrap_or(DEFAULT_HEARTBEAT_THRESHOLD);
+
+        match self.heartbeat_threshold.lock() {
+            Ok(mut guard) => {
+                if *guard != threshold {
+                    info!("Heartbeat watchdog threshold set to {:?}", threshold);
+                }
+                *guard = threshold;
+            }
+            Err(e) => error!("Mutex lock poisoned: {}", e),
+        }
+    }
+
+    #[cfg(test)]
+    fn get_heartbeat_threshold(&self) -> Duration {
+        self.heartbeat_threshold
+            .lock()
+            .map(|guard| *guard)
+            .unwrap_or(DEFAULT_HEARTBEAT_THRESHOLD)
+    }
+
+    fn update_clock(
+        &self,
+        clock: &SharedStreamClock,
+        initial_clk: Option<&str>,
+        clk: Option<&str>,
+    ) {
+        match clock.write() {
+            Ok(mut guard) => guard.update(initial_clk, clk),
+            Err(e) => error!("Clock lock poisoned: {}", e),
+        }
+    }
+
     fn parse_market_change_message(&mut self, market_change_message: MarketChangeMessage) {
         info!(
             "Parsing market change message with {} market changes",
             market_change_message.market_changes.len()
         );
 
+        let market_scope = Arc::clone(&self.market_scope);
+        let scope_guard = market_scope.read().ok();
+        let scope = scope_guard.as_ref().and_then(|guard| guard.as_ref());
+
+        if let Some(scope) = scope {
+            if let Ok(mut markets) = self.markets.write() {
+                if markets.keys().any(|id| !scope.contains(id)) {
+                    markets.retain(|id, _| scope.contains(id));
+                    debug!("Dropped cached books for unsubscribed markets");
+                }
+            }
+        }
+
         for market_change in market_change_message.market_changes {
-            let market_id = market_change.id;
+            let market_id = market_change.id.clone();
+            if scope.is_some_and(|scope| !scope.contains(&market_id)) {
+                debug!("Ignoring update for unsubscribed market {market_id}");
+                continue;
+            }
             info!("Processing market change for market {market_id}");
 
             if let Some(ref market_def) = market_change.market_definition {
@@ -560,92 +919,553 @@ impl BetfairStreamer {
                     "Market {market_id} has marketDefinition with status: {:?}, inPlay: {}",
                     market_def.status, market_def.in_play
                 );
-                self.market_definitions
-                    .insert(market_id.clone(), market_def.clone());
+                self.emit(StreamEvent::MarketDefinitionChange {
+                    market_id: market_id.clone(),
+                    market_definition: Box::new(market_def.clone()),
+                });
             }
 
-            let market_orderbooks = self.orderbooks.entry(market_id.clone()).or_default();
+            // Apply the whole change under one write so snapshots never see it half-done
+            let (orderbooks_snapshot, market_definition, key_line_change) = {
+                let mut markets = match self.markets.write() {
+                    Ok(markets) => markets,
+                    Err(e) => {
+                        error!("Market cache lock poisoned: {}", e);
+                        return;
+                    }
+                };
+                let market = markets
+                    .entry(market_id.clone())
+                    .or_insert_with(|| MarketCache::new(market_id.clone()));
+                let previous_key_line = market.key_line.clone();
+                market.apply_market_change(&market_change, market_change_message.pt);
+                let key_line_change =
+                    (market.key_line != previous_key_line).then(|| market.key_line.clone());
 
-            if let Some(runner_changes) = market_change.runner_changes {
                 info!(
-                    "Market {market_id} has {} runner changes",
-                    runner_changes.len()
+                    "Market {market_id} now has {} runners with orderbook data",
+                    market.runners.len()
                 );
-
-                for runner_change in runner_changes {
-                    let runner_id = runner_change.id.to_string();
-                    debug!("Processing runner change for runner {runner_id} in market {market_id}");
-
-                    let orderbook = market_orderbooks.entry(runner_id.clone()).or_default();
-                    let mut has_bid_updates = false;
-                    let mut has_ask_updates = false;
-
-                    if let Some(batb) = runner_change.available_to_back {
-                        for level in batb {
-                            if level.len() >= 3 {
-                                let level_index = level[0].to_u64().unwrap_or(0) as usize;
-                                let price = level[1];
-                                let size = level[2];
-                                orderbook.add_bid(level_index, price, size);
-                                has_bid_updates = true;
-                            }
-                        }
-                    }
-
-                    if let Some(batl) = runner_change.available_to_lay {
-                        for level in batl {
-                            if level.len() >= 3 {
-                                let level_index = level[0].to_u64().unwrap_or(0) as usize;
-                                let price = level[1];
-                                let size = level[2];
-                                orderbook.add_ask(level_index, price, size);
-                                has_ask_updates = true;
-                            }
-                        }
-                    }
-
-                    if has_bid_updates || has_ask_updates {
-                        debug!("Updated orderbook for runner {runner_id}: bids={has_bid_updates}, asks={has_ask_updates}");
-                    }
-
-                    orderbook.set_ts(market_change_message.pt);
-                    debug!("Orderbook for runner {}:", runner_id);
-                    debug!("\n{}", orderbook.pretty_print());
-                }
-            } else {
-                debug!("Market {market_id} has no runner changes");
+                (
+                    market.orderbooks(),
+                    market.definition.clone(),
+                    key_line_change,
+                )
+            };
+
+            if let Some(key_line) = key_line_change {
+                info!("Market {market_id} key line changed to {key_line:?}");
+                self.emit(StreamEvent::KeyLineChange {
+                    market_id: market_id.clone(),
+                    key_line,
+                });
             }
 
-            info!(
-                "Market {market_id} now has {} runners with orderbook data",
-                market_orderbooks.len()
-            );
-
             if let Some(callback) = &self.orderbook_callback {
                 info!(
                     "Invoking orderbook callback for market {market_id} with {} runners",
-                    market_orderbooks.len()
+                    orderbooks_snapshot.len()
+                );
+                // Invoked inline so updates are delivered in stream order
+                callback(
+                    market_id.clone(),
+                    orderbooks_snapshot.clone(),
+                    market_definition,
                 );
-                let market_id_clone = market_id.clone();
-                let orderbooks_clone = market_orderbooks.clone();
-                let market_def_clone = self.market_definitions.get(&market_id).cloned();
-                let callback_clone = callback.clone();
-                tokio::spawn(async move {
-                    callback_clone(market_id_clone, orderbooks_clone, market_def_clone);
-                });
             } else {
-                warn!("No orderbook callback set for market {market_id} - data will not be propagated");
+                debug!("No orderbook callback set for market {market_id}");
             }
+
+            self.emit(StreamEvent::MarketChange {
+                market_id,
+                orderbooks: orderbooks_snapshot,
+                publish_time: market_change_message.pt,
+            });
         }
     }
 
     fn parse_order_change_message(&mut self, order_change_message: OrderChangeMessage) {
         if let Some(callback) = &self.orderupdate_callback {
-            let callback_clone = callback.clone();
-            let message_clone = order_change_message.clone();
-            tokio::spawn(async move {
-                callback_clone(message_clone);
+            // Invoked inline so updates are delivered in stream order
+            callback(order_change_message.clone());
+        }
+
+        if order_change_message.ct.as_deref() == Some("HEARTBEAT") {
+            self.emit(StreamEvent::Heartbeat {
+                op: "ocm".to_string(),
+                publish_time: Some(order_change_message.pt),
+            });
+        } else {
+            self.emit(StreamEvent::OrderChange(order_change_message));
+        }
+    }
+}
+
+/// Join segment fragments into one message: change arrays are concatenated in order and
+/// clocks/timestamps are taken from the latest fragment that carries them.
+fn merge_segments(fragments: Vec<Value>) -> Value {
+    let mut fragments = fragments.into_iter();
+    let Some(mut merged) = fragments.next() else {
+        return Value::Null;
+    };
+
+    for fragment in fragments {
+        let Value::Object(fields) = fragment else {
+            continue;
+        };
+        for (key, value) in fields {
+            match key.as_str() {
+                "mc" | "oc" => {
+                    let Value::Array(changes) = value else {
+                        continue;
+                    };
+                    match merged.get_mut(&key).and_then(Value::as_array_mut) {
+                        Some(existing) => existing.extend(changes),
+                        None => merged[key.as_str()] = Value::Array(changes),
+                    }
+                }
+                _ => merged[key.as_str()] = value,
+            }
+        }
+    }
+
+    if let Value::Object(fields) = &mut merged {
+        fields.remove("segmentType");
+    }
+    merged
+}
+
+/// Longest expected gap between messages for a subscription's negotiated settings
+fn negotiated_interval(heartbeat_ms: Option<i64>, conflate_ms: Option<i64>) -> Option<Duration> {
+    heartbeat_ms
+        .into_iter()
+        .chain(conflate_ms)
+        .filter(|ms| *ms > 0)
+        .max()
+        .map(|ms| Duration::from_millis(ms as u64))
+}
+
+fn resubscription_messages(
+    subscriptions: &SharedSubscriptions,
+    market_clock: &SharedStreamClock,
+    order_clock: &SharedStreamClock,
+) -> Vec<String> {
+    subscriptions
+        .read()
+        .map(|registry| {
+            registry.resubscription_messages(&read_clock(market_clock), &read_clock(order_clock))
+        })
+        .unwrap_or_default()
+}
+
+fn read_clock(clock: &SharedStreamClock) -> StreamClock {
+    clock.read().map(|guard| guard.clone()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::streaming::{MarketDataFilter, OrderFilter};
+    use crate::dto::StreamErrorCode;
+    use crate::subscriptions::OrderSubscription;
+    use rust_decimal::Decimal;
+
+    fn runner_book(streamer: &BetfairStreamer, market_id: &str, selection_id: u64) -> Orderbook {
+        streamer.markets.read().unwrap()[market_id].runners[&RunnerKey::from(selection_id)]
+            .book
+            .clone()
+    }
+
+    fn market_count(streamer: &BetfairStreamer) -> usize {
+        streamer.markets.read().unwrap().len()
+    }
+
+    #[test]
+    fn test_stream_clock_update_keeps_last_values() {
+        let mut clock = StreamClock::default();
+        assert!(clock.is_empty());
+        assert_eq!(clock.subscription_fields(), "");
+
+        clock.update(Some("AAA"), Some("BBB"));
+        clock.update(None, Some("CCC"));
+        clock.update(Some(""), None);
+
+        assert_eq!(clock.initial_clk.as_deref(), Some("AAA"));
+        assert_eq!(clock.clk.as_deref(), Some("CCC"));
+
+        clock.clear();
+        assert!(clock.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_replays_registry_with_clocks() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        let (tx, mut rx) = mpsc::channel(10);
+        streamer.message_sender = Some(tx);
+
+        let subscriptions = SharedSubscriptions::default();
+        if let Ok(mut registry) = subscriptions.write() {
+            registry.markets.insert("1.234".to_string());
+            registry.market_data_filter = Some(MarketDataFilter::best_offers(3));
+            registry.orders = Some(OrderSubscription {
+                filter: Some(OrderFilter {
+                    include_overall_position: Some(false),
+                    customer_strategy_refs: Some(vec!["alpha".to_string()]),
+                    partition_matched_by_strategy_ref: Some(true),
+                }),
             });
         }
+        streamer.set_subscriptions(subscriptions);
+        streamer
+            .market_clock
+            .write()
+            .unwrap()
+            .update(Some("G1=="), Some("AKIB"));
+        streamer
+            .order_clock
+            .write()
+            .unwrap()
+            .update(None, Some("ORD"));
+
+        streamer.resubscribe().await.unwrap();
+
+        let market: Value = serde_json::from_str(rx.recv().await.unwrap().trim()).unwrap();
+        assert_eq!(market["op"], "marketSubscription");
+        assert_eq!(market["marketFilter"]["marketIds"][0], "1.234");
+        assert_eq!(market["initialClk"], "G1==");
+        assert_eq!(market["clk"], "AKIB");
+
+        let orders: Value = serde_json::from_str(rx.recv().await.unwrap().trim()).unwrap();
+        assert_eq!(orders["op"], "orderSubscription");
+        assert_eq!(orders["orderFilter"]["customerStrategyRefs"][0], "alpha");
+        assert_eq!(orders["orderFilter"]["includeOverallPosition"], false);
+        assert_eq!(orders["clk"], "ORD");
+        assert!(orders.get("initialClk").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_message_tracks_clocks() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+
+        let image = r#"{"op":"mcm","id":1,"initialClk":"INIT","clk":"C1","pt":1,"ct":"SUB_IMAGE","mc":[{"id":"1.1","img":true,"rc":[{"id":10,"batb":[[0,2.0,5.0]]}]}]}"#;
+        streamer.handle_message(image.to_string()).await.unwrap();
+        let delta = r#"{"op":"mcm","id":1,"clk":"C2","pt":2,"ct":"RESUB_DELTA","mc":[{"id":"1.1","rc":[{"id":10,"batl":[[0,2.2,3.0]]}]}]}"#;
+        streamer.handle_message(delta.to_string()).await.unwrap();
+
+        let market_clock = streamer.get_market_clock();
+        assert_eq!(market_clock.initial_clk.as_deref(), Some("INIT"));
+        assert_eq!(market_clock.clk.as_deref(), Some("C2"));
+
+        // The resubscription delta is merged into the existing book rather than replacing it
+        let book = runner_book(&streamer, "1.1", 10);
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.asks.len(), 1);
+
+        let ocm = r#"{"op":"ocm","id":2,"initialClk":"OINIT","clk":"O1","pt":3,"ct":"RESUB_DELTA","oc":[]}"#;
+        streamer.handle_message(ocm.to_string()).await.unwrap();
+        let order_clock = streamer.get_order_clock();
+        assert_eq!(order_clock.initial_clk.as_deref(), Some("OINIT"));
+        assert_eq!(order_clock.clk.as_deref(), Some("O1"));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_threshold_follows_negotiated_heartbeat() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        assert_eq!(
+            streamer.get_heartbeat_threshold(),
+            DEFAULT_HEARTBEAT_THRESHOLD
+        );
+
+        let mcm = r#"{"op":"mcm","id":1,"clk":"C1","pt":1,"ct":"SUB_IMAGE","heartbeatMs":1000,"conflateMs":0,"mc":[]}"#;
+        streamer.handle_message(mcm.to_string()).await.unwrap();
+        // Short intervals never drop below the default, which stays well above the poll interval
+        assert_eq!(
+            streamer.get_heartbeat_threshold(),
+            DEFAULT_HEARTBEAT_THRESHOLD
+        );
+
+        // The slowest subscription wins so quiet order streams don't trip the watchdog
+        let ocm =
+            r#"{"op":"ocm","id":2,"clk":"O1","pt":2,"ct":"SUB_IMAGE","heartbeatMs":5000,"oc":[]}"#;
+        streamer.handle_message(ocm.to_string()).await.unwrap();
+        assert_eq!(streamer.get_heartbeat_threshold(), Duration::from_secs(15));
+
+        let ocm =
+            r#"{"op":"ocm","id":2,"clk":"O2","pt":3,"ct":"SUB_IMAGE","heartbeatMs":500,"oc":[]}"#;
+        streamer.handle_message(ocm.to_string()).await.unwrap();
+        assert_eq!(
+            streamer.get_heartbeat_threshold(),
+            DEFAULT_HEARTBEAT_THRESHOLD
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_silent_stream_resubscribes_once_per_threshold() {
+        let streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        let (tx, mut rx) = mpsc::channel(100);
+        *streamer.active_sender.write().unwrap() = Some(tx);
+        if let Ok(mut registry) = streamer.subscriptions.write() {
+            registry.markets.insert("1.1".to_string());
+            registry.market_data_filter = Some(MarketDataFilter::best_offers(3));
+        }
+        *streamer.last_message_ts.lock().unwrap() = Instant::now();
+
+        let watchdog = streamer.spawn_heartbeat_watchdog();
+
+        // First check past the threshold is at 11s, the next one a full threshold later at 22s
+        tokio::time::sleep(Duration::from_secs(15)).await;
+        let message: Value = serde_json::from_str(rx.try_recv().unwrap().trim()).unwrap();
+        assert_eq!(message["op"], "marketSubscription");
+        assert!(rx.try_recv().is_err());
+
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+
+        watchdog.abort();
+    }
+
+    #[tokio::test]
+    async fn test_segmented_image_applied_on_seg_end() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+
+        let start = r#"{"op":"mcm","id":1,"initialClk":"I1","clk":"C1","pt":1,"ct":"SUB_IMAGE","segmentType":"SEG_START","mc":[{"id":"1.1","img":true,"rc":[{"id":10,"batb":[[0,2.0,5.0]]}]}]}"#;
+        let middle = r#"{"op":"mcm","id":1,"clk":"C2","pt":2,"ct":"SUB_IMAGE","segmentType":"SEG","mc":[{"id":"1.2","img":true,"rc":[{"id":20,"batb":[[0,3.0,5.0]]}]}]}"#;
+        let end = r#"{"op":"mcm","id":1,"clk":"C3","pt":3,"ct":"SUB_IMAGE","segmentType":"SEG_END","mc":[{"id":"1.3","img":true,"rc":[{"id":30,"batb":[[0,4.0,5.0]]}]}]}"#;
+
+        streamer.handle_message(start.to_string()).await.unwrap();
+        streamer.handle_message(middle.to_string()).await.unwrap();
+        assert_eq!(market_count(&streamer), 0);
+        assert!(streamer.get_market_clock().is_empty());
+
+        streamer.handle_message(end.to_string()).await.unwrap();
+        assert_eq!(market_count(&streamer), 3);
+        assert!(streamer.segment_buffers.is_empty());
+
+        let clock = streamer.get_market_clock();
+        assert_eq!(clock.initial_clk.as_deref(), Some("I1"));
+        assert_eq!(clock.clk.as_deref(), Some("C3"));
+    }
+
+    #[test]
+    fn test_segment_without_start_is_dropped() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+
+        let orphan: Value = serde_json::from_str(
+            r#"{"op":"ocm","id":2,"clk":"O1","pt":1,"segmentType":"SEG_END","oc":[]}"#,
+        )
+        .unwrap();
+        assert!(streamer.buffer_segment(orphan).is_none());
+
+        let start: Value = serde_json::from_str(
+            r#"{"op":"ocm","id":2,"clk":"O1","pt":1,"segmentType":"SEG_START","oc":[{"id":"1.1"}]}"#,
+        )
+        .unwrap();
+        let end: Value = serde_json::from_str(
+            r#"{"op":"ocm","id":2,"clk":"O2","pt":2,"segmentType":"SEG_END","oc":[{"id":"1.2"}]}"#,
+        )
+        .unwrap();
+        assert!(streamer.buffer_segment(start).is_none());
+        let merged = streamer.buffer_segment(end).unwrap();
+        assert_eq!(merged["oc"].as_array().unwrap().len(), 2);
+        assert_eq!(merged["clk"], "O2");
+        assert!(merged.get("segmentType").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_events_are_emitted_in_stream_order() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        let (event_sender, mut events) = broadcast::channel(16);
+        streamer.set_event_sender(event_sender);
+
+        let messages = [
+            r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false,"connectionId":"abc"}"#,
+            r#"{"op":"mcm","id":2,"clk":"C1","pt":10,"ct":"SUB_IMAGE","mc":[{"id":"1.1","marketDefinition":{"status":"OPEN","inPlay":false,"complete":true,"marketTime":null},"rc":[{"id":10,"batb":[[0,2.0,5.0]]}]}]}"#,
+            r#"{"op":"ocm","id":3,"clk":"O1","pt":11,"ct":"SUB_IMAGE","oc":[{"id":"1.1"}]}"#,
+            r#"{"op":"ocm","id":3,"clk":"O2","pt":12,"ct":"HEARTBEAT"}"#,
+            r#"{"op":"mcm","id":2,"clk":"C2","pt":13,"ct":"HEARTBEAT"}"#,
+        ];
+        for message in messages {
+            streamer.handle_message(message.to_string()).await.unwrap();
+        }
+
+        match events.try_recv().unwrap() {
+            StreamEvent::Status(status) => {
+                assert!(status.is_success());
+                assert_eq!(status.connection_id.as_deref(), Some("abc"));
+            }
+            other => panic!("expected status, got {other:?}"),
+        }
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            StreamEvent::MarketDefinitionChange { ref market_id, .. } if market_id == "1.1"
+        ));
+        match events.try_recv().unwrap() {
+            StreamEvent::MarketChange {
+                market_id,
+                orderbooks,
+                publish_time,
+            } => {
+                assert_eq!(market_id, "1.1");
+                assert_eq!(orderbooks.len(), 1);
+                assert_eq!(publish_time, 10);
+            }
+            other => panic!("expected market change, got {other:?}"),
+        }
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            StreamEvent::OrderChange(ref ocm) if ocm.pt == 11
+        ));
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            StreamEvent::Heartbeat { ref op, publish_time: Some(12) } if op == "ocm"
+        ));
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            StreamEvent::Heartbeat { ref op, publish_time: Some(13) } if op == "mcm"
+        ));
+        assert!(events.try_recv().is_err());
+        assert_eq!(streamer.get_market_clock().clk.as_deref(), Some("C2"));
+    }
+
+    #[tokio::test]
+    async fn test_market_scope_drops_unsubscribed_markets() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        let scope = SharedMarketScope::default();
+        streamer.set_market_scope(Arc::clone(&scope));
+
+        let image = r#"{"op":"mcm","id":1,"clk":"C1","pt":1,"mc":[{"id":"1.1","rc":[{"id":10,"batb":[[0,2.0,5.0]]}]},{"id":"1.2","rc":[{"id":20,"batb":[[0,3.0,5.0]]}]}]}"#;
+        streamer.handle_message(image.to_string()).await.unwrap();
+        assert_eq!(market_count(&streamer), 2);
+
+        *scope.write().unwrap() = Some(HashSet::from(["1.1".to_string()]));
+        let late = r#"{"op":"mcm","id":1,"clk":"C2","pt":2,"mc":[{"id":"1.2","rc":[{"id":20,"batb":[[0,3.1,5.0]]}]}]}"#;
+        streamer.handle_message(late.to_string()).await.unwrap();
+
+        assert_eq!(market_count(&streamer), 1);
+        assert!(streamer.markets.read().unwrap().contains_key("1.1"));
+    }
+
+    #[tokio::test]
+    async fn test_fatal_status_emits_error_and_is_recorded() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        let (tx, mut rx) = broadcast::channel(16);
+        streamer.set_event_sender(tx);
+
+        let failure = r#"{"op":"status","id":1,"statusCode":"FAILURE","errorCode":"INVALID_SESSION_INFORMATION","errorMessage":"Session expired","connectionClosed":true}"#;
+        streamer.handle_message(failure.to_string()).await.unwrap();
+
+        assert!(matches!(rx.try_recv().unwrap(), StreamEvent::Status(_)));
+        match rx.try_recv().unwrap() {
+            StreamEvent::Error(status) => {
+                assert_eq!(
+                    status.error_code,
+                    Some(StreamErrorCode::InvalidSessionInformation)
+                );
+                assert!(status.connection_closed);
+            }
+            other => panic!("expected error, got {other:?}"),
+        }
+        assert!(streamer.fatal_status().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_non_fatal_status_is_not_recorded() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        streamer.auth_status =
+            Some(serde_json::from_str(r#"{"op":"status","id":1,"statusCode":"SUCCESS"}"#).unwrap());
+
+        let timeout = r#"{"op":"status","id":2,"statusCode":"FAILURE","errorCode":"TIMEOUT","connectionClosed":true}"#;
+        streamer.handle_message(timeout.to_string()).await.unwrap();
+
+        assert!(streamer.fatal_status().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_authentication() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        let (tx, rx) = mpsc::channel(4);
+        streamer.message_receiver = Some(rx);
+        let (sender, _writes) = mpsc::channel(4);
+        streamer.message_sender = Some(sender);
+
+        tx.send(r#"{"op":"connection","connectionId":"abc"}"#.to_string())
+            .await
+            .unwrap();
+        // Only the status echoing the authentication ID answers it
+        tx.send(
+            r#"{"op":"status","id":2,"statusCode":"FAILURE","errorCode":"TIMEOUT","connectionClosed":false}"#.to_string(),
+        )
+        .await
+        .unwrap();
+        tx.send(
+            r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false}"#.to_string(),
+        )
+        .await
+        .unwrap();
+        assert!(streamer.active_sender.read().unwrap().is_none());
+        assert!(streamer
+            .wait_for_authentication()
+            .await
+            .unwrap()
+            .is_success());
+        assert!(streamer.active_sender.read().unwrap().is_some());
+
+        streamer.auth_status = None;
+        tx.send(r#"{"op":"status","id":1,"statusCode":"FAILURE","errorCode":"NO_APP_KEY","connectionClosed":true}"#.to_string())
+            .await
+            .unwrap();
+        assert!(streamer.wait_for_authentication().await.is_err());
+        assert!(streamer.fatal_status().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_full_depth_fields_applied_to_book() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+
+        let full = r#"{"op":"mcm","id":1,"clk":"C1","pt":1,"mc":[{"id":"1.1","rc":[{"id":10,"atb":[[2.0,5.0],[1.99,8.0]],"trd":[[2.0,30.0]],"ltp":2.0,"tv":30.0,"spn":2.1,"spf":"Infinity","bdatb":[[0,2.0,5.0]]}]}]}"#;
+        streamer.handle_message(full.to_string()).await.unwrap();
+        let remove = r#"{"op":"mcm","id":1,"clk":"C2","pt":2,"mc":[{"id":"1.1","rc":[{"id":10,"atb":[[1.99,0]],"trd":[[2.02,4.0]],"ltp":2.02}]}]}"#;
+        streamer.handle_message(remove.to_string()).await.unwrap();
+
+        let book = runner_book(&streamer, "1.1", 10);
+        assert_eq!(book.available_to_back.len(), 1);
+        assert_eq!(book.traded.len(), 2);
+        assert_eq!(book.last_traded_price, Some(Decimal::new(202, 2)));
+        assert_eq!(book.traded_volume, Some(Decimal::new(30, 0)));
+        assert_eq!(book.sp_near, Some(Decimal::new(21, 1)));
+        assert_eq!(book.sp_far, None);
+        assert_eq!(book.display_bids.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resubscription_image_replaces_stale_levels() {
+        let mut streamer = BetfairStreamer::new("key".to_string(), "token".to_string());
+        let (tx, mut rx) = broadcast::channel(16);
+        streamer.set_event_sender(tx);
+
+        let messages = [
+            r#"{"op":"mcm","id":1,"initialClk":"I1","clk":"C1","pt":1,"ct":"SUB_IMAGE","mc":[{"id":"1.1","img":true,"rc":[{"id":10,"batb":[[0,2.0,5.0],[1,1.99,3.0]]},{"id":20,"batl":[[0,5.0,2.0]]}]}]}"#,
+            r#"{"op":"mcm","id":1,"clk":"C2","pt":2,"mc":[{"id":"1.1","rc":[{"id":10,"batb":[[2,1.98,1.0]]}]}]}"#,
+            // After a reconnect the server sends a fresh image rather than a delta
+            r#"{"op":"mcm","id":1,"initialClk":"I2","clk":"C3","pt":3,"ct":"SUB_IMAGE","mc":[{"id":"1.1","img":true,"rc":[{"id":10,"batb":[[0,2.1,4.0]]}]}]}"#,
+        ];
+        for message in messages {
+            streamer.handle_message(message.to_string()).await.unwrap();
+        }
+
+        let book = runner_book(&streamer, "1.1", 10);
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.bids[0].price, Decimal::new(21, 1));
+
+        let mut last_orderbooks = None;
+        while let Ok(event) = rx.try_recv() {
+            if let StreamEvent::MarketChange { orderbooks, .. } = event {
+                last_orderbooks = Some(orderbooks);
+            }
+        }
+        let orderbooks = last_orderbooks.unwrap();
+        assert_eq!(orderbooks.len(), 1);
+        assert!(!orderbooks.contains_key(&RunnerKey::from(20)));
     }
 }
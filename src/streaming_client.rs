@@ -1,46 +1,66 @@
 use crate::config::Config;
 use crate::connection_state::{ConnectionManager, ConnectionState};
-use crate::dto::streaming::{MarketDefinition, OrderChangeMessage, OrderFilter};
-use crate::order_cache::OrderCache;
+use crate::dto::streaming::{
+    MarketDataFilter, MarketDefinition, MarketFilter, OrderChangeMessage, OrderFilter, RunnerKey,
+    SubscriptionOptions,
+};
+use crate::market_cache::{MarketCache, SharedMarketCaches};
+use crate::order_cache::{OrderCache, DEFAULT_COMPLETED_ORDER_RETENTION};
 use crate::orderbook::Orderbook;
-use crate::streamer::BetfairStreamer;
+use crate::session::SessionToken;
+use crate::stream_transport::{StreamEndpoint, StreamTransport};
+use crate::streamer::{BetfairStreamer, SharedMarketScope, SharedMessageSender, SharedStreamClock};
+use crate::subscriptions::{
+    market_filter_subscription_message, market_set_subscription_message, OrderSubscription,
+    SharedSubscriptions,
+};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
+pub use crate::streamer::{StreamClock, StreamEvent};
+
+/// Events buffered per receiver before a slow receiver starts lagging
+const EVENT_CHANNEL_CAPACITY: usize = 4096;
+
 /// Type alias for orderbook callback function
 type OrderbookCallback = Arc<
-    dyn Fn(String, HashMap<String, Orderbook>, Option<MarketDefinition>) + Send + Sync + 'static,
+    dyn Fn(String, HashMap<RunnerKey, Orderbook>, Option<MarketDefinition>) + Send + Sync + 'static,
 >;
 type OrderUpdateCallback = Arc<dyn Fn(OrderChangeMessage) + Send + Sync + 'static>;
 
 /// A non-blocking streaming client for Betfair market data
 pub struct StreamingClient {
     api_key: String,
-    session_token: Option<String>,
+    session: SessionToken,
     streaming_task: Option<JoinHandle<()>>,
     command_sender: Option<mpsc::Sender<StreamingCommand>>,
-    orderbooks: Arc<RwLock<HashMap<String, HashMap<String, Orderbook>>>>,
+    orderbooks: Arc<RwLock<HashMap<String, HashMap<RunnerKey, Orderbook>>>>,
+    markets: SharedMarketCaches,
     orders: Arc<RwLock<HashMap<String, OrderCache>>>,
     is_connected: Arc<RwLock<bool>>,
     last_update_times: Arc<RwLock<HashMap<String, Instant>>>,
     custom_orderbook_callback: Option<OrderbookCallback>,
     custom_order_callback: Option<OrderUpdateCallback>,
     connection_manager: ConnectionManager,
-    subscribed_markets: Arc<RwLock<HashMap<String, usize>>>,
-    subscribed_to_orders: Arc<RwLock<bool>>,
-    order_filter: Arc<RwLock<Option<OrderFilter>>>,
+    event_sender: broadcast::Sender<StreamEvent>,
+    subscriptions: SharedSubscriptions,
+    market_scope: SharedMarketScope,
+    market_clock: SharedStreamClock,
+    order_clock: SharedStreamClock,
     enable_reconnection: bool,
+    order_retention: Duration,
+    transport: StreamTransport,
 }
 
 #[derive(Debug)]
 enum StreamingCommand {
-    Subscribe(String, usize),           // market_id, levels
-    SubscribeBatch(Vec<String>, usize), // market_ids, levels
+    SubscribeBatch(Vec<String>, MarketDataFilter), // market_ids, data filter
+    SubscribeFilter(MarketFilter, MarketDataFilter),
     Unsubscribe(String),
     SubscribeOrders(Option<OrderFilter>), // order subscription with optional filter
     Stop,
@@ -51,20 +71,25 @@ impl StreamingClient {
     pub fn new(api_key: String) -> Self {
         Self {
             api_key,
-            session_token: None,
+            session: SessionToken::default(),
             streaming_task: None,
             command_sender: None,
             orderbooks: Arc::new(RwLock::new(HashMap::new())),
+            markets: SharedMarketCaches::default(),
             orders: Arc::new(RwLock::new(HashMap::new())),
             is_connected: Arc::new(RwLock::new(false)),
             last_update_times: Arc::new(RwLock::new(HashMap::new())),
             custom_orderbook_callback: None,
             custom_order_callback: None,
             connection_manager: ConnectionManager::new(),
-            subscribed_markets: Arc::new(RwLock::new(HashMap::new())),
-            subscribed_to_orders: Arc::new(RwLock::new(false)),
-            order_filter: Arc::new(RwLock::new(None)),
+            event_sender: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            subscriptions: SharedSubscriptions::default(),
+            market_scope: SharedMarketScope::default(),
+            market_clock: SharedStreamClock::default(),
+            order_clock: SharedStreamClock::default(),
             enable_reconnection: true,
+            order_retention: DEFAULT_COMPLETED_ORDER_RETENTION,
+            transport: StreamTransport::default(),
         }
     }
 
@@ -72,31 +97,81 @@ impl StreamingClient {
     pub fn with_session_token(api_key: String, session_token: String) -> Self {
         Self {
             api_key,
-            session_token: Some(session_token),
+            session: SessionToken::new(session_token),
             streaming_task: None,
             command_sender: None,
             orderbooks: Arc::new(RwLock::new(HashMap::new())),
+            markets: SharedMarketCaches::default(),
             orders: Arc::new(RwLock::new(HashMap::new())),
             is_connected: Arc::new(RwLock::new(false)),
             last_update_times: Arc::new(RwLock::new(HashMap::new())),
             custom_orderbook_callback: None,
             custom_order_callback: None,
             connection_manager: ConnectionManager::new(),
-            subscribed_markets: Arc::new(RwLock::new(HashMap::new())),
-            subscribed_to_orders: Arc::new(RwLock::new(false)),
-            order_filter: Arc::new(RwLock::new(None)),
+            event_sender: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            subscriptions: SharedSubscriptions::default(),
+            market_scope: SharedMarketScope::default(),
+            market_clock: SharedStreamClock::default(),
+            order_clock: SharedStreamClock::default(),
             enable_reconnection: true,
+            order_retention: DEFAULT_COMPLETED_ORDER_RETENTION,
+            transport: StreamTransport::default(),
         }
     }
 
     /// Create from Config for backward compatibility
     pub fn from_config(config: Config) -> Self {
-        Self::new(config.betfair.api_key.clone())
+        let mut client = Self::new(config.betfair.api_key.clone());
+        client.use_config_endpoint(&config);
+        client
+    }
+
+    /// Connect to the stream endpoint of the config: `stream_url` if set, otherwise that of
+    /// its custom `endpoints` or jurisdiction. An invalid one keeps the default endpoint.
+    pub(crate) fn use_config_endpoint(&mut self, config: &Config) {
+        match config.stream_endpoint() {
+            Ok(endpoint) => self.set_stream_endpoint(endpoint),
+            Err(e) => error!("Ignoring invalid stream endpoint, using the default: {e}"),
+        }
+    }
+
+    /// Connect to this endpoint instead of the production Betfair stream
+    pub fn set_stream_endpoint(&mut self, endpoint: StreamEndpoint) {
+        self.transport = StreamTransport::Endpoint(endpoint);
     }
 
-    /// Set or update the session token
+    /// Open connections through a custom transport, e.g. an in-memory duplex in tests
+    pub fn set_transport(&mut self, transport: StreamTransport) {
+        self.transport = transport;
+    }
+
+    /// Set or update the session token, including that of an attached session
     pub fn set_session_token(&mut self, token: String) {
-        self.session_token = Some(token);
+        self.session.set(token);
+    }
+
+    /// Share the session of a `RestClient`, so reconnections use the token of its latest login
+    pub fn attach_session(&mut self, session: SessionToken) {
+        self.session = session;
+    }
+
+    /// The session token new connections authenticate with
+    pub fn session(&self) -> SessionToken {
+        self.session.clone()
+    }
+
+    /// Set conflation/heartbeat for market subscriptions sent from now on (including resubscriptions)
+    pub fn set_market_subscription_options(&self, options: SubscriptionOptions) {
+        if let Ok(mut registry) = self.subscriptions.write() {
+            registry.market_options = options;
+        }
+    }
+
+    /// Set conflation/heartbeat for order subscriptions sent from now on (including resubscriptions)
+    pub fn set_order_subscription_options(&self, options: SubscriptionOptions) {
+        if let Ok(mut registry) = self.subscriptions.write() {
+            registry.order_options = options;
+        }
     }
 
     /// Enable or disable automatic reconnection
@@ -104,6 +179,12 @@ impl StreamingClient {
         self.enable_reconnection = enabled;
     }
 
+    /// How long fully matched, lapsed or cancelled orders, and closed markets, stay in the
+    /// order caches. Applies from the next `start()`.
+    pub fn set_order_retention(&mut self, retention: Duration) {
+        self.order_retention = retention;
+    }
+
     /// Get the current connection state
     pub async fn get_connection_state(&self) -> ConnectionState {
         self.connection_manager.get_state().await
@@ -114,11 +195,30 @@ impl StreamingClient {
         self.connection_manager.get_reconnect_attempts().await
     }
 
+    /// Receive typed stream events.
+    ///
+    /// Events are delivered in the order they arrived on the connection, after the
+    /// shared orderbook/order state has been updated. A receiver that falls more than
+    /// `EVENT_CHANNEL_CAPACITY` events behind gets `RecvError::Lagged` and skips ahead.
+    pub fn events(&self) -> broadcast::Receiver<StreamEvent> {
+        self.event_sender.subscribe()
+    }
+
     /// Get a reference to the shared orderbooks
-    pub fn get_orderbooks(&self) -> Arc<RwLock<HashMap<String, HashMap<String, Orderbook>>>> {
+    pub fn get_orderbooks(&self) -> Arc<RwLock<HashMap<String, HashMap<RunnerKey, Orderbook>>>> {
         self.orderbooks.clone()
     }
 
+    /// Get the shared market caches with typed definitions, runner states and books
+    pub fn get_market_caches(&self) -> SharedMarketCaches {
+        self.markets.clone()
+    }
+
+    /// Get a consistent copy of one market: definition, runners and books from the same update
+    pub fn get_market_snapshot(&self, market_id: &str) -> Option<MarketCache> {
+        self.markets.read().ok()?.get(market_id).cloned()
+    }
+
     /// Get the last update time for a market
     pub fn get_last_update_time(&self, market_id: &str) -> Option<Instant> {
         self.last_update_times.read().ok()?.get(market_id).copied()
@@ -127,7 +227,10 @@ impl StreamingClient {
     /// Set a custom orderbook callback that will be called immediately when new data arrives
     pub fn set_orderbook_callback<F>(&mut self, callback: F)
     where
-        F: Fn(String, HashMap<String, Orderbook>, Option<MarketDefinition>) + Send + Sync + 'static,
+        F: Fn(String, HashMap<RunnerKey, Orderbook>, Option<MarketDefinition>)
+            + Send
+            + Sync
+            + 'static,
     {
         self.custom_orderbook_callback = Some(Arc::new(callback));
     }
@@ -143,16 +246,33 @@ impl StreamingClient {
         self.orders.clone()
     }
 
-    /// Initialize and start the streaming client in a background task with reconnection support
+    /// Get the last clocks received on the market subscription
+    pub fn get_market_clock(&self) -> StreamClock {
+        self.market_clock
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get the last clocks received on the order subscription
+    pub fn get_order_clock(&self) -> StreamClock {
+        self.order_clock
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_default()
+    }
+
+    /// Initialize and start the streaming client in a background task with reconnection support.
+    ///
+    /// Returns once the stream has accepted the session, or the authentication failure.
+    /// Fatal status codes later on stop the client with `ConnectionState::Failed` and a
+    /// `StreamEvent::Error`.
     pub async fn start(&mut self) -> Result<()> {
         // Ensure we have a session token
-        let session_token = self
-            .session_token
-            .as_ref()
-            .ok_or_else(|| {
-                anyhow::anyhow!("Session token not set. Call set_session_token() first.")
-            })?
-            .clone();
+        let session_token = self.session.get().ok_or_else(|| {
+            anyhow::anyhow!("Session token not set. Call set_session_token() first.")
+        })?;
+        let session = self.session.clone();
 
         // Create command channel
         let (cmd_tx, mut cmd_rx) = mpsc::channel::<StreamingCommand>(100);
@@ -161,32 +281,39 @@ impl StreamingClient {
         // Clone necessary data for the task
         let api_key = self.api_key.clone();
         let orderbooks = self.orderbooks.clone();
+        let markets = self.markets.clone();
         let orders = self.orders.clone();
         let is_connected = self.is_connected.clone();
         let last_update_times = self.last_update_times.clone();
         let custom_orderbook_callback = self.custom_orderbook_callback.clone();
         let custom_order_callback = self.custom_order_callback.clone();
         let connection_manager = self.connection_manager.clone();
-        let subscribed_markets = self.subscribed_markets.clone();
-        let subscribed_to_orders = self.subscribed_to_orders.clone();
-        let order_filter = self.order_filter.clone();
+        let event_sender = self.event_sender.clone();
+        let subscriptions = self.subscriptions.clone();
+        let market_scope = self.market_scope.clone();
+        let market_clock = self.market_clock.clone();
+        let order_clock = self.order_clock.clone();
         let enable_reconnection = self.enable_reconnection;
+        let order_retention = self.order_retention;
+        let transport = self.transport.clone();
 
         // Create a oneshot channel to signal when ready (only used once on first connection)
         let (ready_tx, ready_rx) = oneshot::channel();
         let ready_tx = Arc::new(RwLock::new(Some(ready_tx)));
 
         // Create a shared message sender that will be updated on each connection
-        let active_message_sender = Arc::new(RwLock::new(None::<mpsc::Sender<String>>));
+        let active_message_sender = SharedMessageSender::default();
         let active_message_sender_clone = active_message_sender.clone();
 
         // Spawn command handler task that works across reconnections
         let cmd_sender_ref = active_message_sender.clone();
-        let subscribed_markets_ref = subscribed_markets.clone();
-        let subscribed_to_orders_ref = subscribed_to_orders.clone();
-        let order_filter_ref = order_filter.clone();
+        let subscriptions_ref = subscriptions.clone();
+        let market_scope_ref = market_scope.clone();
         let orderbooks_ref = orderbooks.clone();
+        let markets_ref = markets.clone();
         let last_update_times_ref = last_update_times.clone();
+        let market_clock_ref = market_clock.clone();
+        let order_clock_ref = order_clock.clone();
 
         tokio::spawn(async move {
             while let Some(cmd) = cmd_rx.recv().await {
@@ -207,89 +334,168 @@ impl StreamingClient {
                 let sender = sender.unwrap();
 
                 match cmd {
-                    StreamingCommand::Subscribe(market_id, levels) => {
-                        info!(
-                            "Processing subscription for market {market_id} with {levels} levels"
-                        );
-
-                        if let Ok(mut markets) = subscribed_markets_ref.write() {
-                            markets.insert(market_id.clone(), levels);
-                        }
-
-                        if let Ok(mut obs) = orderbooks_ref.write() {
-                            obs.remove(&market_id);
-                        }
-                        if let Ok(mut times) = last_update_times_ref.write() {
-                            times.remove(&market_id);
-                        }
-
-                        let sub_msg = Self::create_market_subscription_message(&market_id, levels);
-                        if let Err(e) = sender.send(sub_msg).await {
-                            error!("Failed to send subscription: {e}");
-                        }
-                    }
-                    StreamingCommand::SubscribeBatch(market_ids, levels) => {
+                    StreamingCommand::SubscribeBatch(market_ids, data_filter) => {
                         info!(
                             "Processing batch subscription for {} markets",
                             market_ids.len()
                         );
 
-                        if let Ok(mut markets) = subscribed_markets_ref.write() {
-                            for market_id in &market_ids {
-                                markets.insert(market_id.clone(), levels);
-                            }
-                        }
+                        // Betfair replaces the whole subscription, so resend every tracked
+                        // market; one data filter applies to all of them
+                        let (markets, options) = if let Ok(mut registry) = subscriptions_ref.write()
+                        {
+                            registry.markets.extend(market_ids.iter().cloned());
+                            registry.market_data_filter = Some(data_filter.clone());
+                            registry.market_filter = None;
+                            (registry.markets.clone(), registry.market_options)
+                        } else {
+                            continue;
+                        };
+                        set_market_scope(&market_scope_ref, Some(&markets));
 
                         if let Ok(mut obs) = orderbooks_ref.write() {
                             for market_id in &market_ids {
                                 obs.remove(market_id);
                             }
                         }
+                        if let Ok(mut cached) = markets_ref.write() {
+                            for market_id in &market_ids {
+                                cached.remove(market_id);
+                            }
+                        }
                         if let Ok(mut times) = last_update_times_ref.write() {
                             for market_id in &market_ids {
                                 times.remove(market_id);
                             }
                         }
 
-                        let sub_msg =
-                            Self::create_batch_market_subscription_message(&market_ids, levels);
+                        // The market set changed, so the old clocks no longer apply
+                        if let Ok(mut clock) = market_clock_ref.write() {
+                            clock.clear();
+                        }
+
+                        let sub_msg = market_set_subscription_message(
+                            &markets,
+                            &data_filter,
+                            &options,
+                            &StreamClock::default(),
+                        );
                         if let Err(e) = sender.send(sub_msg).await {
                             error!("Failed to send batch subscription: {e}");
                         }
                     }
-                    StreamingCommand::Unsubscribe(market_id) => {
-                        if let Ok(mut markets) = subscribed_markets_ref.write() {
-                            markets.remove(&market_id);
+                    StreamingCommand::SubscribeFilter(market_filter, data_filter) => {
+                        info!("Processing filter subscription: {market_filter:?}");
+
+                        // A filter subscription replaces any explicit market ID subscription
+                        let options = if let Ok(mut registry) = subscriptions_ref.write() {
+                            registry.markets.clear();
+                            registry.market_data_filter = None;
+                            registry.market_filter =
+                                Some((market_filter.clone(), data_filter.clone()));
+                            registry.market_options
+                        } else {
+                            continue;
+                        };
+                        set_market_scope(&market_scope_ref, None);
+                        if let Ok(mut clock) = market_clock_ref.write() {
+                            clock.clear();
+                        }
+
+                        let sub_msg = market_filter_subscription_message(
+                            &market_filter,
+                            &data_filter,
+                            &options,
+                            &StreamClock::default(),
+                        );
+                        if let Err(e) = sender.send(sub_msg).await {
+                            error!("Failed to send filter subscription: {e}");
                         }
+                    }
+                    StreamingCommand::Unsubscribe(market_id) => {
+                        let (removed, markets, filter_active, options) =
+                            if let Ok(mut registry) = subscriptions_ref.write() {
+                                (
+                                    registry
+                                        .markets
+                                        .remove(&market_id)
+                                        .then(|| registry.market_data_filter.clone())
+                                        .flatten(),
+                                    registry.markets.clone(),
+                                    registry.market_filter.is_some(),
+                                    registry.market_options,
+                                )
+                            } else {
+                                continue;
+                            };
                         if let Ok(mut obs) = orderbooks_ref.write() {
                             obs.remove(&market_id);
                         }
+                        if let Ok(mut cached) = markets_ref.write() {
+                            cached.remove(&market_id);
+                        }
                         if let Ok(mut times) = last_update_times_ref.write() {
                             times.remove(&market_id);
                         }
+
+                        let Some(data_filter) = removed else {
+                            if filter_active {
+                                warn!("Market {market_id} is delivered by a filter subscription and cannot be removed individually");
+                            } else {
+                                debug!("Market {market_id} was not subscribed");
+                            }
+                            continue;
+                        };
+
+                        info!(
+                            "Unsubscribing from market {market_id}, {} markets remain",
+                            markets.len()
+                        );
+                        set_market_scope(&market_scope_ref, Some(&markets));
+
+                        // Resume the remaining markets from the current clock; with nothing
+                        // left there is no stream to resume
+                        let clock = if markets.is_empty() {
+                            if let Ok(mut clock) = market_clock_ref.write() {
+                                clock.clear();
+                            }
+                            StreamClock::default()
+                        } else {
+                            market_clock_ref
+                                .read()
+                                .map(|c| c.clone())
+                                .unwrap_or_default()
+                        };
+
+                        let sub_msg = market_set_subscription_message(
+                            &markets,
+                            &data_filter,
+                            &options,
+                            &clock,
+                        );
+                        if let Err(e) = sender.send(sub_msg).await {
+                            error!("Failed to send unsubscription: {e}");
+                        }
                     }
                     StreamingCommand::SubscribeOrders(filter) => {
                         info!("Processing order subscription");
 
-                        if let Ok(mut subscribed) = subscribed_to_orders_ref.write() {
-                            *subscribed = true;
-                        }
-                        if let Ok(mut filter_state) = order_filter_ref.write() {
-                            *filter_state = filter.clone();
+                        if let Ok(mut clock) = order_clock_ref.write() {
+                            clock.clear();
                         }
 
-                        let filter_json = if let Some(f) = filter {
-                            serde_json::to_string(&f).unwrap_or_else(|_| "{}".to_string())
+                        // Registered first so a reconnection replays this exact filter
+                        let sub_msg = if let Ok(mut registry) = subscriptions_ref.write() {
+                            registry.orders = Some(OrderSubscription { filter });
+                            registry.order_subscription_message(&StreamClock::default())
                         } else {
-                            "{}".to_string()
+                            None
                         };
 
-                        let sub_msg = format!(
-                            "{{\"op\":\"orderSubscription\",\"orderFilter\":{filter_json},\"segmentationEnabled\":true,\"heartbeatMs\":5000}}\r\n"
-                        );
-
-                        if let Err(e) = sender.send(sub_msg).await {
-                            error!("Failed to send order subscription: {e}");
+                        if let Some(sub_msg) = sub_msg {
+                            if let Err(e) = sender.send(sub_msg).await {
+                                error!("Failed to send order subscription: {e}");
+                            }
                         }
                     }
                     StreamingCommand::Stop => {
@@ -305,20 +511,106 @@ impl StreamingClient {
             let mut first_start = true;
             let mut reconnect_attempt = 0u32;
 
+            // Create the streamer once so cached books and clocks survive reconnections
+            let mut streamer = BetfairStreamer::new(api_key.clone(), session_token.clone());
+            streamer.set_session(session);
+            streamer.set_clocks(market_clock.clone(), order_clock.clone());
+            streamer.set_event_sender(event_sender.clone());
+            streamer.set_market_scope(market_scope.clone());
+            streamer.set_transport(transport);
+            streamer.set_market_caches(markets.clone());
+            streamer.set_subscriptions(subscriptions.clone());
+            streamer.set_active_sender(active_message_sender_clone.clone());
+
+            info!("Streaming client initialized");
+
+            // Set up orderbook callback
+            let orderbooks_ref = orderbooks.clone();
+            let update_times_ref = last_update_times.clone();
+            let callback_clone = custom_orderbook_callback.clone();
+            streamer.set_orderbook_callback(move |market_id, runner_orderbooks, market_definition| {
+            info!("Orderbook callback triggered for market {market_id} with {} runners", runner_orderbooks.len());
+
+            if let Some(ref market_def) = market_definition {
+                debug!("Market {market_id} status: {:?}, inPlay: {}", market_def.status, market_def.in_play);
+            }
+
+            if let Ok(mut obs) = orderbooks_ref.write() {
+                obs.insert(market_id.clone(), runner_orderbooks.clone());
+                info!("Successfully updated shared orderbooks for market {market_id}. Total markets in shared state: {}", obs.len());
+            } else {
+                error!("Failed to acquire write lock on shared orderbooks for market {market_id}");
+            }
+
+            if let Ok(mut times) = update_times_ref.write() {
+                times.insert(market_id.clone(), Instant::now());
+                debug!("Updated last update time for market {market_id}");
+            } else {
+                error!("Failed to acquire write lock on update times for market {market_id}");
+            }
+
+            if let Some(ref callback) = callback_clone {
+                debug!("Calling custom orderbook callback for market {market_id}");
+                callback(market_id, runner_orderbooks, market_definition);
+            }
+        });
+
+            let orders_ref = orders.clone();
+            let order_callback_clone = custom_order_callback.clone();
+            let order_event_sender = event_sender.clone();
+            streamer.set_orderupdate_callback(move |order_change_message| {
+                let mut order_events = Vec::new();
+                if let Ok(mut order_cache_map) = orders_ref.write() {
+                    let publish_time = order_change_message.pt;
+                    for order_change in &order_change_message.order_changes {
+                        let market_id = &order_change.id;
+                        order_events.extend(
+                            order_cache_map
+                                .entry(market_id.clone())
+                                .or_insert_with(|| {
+                                    OrderCache::with_retention(market_id.clone(), order_retention)
+                                })
+                                .apply_order_change(order_change, publish_time),
+                        );
+                    }
+
+                    // Heartbeats carry a publish time too, so markets without changes still age out
+                    order_cache_map.retain(|_, cache| {
+                        cache.prune_completed(publish_time);
+                        !cache.is_expired(publish_time)
+                    });
+                }
+
+                // Sent once the cache is updated, so receivers see the state the event describes
+                for order_event in order_events {
+                    let _ = order_event_sender.send(StreamEvent::OrderEvent(order_event));
+                }
+
+                if let Some(ref callback) = order_callback_clone {
+                    callback(order_change_message);
+                }
+            });
+
             loop {
                 if !first_start && enable_reconnection {
-                    connection_manager
-                        .set_state(ConnectionState::Reconnecting)
-                        .await;
+                    set_connection_state(
+                        &connection_manager,
+                        &event_sender,
+                        ConnectionState::Reconnecting,
+                    )
+                    .await;
                     reconnect_attempt += 1;
 
                     if reconnect_attempt > 5 {
                         error!("Max reconnection attempts exceeded");
-                        connection_manager
-                            .set_state(ConnectionState::Failed(
+                        set_connection_state(
+                            &connection_manager,
+                            &event_sender,
+                            ConnectionState::Failed(
                                 "Max reconnection attempts exceeded".to_string(),
-                            ))
-                            .await;
+                            ),
+                        )
+                        .await;
                         break;
                     }
 
@@ -330,96 +622,12 @@ impl StreamingClient {
                     break;
                 }
 
-                connection_manager
-                    .set_state(ConnectionState::Connecting)
-                    .await;
-
-                // Create the streamer
-                let mut streamer = BetfairStreamer::new(api_key.clone(), session_token.clone());
-
-                info!("Streaming client initialized");
-
-                // Set up orderbook callback
-                let orderbooks_ref = orderbooks.clone();
-                let update_times_ref = last_update_times.clone();
-                let callback_clone = custom_orderbook_callback.clone();
-                streamer.set_orderbook_callback(move |market_id, runner_orderbooks, market_definition| {
-                info!("Orderbook callback triggered for market {market_id} with {} runners", runner_orderbooks.len());
-
-                if let Some(ref market_def) = market_definition {
-                    debug!("Market {market_id} status: {:?}, inPlay: {}", market_def.status, market_def.in_play);
-                }
-
-                if let Ok(mut obs) = orderbooks_ref.write() {
-                    obs.insert(market_id.clone(), runner_orderbooks.clone());
-                    info!("Successfully updated shared orderbooks for market {market_id}. Total markets in shared state: {}", obs.len());
-                } else {
-                    error!("Failed to acquire write lock on shared orderbooks for market {market_id}");
-                }
-
-                if let Ok(mut times) = update_times_ref.write() {
-                    times.insert(market_id.clone(), Instant::now());
-                    debug!("Updated last update time for market {market_id}");
-                } else {
-                    error!("Failed to acquire write lock on update times for market {market_id}");
-                }
-
-                if let Some(ref callback) = callback_clone {
-                    debug!("Calling custom orderbook callback for market {market_id}");
-                    callback(market_id, runner_orderbooks, market_definition);
-                }
-            });
-
-                let orders_ref = orders.clone();
-                let order_callback_clone = custom_order_callback.clone();
-                streamer.set_orderupdate_callback(move |order_change_message| {
-                    if let Ok(mut order_cache_map) = orders_ref.write() {
-                        for order_change in &order_change_message.order_changes {
-                            let market_id = &order_change.id;
-                            let cache = order_cache_map
-                                .entry(market_id.clone())
-                                .or_insert_with(|| OrderCache::new(market_id.clone()));
-
-                            cache.update_timestamp(order_change_message.pt);
-
-                            if order_change.full_image {
-                                cache.clear();
-                            }
-
-                            if let Some(ref runner_changes) = order_change.order_runner_change {
-                                for runner_change in runner_changes {
-                                    let runner = cache.get_runner_mut(runner_change.id);
-                                    runner.set_handicap(runner_change.handicap);
-
-                                    if runner_change.full_image {
-                                        if let Some(ref orders) = runner_change.unmatched_orders {
-                                            runner.apply_full_image(orders.clone());
-                                        } else {
-                                            runner.orders.clear();
-                                        }
-                                    } else if let Some(ref orders) = runner_change.unmatched_orders
-                                    {
-                                        for order in orders {
-                                            runner.update_order(order.clone());
-                                        }
-                                    }
-
-                                    if let Some(ref matched_backs) = runner_change.matched_backs {
-                                        runner.update_matched_backs(matched_backs.clone());
-                                    }
-
-                                    if let Some(ref matched_lays) = runner_change.matched_lays {
-                                        runner.update_matched_lays(matched_lays.clone());
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    if let Some(ref callback) = order_callback_clone {
-                        callback(order_change_message);
-                    }
-                });
+                set_connection_state(
+                    &connection_manager,
+                    &event_sender,
+                    ConnectionState::Connecting,
+                )
+                .await;
 
                 // Connect to streaming service
                 if let Err(e) = streamer.connect_betfair_tls_stream().await {
@@ -436,6 +644,35 @@ impl StreamingClient {
                     continue;
                 }
 
+                // Nothing is usable until the server accepts the session
+                if let Err(e) = streamer.wait_for_authentication().await {
+                    error!("Streaming authentication failed: {e}");
+                    let fatal = streamer
+                        .fatal_status()
+                        .filter(|_| !streamer.session_refreshed())
+                        .map(|status| status.to_string());
+                    if let Some(reason) = &fatal {
+                        set_connection_state(
+                            &connection_manager,
+                            &event_sender,
+                            ConnectionState::Failed(reason.clone()),
+                        )
+                        .await;
+                    }
+                    if first_start {
+                        if let Ok(mut tx_opt) = ready_tx.write() {
+                            if let Some(tx) = tx_opt.take() {
+                                let _ = tx.send(Err(e));
+                            }
+                        }
+                        return;
+                    }
+                    if fatal.is_some() {
+                        break;
+                    }
+                    continue;
+                }
+
                 info!("Connected to streaming service");
 
                 // Mark as connected
@@ -443,9 +680,12 @@ impl StreamingClient {
                     *connected = true;
                 }
 
-                connection_manager
-                    .set_state(ConnectionState::Connected)
-                    .await;
+                set_connection_state(
+                    &connection_manager,
+                    &event_sender,
+                    ConnectionState::Connected,
+                )
+                .await;
 
                 if !first_start {
                     info!("Successfully reconnected, resetting reconnect counter");
@@ -476,68 +716,11 @@ impl StreamingClient {
                     *sender_guard = Some(message_sender.clone());
                 }
 
-                // Resubscribe to markets after reconnection
+                // Restore every subscription with its original parameters after reconnection
                 if !first_start {
-                    info!("Resubscribing to previously subscribed markets");
-
-                    // Get market subscription data without holding the lock
-                    let (market_list, levels) = {
-                        if let Ok(markets) = subscribed_markets.read() {
-                            if !markets.is_empty() {
-                                let list: Vec<String> = markets.keys().cloned().collect();
-                                let lvl = markets.values().next().copied().unwrap_or(10);
-                                (list, lvl)
-                            } else {
-                                (vec![], 10)
-                            }
-                        } else {
-                            (vec![], 10)
-                        }
-                    };
-
-                    if !market_list.is_empty() {
-                        let sub_msg =
-                            Self::create_batch_market_subscription_message(&market_list, levels);
-                        if let Err(e) = message_sender.send(sub_msg).await {
-                            error!("Failed to resubscribe to markets: {e}");
-                        } else {
-                            info!("Resubscribed to {} markets", market_list.len());
-                        }
-                    }
-
-                    // Resubscribe to orders
-                    let (should_subscribe, filter_json) = {
-                        if let Ok(subscribed) = subscribed_to_orders.read() {
-                            if *subscribed {
-                                if let Ok(filter) = order_filter.read() {
-                                    let json = if let Some(ref f) = *filter {
-                                        serde_json::to_string(f)
-                                            .unwrap_or_else(|_| "{}".to_string())
-                                    } else {
-                                        "{}".to_string()
-                                    };
-                                    (true, json)
-                                } else {
-                                    (false, String::new())
-                                }
-                            } else {
-                                (false, String::new())
-                            }
-                        } else {
-                            (false, String::new())
-                        }
-                    };
-
-                    if should_subscribe {
-                        info!("Resubscribing to order updates");
-                        let sub_msg = format!(
-                            "{{\"op\":\"orderSubscription\",\"orderFilter\":{filter_json},\"segmentationEnabled\":true,\"heartbeatMs\":5000}}\r\n"
-                        );
-                        if let Err(e) = message_sender.send(sub_msg).await {
-                            error!("Failed to resubscribe to orders: {e}");
-                        } else {
-                            info!("Resubscribed to order updates");
-                        }
+                    info!("Resubscribing to previous subscriptions");
+                    if let Err(e) = streamer.resubscribe().await {
+                        error!("Failed to resubscribe: {e}");
                     }
                 }
 
@@ -559,9 +742,12 @@ impl StreamingClient {
                     *connected = false;
                 }
 
-                connection_manager
-                    .set_state(ConnectionState::Disconnected)
-                    .await;
+                set_connection_state(
+                    &connection_manager,
+                    &event_sender,
+                    ConnectionState::Disconnected,
+                )
+                .await;
 
                 // Clear the active message sender
                 if let Ok(mut sender_guard) = active_message_sender_clone.write() {
@@ -577,6 +763,20 @@ impl StreamingClient {
                     }
                 }
 
+                // Reconnecting cannot fix a rejected session or exceeded limits,
+                // unless the session has been replaced since
+                if streamer.session_refreshed() {
+                    warn!("Stream session was rejected but has been refreshed, reconnecting");
+                } else if let Some(status) = streamer.fatal_status() {
+                    set_connection_state(
+                        &connection_manager,
+                        &event_sender,
+                        ConnectionState::Failed(status.to_string()),
+                    )
+                    .await;
+                    break;
+                }
+
                 // Loop will continue for reconnection if enabled
                 if !enable_reconnection {
                     info!("Reconnection disabled, exiting streaming task");
@@ -603,13 +803,27 @@ impl StreamingClient {
         }
     }
 
-    /// Subscribe to a market
+    /// Subscribe to a market's best offers and market definition
     pub async fn subscribe_to_market(&self, market_id: String, levels: usize) -> Result<()> {
         info!("Subscribing to market {market_id} with {levels} levels");
+        self.subscribe_to_market_with_data_filter(market_id, MarketDataFilter::best_offers(levels))
+            .await
+    }
 
+    /// Subscribe to a market with an explicit set of market data fields.
+    ///
+    /// Markets already subscribed by ID stay subscribed; the data filter applies to all of them.
+    pub async fn subscribe_to_market_with_data_filter(
+        &self,
+        market_id: String,
+        data_filter: MarketDataFilter,
+    ) -> Result<()> {
         if let Some(sender) = &self.command_sender {
             match sender
-                .send(StreamingCommand::Subscribe(market_id.clone(), levels))
+                .send(StreamingCommand::SubscribeBatch(
+                    vec![market_id.clone()],
+                    data_filter,
+                ))
                 .await
             {
                 Ok(_) => {
@@ -628,15 +842,29 @@ impl StreamingClient {
     }
 
     /// Subscribe to multiple markets in a single subscription (recommended approach)
-    /// This sends all markets in one message to Betfair, avoiding subscription replacement
+    /// This sends all tracked markets in one message to Betfair, so earlier markets are kept
     pub async fn subscribe_to_markets(&self, market_ids: Vec<String>, levels: usize) -> Result<()> {
+        self.subscribe_to_markets_with_data_filter(
+            market_ids,
+            MarketDataFilter::best_offers(levels),
+        )
+        .await
+    }
+
+    /// Subscribe to multiple markets with an explicit set of market data fields,
+    /// e.g. `EX_ALL_OFFERS`, `EX_TRADED`, `EX_LTP` or `SP_PROJECTED`
+    pub async fn subscribe_to_markets_with_data_filter(
+        &self,
+        market_ids: Vec<String>,
+        data_filter: MarketDataFilter,
+    ) -> Result<()> {
         if market_ids.is_empty() {
             return Err(anyhow::anyhow!("Cannot subscribe to empty market list"));
         }
 
         if let Some(sender) = &self.command_sender {
             sender
-                .send(StreamingCommand::SubscribeBatch(market_ids, levels))
+                .send(StreamingCommand::SubscribeBatch(market_ids, data_filter))
                 .await?;
         } else {
             return Err(anyhow::anyhow!("Streaming client not started"));
@@ -644,7 +872,32 @@ impl StreamingClient {
         Ok(())
     }
 
-    /// Unsubscribe from a market
+    /// Subscribe to every market matching a streaming market filter.
+    ///
+    /// Markets that start matching the filter later (e.g. newly loaded races) are
+    /// streamed automatically. This replaces any market ID subscription.
+    pub async fn subscribe_with_filter(
+        &self,
+        market_filter: impl Into<MarketFilter>,
+        data_filter: MarketDataFilter,
+    ) -> Result<()> {
+        if let Some(sender) = &self.command_sender {
+            sender
+                .send(StreamingCommand::SubscribeFilter(
+                    market_filter.into(),
+                    data_filter,
+                ))
+                .await?;
+        } else {
+            return Err(anyhow::anyhow!("Streaming client not started"));
+        }
+        Ok(())
+    }
+
+    /// Unsubscribe from a market.
+    ///
+    /// Resubscribes to the remaining markets with the same data filter, resuming from the
+    /// current clock. Markets delivered by a filter subscription cannot be removed individually.
     pub async fn unsubscribe_from_market(&self, market_id: String) -> Result<()> {
         if let Some(sender) = &self.command_sender {
             sender
@@ -688,40 +941,21 @@ impl StreamingClient {
             .map(|connected| *connected)
             .unwrap_or(false)
     }
+}
 
-    /// Create a market subscription message for a single market
-    fn create_market_subscription_message(market_id: &str, levels: usize) -> String {
-        // Use a timestamp-based ID to avoid conflicts
-        let id = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis()
-            % 10000; // Keep it small but unique
-
-        format!(
-            "{{\"op\": \"marketSubscription\", \"id\": {id}, \"marketFilter\": {{ \"marketIds\":[\"{market_id}\"]}}, \"marketDataFilter\": {{ \"fields\": [\"EX_BEST_OFFERS\"], \"ladderLevels\": {levels}}}}}\r\n"
-        )
+fn set_market_scope(market_scope: &SharedMarketScope, markets: Option<&HashSet<String>>) {
+    if let Ok(mut scope) = market_scope.write() {
+        *scope = markets.cloned();
     }
+}
 
-    /// Create a market subscription message for multiple markets
-    fn create_batch_market_subscription_message(market_ids: &[String], levels: usize) -> String {
-        // Use a timestamp-based ID to avoid conflicts
-        let id = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis()
-            % 10000; // Keep it small but unique
-
-        let market_ids_json = market_ids
-            .iter()
-            .map(|id| format!("\"{id}\""))
-            .collect::<Vec<_>>()
-            .join(",");
-
-        format!(
-            "{{\"op\": \"marketSubscription\", \"id\": {id}, \"marketFilter\": {{ \"marketIds\":[{market_ids_json}]}}, \"marketDataFilter\": {{ \"fields\": [\"EX_BEST_OFFERS\", \"EX_MARKET_DEF\"], \"ladderLevels\": {levels}}}}}\r\n"
-        )
-    }
+async fn set_connection_state(
+    connection_manager: &ConnectionManager,
+    event_sender: &broadcast::Sender<StreamEvent>,
+    state: ConnectionState,
+) {
+    connection_manager.set_state(state.clone()).await;
+    let _ = event_sender.send(StreamEvent::ConnectionStateChange(state));
 }
 
 impl Drop for StreamingClient {
@@ -746,6 +980,9 @@ mod tests {
                 password: "test_pass".to_string(),
                 api_key: "test_api_key".to_string(),
                 pem_path: "/tmp/test.pem".to_string(),
+                stream_url: None,
+                jurisdiction: Default::default(),
+                endpoints: None,
             },
         }
     }
@@ -754,7 +991,7 @@ mod tests {
     fn test_new_streaming_client() {
         let client = StreamingClient::new("test_api_key".to_string());
         assert_eq!(client.api_key, "test_api_key");
-        assert!(client.session_token.is_none());
+        assert!(client.session.get().is_none());
         assert!(client.streaming_task.is_none());
         assert!(client.command_sender.is_none());
         assert!(!client.is_connected());
@@ -767,7 +1004,7 @@ mod tests {
             "test_token".to_string(),
         );
         assert_eq!(client.api_key, "test_api_key");
-        assert_eq!(client.session_token, Some("test_token".to_string()));
+        assert_eq!(client.session.get(), Some("test_token".to_string()));
         assert!(!client.is_connected());
     }
 
@@ -776,17 +1013,17 @@ mod tests {
         let config = create_test_config();
         let client = StreamingClient::from_config(config);
         assert_eq!(client.api_key, "test_api_key");
-        assert!(client.session_token.is_none());
+        assert!(client.session.get().is_none());
         assert!(!client.is_connected());
     }
 
     #[test]
     fn test_set_session_token() {
         let mut client = StreamingClient::new("test_api_key".to_string());
-        assert!(client.session_token.is_none());
+        assert!(client.session.get().is_none());
 
         client.set_session_token("new_token".to_string());
-        assert_eq!(client.session_token, Some("new_token".to_string()));
+        assert_eq!(client.session.get(), Some("new_token".to_string()));
     }
 
     #[test]
@@ -847,6 +1084,19 @@ mod tests {
             .contains("Streaming client not started"));
     }
 
+    #[tokio::test]
+    async fn test_subscribe_with_filter_without_start() {
+        let client = StreamingClient::new("test_api_key".to_string());
+        let filter = MarketFilter {
+            event_type_ids: Some(vec!["7".to_string()]),
+            ..Default::default()
+        };
+        let result = client
+            .subscribe_with_filter(filter, MarketDataFilter::best_offers(3))
+            .await;
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_stop_without_start() {
         let mut client = StreamingClient::new("test_api_key".to_string());
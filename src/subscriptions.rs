@@ -0,0 +1,333 @@
+use crate::dto::streaming::{MarketDataFilter, MarketFilter, OrderFilter, SubscriptionOptions};
+use crate::streamer::StreamClock;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+/// Market ID that never exists, used to subscribe to "no markets"
+pub const NO_MARKETS_PLACEHOLDER_ID: &str = "1.0";
+
+/// Shared handle to the subscriptions of one stream, replayed on every reconnection
+pub type SharedSubscriptions = Arc<RwLock<SubscriptionRegistry>>;
+
+/// Everything needed to restore the market and order subscriptions on a new connection
+#[derive(Debug, Clone)]
+pub struct SubscriptionRegistry {
+    /// Markets subscribed by ID
+    pub markets: HashSet<String>,
+    /// Data filter for every market in `markets`; a subscription carries only one
+    pub market_data_filter: Option<MarketDataFilter>,
+    /// A streaming market filter subscription; replaces `markets` while set
+    pub market_filter: Option<(MarketFilter, MarketDataFilter)>,
+    pub market_options: SubscriptionOptions,
+    pub orders: Option<OrderSubscription>,
+    pub order_options: SubscriptionOptions,
+}
+
+/// An order subscription; `filter: None` subscribes with an empty filter (`{}`)
+#[derive(Debug, Clone, Default)]
+pub struct OrderSubscription {
+    pub filter: Option<OrderFilter>,
+}
+
+impl Default for SubscriptionRegistry {
+    fn default() -> Self {
+        Self {
+            markets: HashSet::new(),
+            market_data_filter: None,
+            market_filter: None,
+            market_options: SubscriptionOptions::default(),
+            orders: None,
+            order_options: SubscriptionOptions::new(None, Some(5000)),
+        }
+    }
+}
+
+impl SubscriptionRegistry {
+    /// The current market subscription, or `None` if nothing is subscribed
+    pub fn market_subscription_message(&self, clock: &StreamClock) -> Option<String> {
+        if let Some((market_filter, data_filter)) = &self.market_filter {
+            return Some(market_filter_subscription_message(
+                market_filter,
+                data_filter,
+                &self.market_options,
+                clock,
+            ));
+        }
+
+        if self.markets.is_empty() {
+            return None;
+        }
+        let data_filter = self.market_data_filter.as_ref()?;
+        Some(market_set_subscription_message(
+            &self.markets,
+            data_filter,
+            &self.market_options,
+            clock,
+        ))
+    }
+
+    /// The current order subscription, or `None` if orders are not subscribed
+    pub fn order_subscription_message(&self, clock: &StreamClock) -> Option<String> {
+        let orders = self.orders.as_ref()?;
+        Some(order_subscription_message(
+            orders.filter.as_ref(),
+            &self.order_options,
+            clock,
+        ))
+    }
+
+    /// Messages restoring every subscription with its original parameters, resuming from the clocks
+    pub fn resubscription_messages(
+        &self,
+        market_clock: &StreamClock,
+        order_clock: &StreamClock,
+    ) -> Vec<String> {
+        self.market_subscription_message(market_clock)
+            .into_iter()
+            .chain(self.order_subscription_message(order_clock))
+            .collect()
+    }
+}
+
+/// Create a subscription for exactly `markets`.
+///
+/// Betfair has no unsubscribe and treats an empty market filter as "every market",
+/// so an empty set subscribes to a market ID that can never exist instead.
+pub fn market_set_subscription_message(
+    markets: &HashSet<String>,
+    data_filter: &MarketDataFilter,
+    options: &SubscriptionOptions,
+    clock: &StreamClock,
+) -> String {
+    let mut market_ids: Vec<String> = markets.iter().cloned().collect();
+    if market_ids.is_empty() {
+        market_ids.push(NO_MARKETS_PLACEHOLDER_ID.to_string());
+    }
+    market_ids.sort();
+
+    market_ids_subscription_message(&market_ids, data_filter, options, clock)
+}
+
+/// Create a market subscription message for multiple markets, resuming from `clock` if set
+pub fn market_ids_subscription_message(
+    market_ids: &[String],
+    data_filter: &MarketDataFilter,
+    options: &SubscriptionOptions,
+    clock: &StreamClock,
+) -> String {
+    market_filter_subscription_message(
+        &MarketFilter::with_market_ids(market_ids.to_vec()),
+        data_filter,
+        options,
+        clock,
+    )
+}
+
+/// Create a market subscription message for a streaming market filter
+pub fn market_filter_subscription_message(
+    market_filter: &MarketFilter,
+    data_filter: &MarketDataFilter,
+    options: &SubscriptionOptions,
+    clock: &StreamClock,
+) -> String {
+    // Use a timestamp-based ID to avoid conflicts
+    let id = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        % 10000; // Keep it small but unique
+
+    format!(
+        "{{\"op\": \"marketSubscription\", \"id\": {id}, \"marketFilter\": {market_filter_json}, \"marketDataFilter\": {data_filter_json}, \"segmentationEnabled\": true{option_fields}{clock_fields}}}\r\n",
+        market_filter_json = serde_json::to_string(market_filter).unwrap_or_else(|_| "{}".to_string()),
+        data_filter_json = serde_json::to_string(data_filter).unwrap_or_else(|_| "{}".to_string()),
+        option_fields = options.subscription_fields(),
+        clock_fields = clock.subscription_fields()
+    )
+}
+
+/// Create an order subscription message, resuming from `clock` if set
+pub fn order_subscription_message(
+    filter: Option<&OrderFilter>,
+    options: &SubscriptionOptions,
+    clock: &StreamClock,
+) -> String {
+    let filter_json = filter
+        .and_then(|f| serde_json::to_string(f).ok())
+        .unwrap_or_else(|| "{}".to_string());
+    let option_fields = options.subscription_fields();
+    let clock_fields = clock.subscription_fields();
+    format!(
+        "{{\"op\":\"orderSubscription\",\"orderFilter\":{filter_json},\"segmentationEnabled\":true{option_fields}{clock_fields}}}\r\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn parse(message: &str) -> Value {
+        serde_json::from_str(message.trim()).unwrap()
+    }
+
+    #[test]
+    fn test_filtered_market_subscription_message() {
+        let rest_filter = crate::dto::market::MarketFilter {
+            event_type_ids: Some(vec!["7".to_string()]),
+            market_countries: Some(vec!["GB".to_string(), "IE".to_string()]),
+            market_type_codes: Some(vec!["WIN".to_string()]),
+            ..Default::default()
+        };
+        let msg = market_filter_subscription_message(
+            &MarketFilter::from(rest_filter),
+            &MarketDataFilter::best_offers(3),
+            &SubscriptionOptions::new(Some(250), None),
+            &StreamClock::default(),
+        );
+
+        let parsed = parse(&msg);
+        assert_eq!(parsed["op"], "marketSubscription");
+        assert_eq!(parsed["marketFilter"]["eventTypeIds"][0], "7");
+        assert_eq!(parsed["marketFilter"]["countryCodes"][1], "IE");
+        assert_eq!(parsed["marketFilter"]["marketTypes"][0], "WIN");
+        assert!(parsed["marketFilter"].get("marketIds").is_none());
+        assert_eq!(parsed["marketDataFilter"]["ladderLevels"], 3);
+        assert_eq!(parsed["conflateMs"], 250);
+        assert!(parsed.get("heartbeatMs").is_none());
+    }
+
+    #[test]
+    fn test_batch_market_subscription_message() {
+        let msg = market_ids_subscription_message(
+            &["1.1".to_string(), "1.2".to_string()],
+            &MarketDataFilter::best_offers(5),
+            &SubscriptionOptions::default(),
+            &StreamClock::default(),
+        );
+
+        let parsed = parse(&msg);
+        assert_eq!(parsed["marketFilter"]["marketIds"][1], "1.2");
+        assert_eq!(parsed["marketDataFilter"]["fields"][1], "EX_MARKET_DEF");
+        assert_eq!(parsed["segmentationEnabled"], true);
+    }
+
+    #[test]
+    fn test_market_set_subscription_message() {
+        let markets = HashSet::from(["1.2".to_string(), "1.1".to_string()]);
+        let clock = StreamClock {
+            initial_clk: Some("I".to_string()),
+            clk: Some("C".to_string()),
+        };
+
+        let msg = market_set_subscription_message(
+            &markets,
+            &MarketDataFilter::best_offers(3),
+            &SubscriptionOptions::default(),
+            &clock,
+        );
+        let parsed = parse(&msg);
+        assert_eq!(
+            parsed["marketFilter"]["marketIds"],
+            serde_json::json!(["1.1", "1.2"])
+        );
+        assert_eq!(parsed["clk"], "C");
+
+        // An empty filter would mean "all markets", so the empty set uses a placeholder
+        let msg = market_set_subscription_message(
+            &HashSet::new(),
+            &MarketDataFilter::best_offers(3),
+            &SubscriptionOptions::default(),
+            &StreamClock::default(),
+        );
+        let parsed = parse(&msg);
+        assert_eq!(
+            parsed["marketFilter"]["marketIds"],
+            serde_json::json!([NO_MARKETS_PLACEHOLDER_ID])
+        );
+        assert!(parsed.get("clk").is_none());
+    }
+
+    #[test]
+    fn test_order_subscription_message_options() {
+        let mut registry = SubscriptionRegistry {
+            orders: Some(OrderSubscription::default()),
+            ..Default::default()
+        };
+        let msg = registry
+            .order_subscription_message(&StreamClock::default())
+            .unwrap();
+        let parsed = parse(&msg);
+        assert_eq!(parsed["orderFilter"], serde_json::json!({}));
+        assert_eq!(parsed["heartbeatMs"], 5000);
+
+        registry.order_options = SubscriptionOptions::new(Some(1000), Some(2000));
+        let msg = registry
+            .order_subscription_message(&StreamClock::default())
+            .unwrap();
+        let parsed = parse(&msg);
+        assert_eq!(parsed["conflateMs"], 1000);
+        assert_eq!(parsed["heartbeatMs"], 2000);
+        assert_eq!(parsed["segmentationEnabled"], true);
+    }
+
+    #[test]
+    fn test_resubscription_replays_parameters_and_clocks() {
+        let mut registry = SubscriptionRegistry::default();
+        assert!(registry
+            .resubscription_messages(&StreamClock::default(), &StreamClock::default())
+            .is_empty());
+
+        registry.markets.insert("1.1".to_string());
+        registry.market_data_filter = Some(MarketDataFilter::best_offers(3));
+        registry.market_options = SubscriptionOptions::new(Some(100), None);
+        registry.orders = Some(OrderSubscription {
+            filter: Some(OrderFilter {
+                include_overall_position: Some(false),
+                customer_strategy_refs: Some(vec!["alpha".to_string()]),
+                partition_matched_by_strategy_ref: Some(true),
+            }),
+        });
+
+        let market_clock = StreamClock {
+            initial_clk: Some("MI".to_string()),
+            clk: Some("MC".to_string()),
+        };
+        let order_clock = StreamClock {
+            initial_clk: Some("OI".to_string()),
+            clk: Some("OC".to_string()),
+        };
+        let messages = registry.resubscription_messages(&market_clock, &order_clock);
+        assert_eq!(messages.len(), 2);
+
+        let market = parse(&messages[0]);
+        assert_eq!(market["marketFilter"]["marketIds"][0], "1.1");
+        assert_eq!(market["marketDataFilter"]["ladderLevels"], 3);
+        assert_eq!(market["conflateMs"], 100);
+        assert_eq!(market["clk"], "MC");
+
+        let orders = parse(&messages[1]);
+        assert_eq!(orders["op"], "orderSubscription");
+        assert_eq!(orders["orderFilter"]["customerStrategyRefs"][0], "alpha");
+        assert_eq!(orders["orderFilter"]["partitionMatchedByStrategyRef"], true);
+        assert_eq!(orders["orderFilter"]["includeOverallPosition"], false);
+        assert_eq!(orders["initialClk"], "OI");
+        assert_eq!(orders["clk"], "OC");
+
+        // A filter subscription takes precedence over market IDs
+        registry.market_filter = Some((
+            MarketFilter {
+                event_type_ids: Some(vec!["7".to_string()]),
+                ..Default::default()
+            },
+            MarketDataFilter::best_offers(1),
+        ));
+        let market = parse(
+            &registry
+                .market_subscription_message(&StreamClock::default())
+                .unwrap(),
+        );
+        assert_eq!(market["marketFilter"]["eventTypeIds"][0], "7");
+        assert!(market["marketFilter"].get("marketIds").is_none());
+    }
+}
@@ -1,21 +1,28 @@
+use crate::account::AccountStatementPager;
 use crate::api_client::RestClient;
 use crate::config::Config;
-use crate::dto::rpc::{InteractiveLoginResponse, LoginResponse};
+use crate::dto::rpc::{InteractiveLoginResponse, LoginResponse, SessionResponse};
 use crate::dto::*;
+use crate::heartbeat::{HeartbeatHandle, HeartbeatStatus};
+use crate::market_cache::MarketCache;
 use crate::orderbook::Orderbook;
-use crate::streaming_client::StreamingClient;
+use crate::session::KeepAliveHandle;
+use crate::streaming_client::{StreamEvent, StreamingClient};
 use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 
 /// Type alias for the shared orderbook state
-pub type SharedOrderbooks = Arc<RwLock<HashMap<String, HashMap<String, Orderbook>>>>;
+pub type SharedOrderbooks = Arc<RwLock<HashMap<String, HashMap<RunnerKey, Orderbook>>>>;
 
 /// Unified client combining REST API and streaming capabilities
 pub struct BetfairClient {
     api_client: RestClient,
     streaming_client: Option<StreamingClient>,
+    keep_alive: Option<KeepAliveHandle>,
+    heartbeat: Option<HeartbeatHandle>,
     config: Config,
 }
 
@@ -26,6 +33,8 @@ impl BetfairClient {
         Self {
             api_client,
             streaming_client: None,
+            keep_alive: None,
+            heartbeat: None,
             config,
         }
     }
@@ -36,12 +45,8 @@ impl BetfairClient {
         let response = self.api_client.login().await?;
 
         // If login successful and we want streaming, initialize streaming client
-        if response.login_status == "SUCCESS" {
-            let streaming = StreamingClient::with_session_token(
-                self.config.betfair.api_key.clone(),
-                response.session_token.clone(),
-            );
-            self.streaming_client = Some(streaming);
+        if response.login_status == "SUCCESS" && self.streaming_client.is_none() {
+            self.streaming_client = Some(self.new_streaming_client());
         }
 
         Ok(response)
@@ -59,13 +64,9 @@ impl BetfairClient {
             .login_interactive(username, password)
             .await?;
 
-        // Extract session token and check if login was successful
-        if let Some(session_token) = self.api_client.get_session_token() {
-            let streaming = StreamingClient::with_session_token(
-                self.config.betfair.api_key.clone(),
-                session_token,
-            );
-            self.streaming_client = Some(streaming);
+        // Check if login was successful
+        if self.api_client.get_session_token().is_some() && self.streaming_client.is_none() {
+            self.streaming_client = Some(self.new_streaming_client());
         }
 
         Ok(response)
@@ -78,20 +79,99 @@ impl BetfairClient {
 
     /// Set session token (useful for restoring sessions)
     pub fn set_session_token(&mut self, token: String) {
-        self.api_client.set_session_token(token.clone());
+        // The streaming client shares the session, so it sees the new token too
+        self.api_client.set_session_token(token);
 
-        // Update streaming client if it exists
-        if let Some(streaming) = &mut self.streaming_client {
-            streaming.set_session_token(token.clone());
-        } else {
-            // Create streaming client with the token
-            self.streaming_client = Some(StreamingClient::with_session_token(
-                self.config.betfair.api_key.clone(),
-                token,
-            ));
+        if self.streaming_client.is_none() {
+            self.streaming_client = Some(self.new_streaming_client());
+        }
+    }
+
+    /// The streaming client follows the REST session, including automatic re-logins
+    fn new_streaming_client(&self) -> StreamingClient {
+        let mut streaming = StreamingClient::new(self.config.betfair.api_key.clone());
+        streaming.attach_session(self.api_client.session());
+        streaming.use_config_endpoint(&self.config);
+        streaming
+    }
+
+    /// Extend the session so it does not expire through inactivity
+    pub async fn keep_alive(&self) -> Result<SessionResponse> {
+        self.api_client.keep_alive().await
+    }
+
+    /// Stop the keep-alive and heartbeat tasks and log out, invalidating the session token
+    pub async fn logout(&mut self) -> Result<SessionResponse> {
+        self.stop_keep_alive();
+        if let Err(e) = self.stop_heartbeat().await {
+            tracing::warn!("Failed to disarm the heartbeat before logging out: {e}");
+        }
+        self.api_client.logout().await
+    }
+
+    /// Keep the session alive from a background task, logging in again if it expires anyway.
+    ///
+    /// Replaces any keep-alive task started before.
+    pub fn start_keep_alive(&mut self, interval: Duration) {
+        self.keep_alive = Some(self.api_client.spawn_keep_alive(interval));
+    }
+
+    /// Stop the background keep-alive task, if running
+    pub fn stop_keep_alive(&mut self) {
+        if let Some(keep_alive) = self.keep_alive.take() {
+            keep_alive.stop();
+        }
+    }
+
+    /// Whether a background keep-alive task is running
+    pub fn is_keep_alive_running(&self) -> bool {
+        self.keep_alive
+            .as_ref()
+            .is_some_and(|keep_alive| keep_alive.is_running())
+    }
+
+    /// Arm or refresh the dead man's switch cancelling unmatched bets; a zero timeout disarms it
+    pub async fn heartbeat(&self, preferred_timeout: Duration) -> Result<HeartbeatReport> {
+        self.api_client.heartbeat(preferred_timeout).await
+    }
+
+    /// Keep the dead man's switch armed from a background task.
+    ///
+    /// If this process dies or loses its connection, Betfair cancels the unmatched bets once
+    /// the timeout passes. Replaces any heartbeat task started before; the receiver sees
+    /// every heartbeat, including failed ones.
+    pub fn start_heartbeat(
+        &mut self,
+        preferred_timeout: Duration,
+    ) -> watch::Receiver<HeartbeatStatus> {
+        let heartbeat = self.api_client.spawn_heartbeat(preferred_timeout);
+        let reports = heartbeat.subscribe();
+        self.heartbeat = Some(heartbeat);
+        reports
+    }
+
+    /// Stop the background heartbeats and disarm the switch, keeping unmatched bets open
+    pub async fn stop_heartbeat(&mut self) -> Result<Option<HeartbeatReport>> {
+        match self.heartbeat.take() {
+            Some(heartbeat) => heartbeat.stop().await.map(Some),
+            None => Ok(None),
         }
     }
 
+    /// The report of the last background heartbeat
+    pub fn latest_heartbeat(&self) -> Option<HeartbeatReport> {
+        self.heartbeat
+            .as_ref()
+            .and_then(|heartbeat| heartbeat.latest_report())
+    }
+
+    /// Whether a background heartbeat task is running
+    pub fn is_heartbeat_running(&self) -> bool {
+        self.heartbeat
+            .as_ref()
+            .is_some_and(|heartbeat| heartbeat.is_running())
+    }
+
     // ========== REST API Methods (delegated to RestClient) ==========
 
     /// List sports (event types)
@@ -112,6 +192,36 @@ impl BetfairClient {
         self.api_client.list_events(filter).await
     }
 
+    /// List countries
+    pub async fn list_countries(
+        &self,
+        filter: Option<MarketFilter>,
+    ) -> Result<Vec<CountryCodeResult>> {
+        self.api_client.list_countries(filter).await
+    }
+
+    /// List venues
+    pub async fn list_venues(&self, filter: Option<MarketFilter>) -> Result<Vec<VenueResult>> {
+        self.api_client.list_venues(filter).await
+    }
+
+    /// List market types
+    pub async fn list_market_types(
+        &self,
+        filter: Option<MarketFilter>,
+    ) -> Result<Vec<MarketTypeResult>> {
+        self.api_client.list_market_types(filter).await
+    }
+
+    /// List time ranges
+    pub async fn list_time_ranges(
+        &self,
+        filter: Option<MarketFilter>,
+        granularity: TimeGranularity,
+    ) -> Result<Vec<TimeRangeResult>> {
+        self.api_client.list_time_ranges(filter, granularity).await
+    }
+
     /// List market catalogue
     pub async fn list_market_catalogue(
         &self,
@@ -151,6 +261,22 @@ impl BetfairClient {
         self.api_client.cancel_orders(request).await
     }
 
+    /// Replace orders with new prices
+    pub async fn replace_orders(
+        &self,
+        request: ReplaceOrdersRequest,
+    ) -> Result<ReplaceOrdersResponse> {
+        self.api_client.replace_orders(request).await
+    }
+
+    /// Update the persistence type of orders
+    pub async fn update_orders(
+        &self,
+        request: UpdateOrdersRequest,
+    ) -> Result<UpdateOrdersResponse> {
+        self.api_client.update_orders(request).await
+    }
+
     /// List current orders
     pub async fn list_current_orders(
         &self,
@@ -167,6 +293,14 @@ impl BetfairClient {
         self.api_client.list_cleared_orders(request).await
     }
 
+    /// List profit and loss per runner, as calculated by the exchange
+    pub async fn list_market_profit_and_loss(
+        &self,
+        request: ListMarketProfitAndLossRequest,
+    ) -> Result<Vec<MarketProfitAndLoss>> {
+        self.api_client.list_market_profit_and_loss(request).await
+    }
+
     /// Get account funds
     pub async fn get_account_funds(
         &self,
@@ -175,6 +309,22 @@ impl BetfairClient {
         self.api_client.get_account_funds(request).await
     }
 
+    /// Get one page of the account statement
+    pub async fn get_account_statement(
+        &self,
+        request: GetAccountStatementRequest,
+    ) -> Result<AccountStatementReport> {
+        self.api_client.get_account_statement(request).await
+    }
+
+    /// Page through the whole account statement
+    pub fn account_statement(
+        &self,
+        request: GetAccountStatementRequest,
+    ) -> AccountStatementPager<'_> {
+        self.api_client.account_statement(request)
+    }
+
     /// Get account details
     pub async fn get_account_details(&self) -> Result<GetAccountDetailsResponse> {
         self.api_client.get_account_details().await
@@ -217,6 +367,36 @@ impl BetfairClient {
         streaming.subscribe_to_markets(market_ids, levels).await
     }
 
+    /// Subscribe to all markets matching a streaming market filter
+    pub async fn subscribe_with_filter(
+        &self,
+        market_filter: impl Into<StreamingMarketFilter>,
+        data_filter: MarketDataFilter,
+    ) -> Result<()> {
+        let streaming = self.streaming_client.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("Streaming client not initialized. Call login() first.")
+        })?;
+
+        streaming
+            .subscribe_with_filter(market_filter, data_filter)
+            .await
+    }
+
+    /// Subscribe to multiple markets with a custom market data field selection
+    pub async fn subscribe_to_markets_with_data_filter(
+        &self,
+        market_ids: Vec<String>,
+        data_filter: MarketDataFilter,
+    ) -> Result<()> {
+        let streaming = self.streaming_client.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("Streaming client not initialized. Call login() first.")
+        })?;
+
+        streaming
+            .subscribe_to_markets_with_data_filter(market_ids, data_filter)
+            .await
+    }
+
     /// Unsubscribe from a market
     pub async fn unsubscribe_from_market(&self, market_id: String) -> Result<()> {
         let streaming = self.streaming_client.as_ref().ok_or_else(|| {
@@ -231,6 +411,13 @@ impl BetfairClient {
         self.streaming_client.as_ref().map(|s| s.get_orderbooks())
     }
 
+    /// Get a consistent snapshot of a streamed market's definition, runners and books
+    pub fn get_market_snapshot(&self, market_id: &str) -> Option<MarketCache> {
+        self.streaming_client
+            .as_ref()
+            .and_then(|s| s.get_market_snapshot(market_id))
+    }
+
     /// Get last update time for a market
     pub fn get_market_last_update_time(&self, market_id: &str) -> Option<Instant> {
         self.streaming_client
@@ -257,7 +444,7 @@ impl BetfairClient {
     /// Set a custom orderbook callback that will be called immediately when new data arrives
     pub fn set_orderbook_callback<F>(&mut self, callback: F) -> Result<()>
     where
-        F: Fn(String, HashMap<String, Orderbook>, Option<crate::dto::MarketDefinition>)
+        F: Fn(String, HashMap<RunnerKey, Orderbook>, Option<crate::dto::MarketDefinition>)
             + Send
             + Sync
             + 'static,
@@ -270,6 +457,35 @@ impl BetfairClient {
         Ok(())
     }
 
+    /// Receive typed stream events (market, order, status, heartbeat and connection changes)
+    pub fn events(&self) -> Result<tokio::sync::broadcast::Receiver<StreamEvent>> {
+        let streaming = self.streaming_client.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("Streaming client not initialized. Call login() first.")
+        })?;
+
+        Ok(streaming.events())
+    }
+
+    /// Set conflation/heartbeat options for subsequent market subscriptions
+    pub fn set_market_subscription_options(&self, options: SubscriptionOptions) -> Result<()> {
+        let streaming = self.streaming_client.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("Streaming client not initialized. Call login() first.")
+        })?;
+
+        streaming.set_market_subscription_options(options);
+        Ok(())
+    }
+
+    /// Set conflation/heartbeat options for subsequent order subscriptions
+    pub fn set_order_subscription_options(&self, options: SubscriptionOptions) -> Result<()> {
+        let streaming = self.streaming_client.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("Streaming client not initialized. Call login() first.")
+        })?;
+
+        streaming.set_order_subscription_options(options);
+        Ok(())
+    }
+
     // ========== Convenience Methods ==========
 
     /// Place an order and subscribe to updates for the market
@@ -311,6 +527,9 @@ mod tests {
                 password: "test_pass".to_string(),
                 api_key: "test_api_key".to_string(),
                 pem_path: "/tmp/test.pem".to_string(),
+                stream_url: None,
+                jurisdiction: Default::default(),
+                endpoints: None,
             },
         }
     }
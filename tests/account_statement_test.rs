@@ -0,0 +1,88 @@
+use betfair_rs::dto::account::{AccountStatementReport, ItemClass};
+use rust_decimal::Decimal;
+
+#[test]
+fn test_deserialize_account_statement_report() {
+    let json = r#"{
+    "accountStatement": [
+        {
+            "refId": "404254051122",
+            "itemDate": "2025-10-05T14:41:18.000Z",
+            "amount": 17.1,
+            "balance": 1119.1,
+            "itemClass": "UNKNOWN",
+            "itemClassData": {
+                "unknownStatementItem": "{\"avgPrice\":9.5,\"betSize\":2.0,\"betType\":\"B\",\"betCategoryType\":\"E\",\"commissionRate\":null,\"eventId\":248324306,\"eventTypeId\":7,\"fullMarketName\":\"Ascot 14:40 - 1m Hcap\",\"grossBetAmount\":0.0,\"marketName\":\"1m Hcap\",\"marketType\":\"O\",\"placedDate\":\"2025-10-05T01:02:11.000Z\",\"selectionId\":56343,\"selectionName\":\"Quick Step\",\"startDate\":\"2025-10-05T13:40:00.000Z\",\"transactionType\":\"ACCOUNT_CREDIT\",\"transactionId\":0,\"winLose\":\"RESULT_WON\"}"
+            },
+            "legacyData": {
+                "avgPrice": 9.5,
+                "betSize": 2.0,
+                "betType": "B",
+                "betCategoryType": "E",
+                "eventId": 248324306,
+                "eventTypeId": 7,
+                "fullMarketName": "Ascot 14:40 - 1m Hcap",
+                "grossBetAmount": 0.0,
+                "marketName": "1m Hcap",
+                "marketType": "O",
+                "placedDate": "2025-10-05T01:02:11.000Z",
+                "selectionId": 56343,
+                "selectionName": "Quick Step",
+                "startDate": "2025-10-05T13:40:00.000Z",
+                "transactionType": "ACCOUNT_CREDIT",
+                "transactionId": 0,
+                "winLose": "RESULT_WON"
+            }
+        },
+        {
+            "refId": "9876543",
+            "itemDate": "2025-10-04T09:00:00.000Z",
+            "amount": -50.0,
+            "balance": 1102.0,
+            "itemClass": "UNKNOWN",
+            "itemClassData": {},
+            "legacyData": {
+                "transactionType": "ACCOUNT_DEBIT",
+                "winLose": "RESULT_NOT_APPLICABLE"
+            }
+        }
+    ],
+    "moreAvailable": true
+}"#;
+
+    let result: Result<AccountStatementReport, _> = serde_json::from_str(json);
+    assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+    let report = result.unwrap();
+    assert!(report.more_available);
+    assert_eq!(report.account_statement.len(), 2);
+
+    let settlement = &report.account_statement[0];
+    assert_eq!(settlement.ref_id, "404254051122");
+    assert_eq!(settlement.amount, Decimal::new(171, 1));
+    assert_eq!(settlement.balance, Decimal::new(11191, 1));
+    assert_eq!(settlement.item_class, ItemClass::Unknown);
+    assert!(settlement
+        .item_class_data
+        .contains_key("unknownStatementItem"));
+
+    let legacy = settlement.legacy_data.as_ref().unwrap();
+    assert_eq!(legacy.avg_price, Some(Decimal::new(95, 1)));
+    assert_eq!(legacy.selection_id, Some(56343));
+    assert_eq!(legacy.win_lose.as_deref(), Some("RESULT_WON"));
+    assert_eq!(legacy.commission_rate, None);
+
+    let withdrawal = &report.account_statement[1];
+    assert_eq!(withdrawal.amount, Decimal::new(-50, 0));
+    let legacy = withdrawal.legacy_data.as_ref().unwrap();
+    assert_eq!(legacy.transaction_type.as_deref(), Some("ACCOUNT_DEBIT"));
+    assert_eq!(legacy.bet_size, None);
+}
+
+#[test]
+fn test_deserialize_empty_account_statement() {
+    let report: AccountStatementReport =
+        serde_json::from_str(r#"{"accountStatement": [], "moreAvailable": false}"#).unwrap();
+    assert!(report.account_statement.is_empty());
+    assert!(!report.more_available);
+}
@@ -1,4 +1,6 @@
-use betfair_rs::config::{BetfairConfig, Config};
+use betfair_rs::config::{BetfairConfig, Config, Endpoints, Jurisdiction};
+use betfair_rs::dto::ConfigDto;
+use betfair_rs::stream_transport::StreamEndpoint;
 use std::fs;
 use std::sync::Mutex;
 use tempfile::tempdir;
@@ -108,6 +110,9 @@ fn test_betfair_config_clone() {
         password: "pass".to_string(),
         api_key: "key".to_string(),
         pem_path: "/path".to_string(),
+        stream_url: None,
+        jurisdiction: Default::default(),
+        endpoints: None,
     };
 
     let cloned = config.clone();
@@ -124,6 +129,9 @@ fn test_config_clone() {
         password: "pass".to_string(),
         api_key: "key".to_string(),
         pem_path: "/path".to_string(),
+        stream_url: None,
+        jurisdiction: Default::default(),
+        endpoints: None,
     };
 
     let config = Config {
@@ -134,3 +142,122 @@ fn test_config_clone() {
     assert_eq!(config.betfair.username, cloned.betfair.username);
     assert_eq!(config.betfair.api_key, cloned.betfair.api_key);
 }
+
+fn parse_config(extra: &str) -> Config {
+    toml::from_str(&format!(
+        r#"
+[betfair]
+username = "user"
+password = "pass"
+api_key = "key"
+pem_path = "/path"
+{extra}
+"#
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_default_endpoints_are_global() {
+    let config = parse_config("");
+    assert_eq!(config.betfair.jurisdiction, Jurisdiction::Global);
+
+    let endpoints = config.endpoints();
+    assert_eq!(
+        endpoints.cert_login_url(),
+        "https://identitysso-cert.betfair.com/api/certlogin"
+    );
+    assert_eq!(
+        endpoints.betting_url(),
+        "https://api.betfair.com/exchange/betting/json-rpc/v1"
+    );
+    assert_eq!(
+        endpoints.keep_alive_url(),
+        "https://identitysso.betfair.com/api/keepAlive"
+    );
+    assert_eq!(config.stream_endpoint().unwrap(), StreamEndpoint::betfair());
+}
+
+#[test]
+fn test_jurisdiction_endpoints() {
+    let config = parse_config(r#"jurisdiction = "it""#);
+    assert_eq!(config.betfair.jurisdiction, Jurisdiction::Italy);
+    let endpoints = config.endpoints();
+    assert_eq!(
+        endpoints.login_url(),
+        "https://identitysso.betfair.it/api/login"
+    );
+    assert_eq!(
+        endpoints.account_url(),
+        "https://api.betfair.it/exchange/account/json-rpc/v1"
+    );
+
+    let config = parse_config(r#"jurisdiction = "sweden""#);
+    let endpoints = config.endpoints();
+    assert_eq!(
+        endpoints.cert_login_url(),
+        "https://identitysso-cert.betfair.se/api/certlogin"
+    );
+    assert_eq!(
+        endpoints.betting_url(),
+        "https://api.betfair.com/exchange/betting/json-rpc/v1"
+    );
+
+    // An explicit stream_url still wins
+    let config = parse_config(
+        r#"jurisdiction = "es"
+stream_url = "tcp://127.0.0.1:9000""#,
+    );
+    assert_eq!(
+        config.stream_endpoint().unwrap(),
+        StreamEndpoint::new("127.0.0.1", 9000, false)
+    );
+    assert_eq!(config.endpoints().stream_url, "stream-api.betfair.es:443");
+}
+
+#[test]
+fn test_custom_endpoints() {
+    let config = parse_config(
+        r#"jurisdiction = "au"
+
+[betfair.endpoints]
+identity_url = "http://127.0.0.1:8080"
+cert_identity_url = "http://127.0.0.1:8080"
+exchange_url = "http://127.0.0.1:8080/exchange"
+stream_url = "tcp://127.0.0.1:9000""#,
+    );
+
+    let endpoints = config.endpoints();
+    assert_eq!(endpoints.login_url(), "http://127.0.0.1:8080/api/login");
+    assert_eq!(
+        endpoints.betting_url(),
+        "http://127.0.0.1:8080/exchange/betting/json-rpc/v1"
+    );
+    assert_eq!(
+        config.stream_endpoint().unwrap(),
+        StreamEndpoint::new("127.0.0.1", 9000, false)
+    );
+    assert_eq!(
+        Endpoints::local("http://localhost:1234/", "tcp://localhost:1235").logout_url(),
+        "http://localhost:1234/api/logout"
+    );
+}
+
+#[test]
+fn test_config_dto_carries_endpoint_profile() {
+    let dto: ConfigDto = toml::from_str(
+        r#"[betfair]
+username = "u"
+password = "p"
+api_key = "k"
+pem_path = "/cert.pem"
+jurisdiction = "it""#,
+    )
+    .unwrap();
+    assert_eq!(dto.betfair.jurisdiction, Jurisdiction::Italy);
+    assert!(dto.betfair.endpoints.is_none());
+
+    let serialized = toml::to_string(&dto).unwrap();
+    assert!(serialized.contains(r#"jurisdiction = "italy""#));
+    assert!(!serialized.contains("endpoints"));
+}
@@ -0,0 +1,66 @@
+use betfair_rs::dto::order::MarketProfitAndLoss;
+use rust_decimal::Decimal;
+
+#[test]
+fn test_deserialize_market_profit_and_loss_response() {
+    let json = r#"[
+    {
+        "marketId": "1.248324306",
+        "commissionApplied": 0.05,
+        "profitAndLosses": [
+            {
+                "selectionId": 56343,
+                "ifWin": 17.1,
+                "ifLose": -2.0
+            },
+            {
+                "selectionId": 47972,
+                "ifWin": -2.0,
+                "ifLose": 1.9
+            }
+        ]
+    },
+    {
+        "marketId": "1.248324307",
+        "profitAndLosses": [
+            {
+                "selectionId": 56343,
+                "ifWin": 4.5,
+                "ifLose": -1.0,
+                "ifPlace": 1.25
+            }
+        ]
+    }
+]"#;
+
+    let result: Result<Vec<MarketProfitAndLoss>, _> = serde_json::from_str(json);
+    assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+    let markets = result.unwrap();
+    assert_eq!(markets.len(), 2);
+
+    let win = &markets[0];
+    assert_eq!(win.market_id, "1.248324306");
+    assert_eq!(win.commission_applied, Some(Decimal::new(5, 2)));
+    assert_eq!(win.profit_and_losses.len(), 2);
+    assert_eq!(win.profit_and_losses[0].selection_id, 56343);
+    assert_eq!(win.profit_and_losses[0].if_win, Some(Decimal::new(171, 1)));
+    assert_eq!(win.profit_and_losses[0].if_lose, Some(Decimal::new(-2, 0)));
+    assert_eq!(win.profit_and_losses[0].if_place, None);
+
+    let place = &markets[1];
+    assert_eq!(place.commission_applied, None);
+    assert_eq!(
+        place.profit_and_losses[0].if_place,
+        Some(Decimal::new(125, 2))
+    );
+}
+
+#[test]
+fn test_deserialize_market_without_matched_bets() {
+    let json = r#"[{"marketId": "1.248324306"}]"#;
+
+    let markets: Vec<MarketProfitAndLoss> = serde_json::from_str(json).unwrap();
+    assert_eq!(markets[0].market_id, "1.248324306");
+    assert!(markets[0].profit_and_losses.is_empty());
+}
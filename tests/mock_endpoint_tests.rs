@@ -0,0 +1,562 @@
+use betfair_rs::config::{BetfairConfig, Config, Endpoints};
+use betfair_rs::dto::{
+    ActionPerformed, LimitOrder, ListMarketBookRequest, ListMarketCatalogueRequest, MarketFilter,
+    MarketProjection, OrderType, PersistenceType, PlaceInstruction, PlaceOrdersRequest, PriceData,
+    PriceProjectionDto, Side,
+};
+use betfair_rs::error::ApiErrorCode;
+use betfair_rs::{BetfairClient, BetfairError, RestClient, StreamingClient};
+use mockito::{Matcher, Server};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use std::time::Duration;
+
+fn mock_config(server: &Server) -> Config {
+    Config {
+        betfair: BetfairConfig {
+            username: "test_user".to_string(),
+            password: "test_pass".to_string(),
+            api_key: "test_key".to_string(),
+            pem_path: "/tmp/test.pem".to_string(),
+            stream_url: None,
+            jurisdiction: Default::default(),
+            endpoints: Some(Endpoints::local(&server.url(), "tcp://127.0.0.1:1")),
+        },
+    }
+}
+
+fn book_request(market_ids: &[&str]) -> ListMarketBookRequest {
+    ListMarketBookRequest {
+        market_ids: market_ids.iter().map(|id| id.to_string()).collect(),
+        price_projection: None,
+        order_projection: None,
+        match_projection: None,
+        include_overall_position: None,
+        partition_matched_by_strategy_ref: None,
+        customer_strategy_refs: None,
+        currency_code: None,
+        locale: None,
+        matched_since: None,
+        bet_ids: None,
+    }
+}
+
+fn place_request() -> PlaceOrdersRequest {
+    PlaceOrdersRequest {
+        market_id: "1.1".to_string(),
+        instructions: vec![PlaceInstruction {
+            order_type: OrderType::Limit,
+            selection_id: 1,
+            handicap: None,
+            side: Side::Back,
+            limit_order: Some(LimitOrder {
+                size: dec!(2),
+                price: dec!(3),
+                persistence_type: PersistenceType::Lapse,
+                time_in_force: None,
+                min_fill_size: None,
+                bet_target_type: None,
+                bet_target_size: None,
+            }),
+            limit_on_close_order: None,
+            market_on_close_order: None,
+            customer_order_ref: None,
+        }],
+        customer_ref: None,
+        market_version: None,
+        customer_strategy_ref: None,
+        async_: None,
+    }
+}
+
+fn all_offers_request(markets: usize) -> ListMarketBookRequest {
+    let market_ids: Vec<String> = (0..markets).map(|i| format!("1.{i}")).collect();
+    ListMarketBookRequest {
+        price_projection: Some(PriceProjectionDto {
+            price_data: Some(vec![PriceData::ExAllOffers]),
+            ex_best_offers_overrides: None,
+            virtualise: None,
+            rollover_stakes: None,
+        }),
+        ..book_request(&market_ids.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+}
+
+/// The `params` of a JSON-RPC request mockito received
+fn rpc_params(request: &mockito::Request) -> serde_json::Value {
+    let body: serde_json::Value = serde_json::from_slice(request.body().unwrap()).unwrap();
+    body["params"].clone()
+}
+
+fn rpc_method(method: &str) -> Matcher {
+    Matcher::PartialJson(json!({ "method": method }))
+}
+
+#[tokio::test]
+async fn test_interactive_login_and_market_book_use_configured_endpoints() {
+    let mut server = Server::new_async().await;
+    let login = server
+        .mock("POST", "/api/login")
+        .match_header("X-Application", "test_key")
+        .with_body(r#"{"token":"mock-token","product":"test_key","status":"SUCCESS","error":""}"#)
+        .create_async()
+        .await;
+    let book = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_header("X-Authentication", "mock-token")
+        .match_body(rpc_method("SportsAPING/v1.0/listMarketBook"))
+        .with_body(
+            r#"{"jsonrpc":"2.0","result":[{"marketId":"1.1","isMarketDataDelayed":false,"status":"OPEN"}],"id":1}"#,
+        )
+        .create_async()
+        .await;
+
+    let mut client = RestClient::new(mock_config(&server));
+    client
+        .login_interactive("test_user".to_string(), "test_pass".to_string())
+        .await
+        .unwrap();
+    assert_eq!(client.get_session_token().as_deref(), Some("mock-token"));
+
+    let books = client
+        .list_market_book(book_request(&["1.1"]))
+        .await
+        .unwrap();
+    assert_eq!(books[0].market_id, "1.1");
+
+    login.assert_async().await;
+    book.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_exception_is_typed_and_not_retried() {
+    let mut server = Server::new_async().await;
+    let book = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .with_status(400)
+        .with_body(
+            r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"ANGX-0001","data":{"APINGException":{"requestUUID":"prd-1","errorCode":"INVALID_APP_KEY","errorDetails":""},"exceptionname":"APINGException"}},"id":1}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut client = RestClient::new(mock_config(&server));
+    client.set_session_token("token".to_string());
+
+    let err = client
+        .list_market_book(book_request(&["1.1"]))
+        .await
+        .unwrap_err();
+    match err.downcast_ref::<BetfairError>() {
+        Some(BetfairError::Api {
+            code, request_uuid, ..
+        }) => {
+            assert_eq!(code, &ApiErrorCode::InvalidAppKey);
+            assert_eq!(request_uuid.as_deref(), Some("prd-1"));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+    book.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_expired_session_logs_in_again_and_resends_once() {
+    let mut server = Server::new_async().await;
+    let login = server
+        .mock("POST", "/api/login")
+        .with_body(r#"{"token":"old-token","status":"SUCCESS"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let rejected = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_header("X-Authentication", "old-token")
+        .with_status(400)
+        .with_body(
+            r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"ANGX-0003","data":{"APINGException":{"requestUUID":"prd-2","errorCode":"NO_SESSION","errorDetails":""},"exceptionname":"APINGException"}},"id":1}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let resent = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_header("X-Authentication", "new-token")
+        .match_body(rpc_method("SportsAPING/v1.0/listMarketBook"))
+        .with_body(
+            r#"{"jsonrpc":"2.0","result":[{"marketId":"1.1","isMarketDataDelayed":false,"status":"OPEN"}],"id":1}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut client = RestClient::new(mock_config(&server));
+    client
+        .login_interactive("test_user".to_string(), "test_pass".to_string())
+        .await
+        .unwrap();
+    login.assert_async().await;
+    login.remove_async().await;
+
+    let mut streaming = StreamingClient::new("test_key".to_string());
+    streaming.attach_session(client.session());
+
+    let relogin = server
+        .mock("POST", "/api/login")
+        .with_body(r#"{"token":"new-token","status":"SUCCESS"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let books = client
+        .list_market_book(book_request(&["1.1"]))
+        .await
+        .unwrap();
+    assert_eq!(books[0].market_id, "1.1");
+    assert_eq!(streaming.session().get().as_deref(), Some("new-token"));
+
+    rejected.assert_async().await;
+    relogin.assert_async().await;
+    resent.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_heavy_market_book_is_split_and_joined_in_order() {
+    let mut server = Server::new_async().await;
+    // 17 per market with EX_ALL_OFFERS, so 11 markets per request
+    let book = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_body(rpc_method("SportsAPING/v1.0/listMarketBook"))
+        .with_body_from_request(|request| {
+            let books: Vec<_> = rpc_params(request)["marketIds"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|market_id| json!({ "marketId": market_id, "isMarketDataDelayed": false }))
+                .collect();
+            json!({ "jsonrpc": "2.0", "result": books, "id": 1 })
+                .to_string()
+                .into_bytes()
+        })
+        .expect(3)
+        .create_async()
+        .await;
+
+    let mut client = RestClient::new(mock_config(&server));
+    client.set_session_token("token".to_string());
+
+    let request = all_offers_request(30);
+    let books = client.list_market_book(request.clone()).await.unwrap();
+    let market_ids: Vec<String> = books.into_iter().map(|book| book.market_id).collect();
+    assert_eq!(market_ids, request.market_ids);
+    book.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_failed_market_book_part_fails_the_call() {
+    let mut server = Server::new_async().await;
+    let failed_ids: Vec<String> = (11..22).map(|i| format!("1.{i}")).collect();
+    let failed = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_body(Matcher::PartialJson(json!({
+            "params": { "marketIds": failed_ids }
+        })))
+        .with_status(400)
+        .with_body(
+            r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"ANGX-0002","data":{"APINGException":{"requestUUID":"prd-3","errorCode":"INVALID_INPUT_DATA","errorDetails":""},"exceptionname":"APINGException"}},"id":1}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/betting/json-rpc/v1")
+        .with_body(r#"{"jsonrpc":"2.0","result":[],"id":1}"#)
+        .create_async()
+        .await;
+
+    let mut client = RestClient::new(mock_config(&server));
+    client.set_session_token("token".to_string());
+
+    let err = client
+        .list_market_book(all_offers_request(30))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<BetfairError>()
+            .and_then(BetfairError::api_error_code),
+        Some(&ApiErrorCode::InvalidInputData)
+    );
+    failed.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_heavy_market_catalogue_keeps_the_listing_order() {
+    let mut server = Server::new_async().await;
+    // Listed in descending order; each part is answered in reverse to check the re-sort
+    let listed: Vec<String> = (0..250).rev().map(|i| format!("1.{i}")).collect();
+    let listing = listed.clone();
+    let catalogue = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_body(rpc_method("SportsAPING/v1.0/listMarketCatalogue"))
+        .with_body_from_request(move |request| {
+            let params = rpc_params(request);
+            let market_ids: Vec<String> = match params["filter"]["marketIds"].as_array() {
+                Some(market_ids) => market_ids
+                    .iter()
+                    .rev()
+                    .map(|id| id.as_str().unwrap().to_string())
+                    .collect(),
+                None => listing.clone(),
+            };
+            let catalogues: Vec<_> = market_ids
+                .iter()
+                .map(|market_id| json!({ "marketId": market_id, "marketName": "Match Odds" }))
+                .collect();
+            json!({ "jsonrpc": "2.0", "result": catalogues, "id": 1 })
+                .to_string()
+                .into_bytes()
+        })
+        // The listing, then 200 and 50 markets with descriptions
+        .expect(3)
+        .create_async()
+        .await;
+
+    let mut client = RestClient::new(mock_config(&server));
+    client.set_session_token("token".to_string());
+
+    let catalogues = client
+        .list_market_catalogue(ListMarketCatalogueRequest {
+            filter: MarketFilter {
+                event_type_ids: Some(vec!["1".to_string()]),
+                ..Default::default()
+            },
+            market_projection: Some(vec![MarketProjection::MarketDescription]),
+            sort: None,
+            max_results: Some(1000),
+            locale: None,
+        })
+        .await
+        .unwrap();
+    let market_ids: Vec<String> = catalogues
+        .into_iter()
+        .map(|catalogue| catalogue.market_id)
+        .collect();
+    assert_eq!(market_ids, listed);
+    catalogue.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_failed_placement_is_checked_not_retried() {
+    let mut server = Server::new_async().await;
+    let place = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_body(Matcher::AllOf(vec![
+            rpc_method("SportsAPING/v1.0/placeOrders"),
+            Matcher::Regex(r#""customerRef":"[0-9a-f]{32}""#.to_string()),
+        ]))
+        .with_status(503)
+        .with_body("Service Unavailable")
+        .expect(1)
+        .create_async()
+        .await;
+    let lookup = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_body(rpc_method("SportsAPING/v1.0/listCurrentOrders"))
+        .with_body(
+            r#"{"jsonrpc":"2.0","result":{"currentOrders":[],"moreAvailable":false},"id":1}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut client = RestClient::new(mock_config(&server));
+    client.set_session_token("token".to_string());
+
+    let request = place_request();
+
+    let err = client.place_orders(request).await.unwrap_err();
+    match err.downcast_ref::<BetfairError>() {
+        Some(BetfairError::Unconfirmed {
+            source,
+            current_orders,
+            ..
+        }) => {
+            assert!(matches!(**source, BetfairError::Http { status: 503, .. }));
+            assert!(current_orders.is_empty());
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+    place.assert_async().await;
+    lookup.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_unconfirmed_placement_is_resent_with_its_refs() {
+    let mut server = Server::new_async().await;
+    let failed = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_body(rpc_method("SportsAPING/v1.0/placeOrders"))
+        .with_status(504)
+        .expect(1)
+        .create_async()
+        .await;
+    let lookup = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_body(rpc_method("SportsAPING/v1.0/listCurrentOrders"))
+        .with_body(
+            r#"{"jsonrpc":"2.0","result":{"currentOrders":[],"moreAvailable":false},"id":1}"#,
+        )
+        .create_async()
+        .await;
+
+    let mut client = RestClient::new(mock_config(&server));
+    client.set_session_token("token".to_string());
+
+    let err = client.place_orders(place_request()).await.unwrap_err();
+    let Some(BetfairError::Unconfirmed {
+        customer_ref,
+        customer_order_refs,
+        ..
+    }) = err.downcast_ref::<BetfairError>()
+    else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(customer_order_refs.len(), 1);
+    failed.assert_async().await;
+    lookup.assert_async().await;
+    failed.remove_async().await;
+
+    let resent = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .match_body(Matcher::PartialJson(json!({
+            "method": "SportsAPING/v1.0/placeOrders",
+            "params": {
+                "customerRef": customer_ref,
+                "instructions": [{ "customerOrderRef": customer_order_refs[0] }]
+            }
+        })))
+        .with_body(format!(
+            r#"{{"jsonrpc":"2.0","result":{{"status":"SUCCESS","marketId":"1.1","customerRef":"{customer_ref}","instructionReports":[]}},"id":1}}"#
+        ))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut request = place_request();
+    request.customer_ref = Some(customer_ref.clone());
+    request.instructions[0].customer_order_ref = Some(customer_order_refs[0].clone());
+    let response = client.place_orders(request).await.unwrap();
+    assert_eq!(response.customer_ref.as_ref(), Some(customer_ref));
+    resent.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_background_heartbeat_reports_and_disarms_on_stop() {
+    let mut server = Server::new_async().await;
+    let armed = server
+        .mock("POST", "/heartbeat/json-rpc/v1")
+        .match_body(Matcher::PartialJson(json!({
+            "method": "HeartbeatAPING/v1.0/heartbeat",
+            "params": { "preferredTimeoutSeconds": 30 }
+        })))
+        .with_body(
+            r#"{"jsonrpc":"2.0","result":{"actionPerformed":"ALL_BETS_CANCELLED","actualTimeoutSeconds":30},"id":1}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let disarmed = server
+        .mock("POST", "/heartbeat/json-rpc/v1")
+        .match_body(Matcher::PartialJson(json!({
+            "params": { "preferredTimeoutSeconds": 0 }
+        })))
+        .with_body(
+            r#"{"jsonrpc":"2.0","result":{"actionPerformed":"NONE","actualTimeoutSeconds":0},"id":1}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let mut client = BetfairClient::new(mock_config(&server));
+    client.set_session_token("token".to_string());
+
+    let mut reports = client.start_heartbeat(Duration::from_secs(30));
+    tokio::time::timeout(Duration::from_secs(5), reports.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(client.is_heartbeat_running());
+    let report = client.latest_heartbeat().unwrap();
+    assert_eq!(report.action_performed, ActionPerformed::AllBetsCancelled);
+    assert_eq!(report.actual_timeout_seconds, 30);
+
+    let report = client.stop_heartbeat().await.unwrap().unwrap();
+    assert_eq!(report.actual_timeout_seconds, 0);
+    assert!(!client.is_heartbeat_running());
+    assert!(client.stop_heartbeat().await.unwrap().is_none());
+
+    armed.assert_async().await;
+    disarmed.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_local_failures_are_not_retried() {
+    let mut server = Server::new_async().await;
+    let login = server
+        .mock("POST", "/cert/api/certlogin")
+        .expect(0)
+        .create_async()
+        .await;
+    let book = server
+        .mock("POST", "/betting/json-rpc/v1")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let mut config = mock_config(&server);
+    config.betfair.pem_path = "/nonexistent/client.pem".to_string();
+    let mut client = RestClient::new(config.clone());
+
+    // The first retry would wait a second
+    let started = std::time::Instant::now();
+    let err = client.login().await.unwrap_err();
+    assert!(err.to_string().contains("Failed to read PEM file"));
+
+    config.betfair.api_key = "bad\nkey".to_string();
+    let mut client = RestClient::new(config);
+    client.set_session_token("token".to_string());
+    client
+        .list_market_book(book_request(&["1.1"]))
+        .await
+        .unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    login.assert_async().await;
+    book.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_background_heartbeat_publishes_failures() {
+    let mut server = Server::new_async().await;
+    server
+        .mock("POST", "/heartbeat/json-rpc/v1")
+        .with_body(
+            r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"ANGX-0003","data":{"APINGException":{"requestUUID":"prd-4","errorCode":"NO_SESSION","errorDetails":""},"exceptionname":"APINGException"}},"id":1}"#,
+        )
+        .create_async()
+        .await;
+
+    let mut client = BetfairClient::new(mock_config(&server));
+    client.set_session_token("token".to_string());
+
+    let mut reports = client.start_heartbeat(Duration::from_secs(30));
+    tokio::time::timeout(Duration::from_secs(5), reports.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    let status = reports.borrow().clone();
+    assert_eq!(status.consecutive_failures, 1);
+    assert!(status.last_error.unwrap().contains("NO_SESSION"));
+    assert!(status.last_report.is_none());
+    assert!(client.latest_heartbeat().is_none());
+}
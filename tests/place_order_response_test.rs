@@ -1,4 +1,6 @@
+use betfair_rs::dto::common::InstructionReportErrorCode;
 use betfair_rs::dto::order::PlaceOrdersResponse;
+use betfair_rs::BetfairError;
 
 #[test]
 fn test_deserialize_place_orders_response() {
@@ -40,3 +42,45 @@ fn test_deserialize_place_orders_response() {
         }
     }
 }
+
+#[test]
+fn test_failed_place_orders_response_error() {
+    let json = r#"{
+    "status": "FAILURE",
+    "errorCode": "INSUFFICIENT_FUNDS",
+    "marketId": "1.248324306",
+    "instructionReports": [
+        {
+            "status": "FAILURE",
+            "errorCode": "INSUFFICIENT_FUNDS",
+            "instruction": {
+                "selectionId": 56343,
+                "limitOrder": {
+                    "size": 2000.0,
+                    "price": 3.5,
+                    "persistenceType": "LAPSE"
+                },
+                "orderType": "LIMIT",
+                "side": "LAY"
+            }
+        }
+    ]
+}"#;
+
+    let response: PlaceOrdersResponse = serde_json::from_str(json).unwrap();
+    match BetfairError::from_execution_report(&response) {
+        Some(BetfairError::Execution {
+            status,
+            error_code,
+            instruction_errors,
+        }) => {
+            assert_eq!(status, "FAILURE");
+            assert_eq!(error_code.as_deref(), Some("INSUFFICIENT_FUNDS"));
+            assert_eq!(
+                instruction_errors,
+                vec![InstructionReportErrorCode::InsufficientFunds]
+            );
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
@@ -0,0 +1,117 @@
+use betfair_rs::dto::common::PersistenceType;
+use betfair_rs::dto::order::{ReplaceOrdersResponse, UpdateOrdersResponse};
+use rust_decimal::Decimal;
+
+#[test]
+fn test_deserialize_replace_orders_response() {
+    let json = r#"{
+    "customerRef": "reprice_1759625902",
+    "status": "SUCCESS",
+    "marketId": "1.248324306",
+    "instructionReports": [
+        {
+            "status": "SUCCESS",
+            "cancelInstructionReport": {
+                "status": "SUCCESS",
+                "instruction": {
+                    "betId": "404254044217"
+                },
+                "sizeCancelled": 2.0,
+                "cancelledDate": "2025-10-05T01:02:11.000Z"
+            },
+            "placeInstructionReport": {
+                "status": "SUCCESS",
+                "instruction": {
+                    "selectionId": 56343,
+                    "handicap": 0.0,
+                    "limitOrder": {
+                        "size": 2.0,
+                        "price": 950.0,
+                        "persistenceType": "PERSIST"
+                    },
+                    "orderType": "LIMIT",
+                    "side": "BACK"
+                },
+                "betId": "404254051122",
+                "placedDate": "2025-10-05T01:02:11.000Z",
+                "averagePriceMatched": 0.0,
+                "sizeMatched": 0.0,
+                "orderStatus": "EXECUTABLE"
+            }
+        }
+    ]
+}"#;
+
+    let response: ReplaceOrdersResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(response.status, "SUCCESS");
+    assert_eq!(response.market_id, "1.248324306");
+
+    let reports = response.instruction_reports.unwrap();
+    let cancel = reports[0].cancel_instruction_report.as_ref().unwrap();
+    assert_eq!(cancel.instruction.bet_id, "404254044217");
+    assert_eq!(cancel.size_cancelled, Some(Decimal::new(2, 0)));
+
+    let place = reports[0].place_instruction_report.as_ref().unwrap();
+    assert_eq!(place.bet_id.as_deref(), Some("404254051122"));
+    assert_eq!(
+        place.instruction.limit_order.as_ref().unwrap().price,
+        Decimal::new(950, 0)
+    );
+}
+
+#[test]
+fn test_deserialize_replace_orders_failure() {
+    let json = r#"{
+    "status": "FAILURE",
+    "errorCode": "BET_ACTION_ERROR",
+    "marketId": "1.248324306",
+    "instructionReports": [
+        {
+            "status": "FAILURE",
+            "errorCode": "BET_TAKEN_OR_LAPSED",
+            "cancelInstructionReport": {
+                "status": "FAILURE",
+                "errorCode": "BET_TAKEN_OR_LAPSED",
+                "instruction": {
+                    "betId": "404254044217"
+                }
+            }
+        }
+    ]
+}"#;
+
+    let response: ReplaceOrdersResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(response.error_code.as_deref(), Some("BET_ACTION_ERROR"));
+    let reports = response.instruction_reports.unwrap();
+    assert_eq!(
+        reports[0].error_code.as_deref(),
+        Some("BET_TAKEN_OR_LAPSED")
+    );
+    assert!(reports[0].place_instruction_report.is_none());
+}
+
+#[test]
+fn test_deserialize_update_orders_response() {
+    let json = r#"{
+    "status": "SUCCESS",
+    "marketId": "1.248324306",
+    "instructionReports": [
+        {
+            "status": "SUCCESS",
+            "instruction": {
+                "betId": "404254044217",
+                "newPersistenceType": "PERSIST"
+            }
+        }
+    ]
+}"#;
+
+    let response: UpdateOrdersResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(response.status, "SUCCESS");
+    let reports = response.instruction_reports.unwrap();
+    assert_eq!(reports[0].instruction.bet_id, "404254044217");
+    assert!(matches!(
+        reports[0].instruction.new_persistence_type,
+        PersistenceType::Persist
+    ));
+}
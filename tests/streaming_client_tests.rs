@@ -1,7 +1,15 @@
 use betfair_rs::config::{BetfairConfig, Config};
-use betfair_rs::streaming_client::StreamingClient;
+use betfair_rs::dto::streaming::OrderFilter;
+use betfair_rs::dto::RunnerKey;
+use betfair_rs::order_cache::OrderEventKind;
+use betfair_rs::session::SessionToken;
+use betfair_rs::stream_transport::StreamTransport;
+use betfair_rs::streaming_client::{StreamEvent, StreamingClient};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream, ReadHalf, WriteHalf};
+use tokio::sync::mpsc;
 
 fn create_test_config() -> Config {
     Config {
@@ -10,6 +18,9 @@ fn create_test_config() -> Config {
             password: "test_pass".to_string(),
             api_key: "test_api_key".to_string(),
             pem_path: "test.pem".to_string(),
+            stream_url: None,
+            jurisdiction: Default::default(),
+            endpoints: None,
         },
     }
 }
@@ -165,3 +176,349 @@ fn test_streaming_client_session_token_update() {
     client.set_session_token("token3".to_string());
     assert!(!client.is_connected());
 }
+
+/// Server side of one scripted connection
+struct ScriptedConnection {
+    reader: BufReader<ReadHalf<DuplexStream>>,
+    writer: WriteHalf<DuplexStream>,
+}
+
+impl ScriptedConnection {
+    async fn read_message(&mut self) -> serde_json::Value {
+        let mut line = String::new();
+        tokio::time::timeout(Duration::from_secs(5), self.reader.read_line(&mut line))
+            .await
+            .expect("timed out waiting for client message")
+            .unwrap();
+        serde_json::from_str(line.trim()).unwrap()
+    }
+
+    async fn send(&mut self, message: &str) {
+        self.writer
+            .write_all(format!("{message}\r\n").as_bytes())
+            .await
+            .unwrap();
+    }
+}
+
+/// A transport handing each new connection's server side to the test
+fn scripted_transport() -> (StreamTransport, mpsc::UnboundedReceiver<ScriptedConnection>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let transport = StreamTransport::custom(move || {
+        let tx = tx.clone();
+        async move {
+            let (client, server) = tokio::io::duplex(64 * 1024);
+            let (reader, writer) = tokio::io::split(server);
+            tx.send(ScriptedConnection {
+                reader: BufReader::new(reader),
+                writer,
+            })
+            .map_err(|_| anyhow::anyhow!("test server gone"))?;
+            Ok(client)
+        }
+    });
+    (transport, rx)
+}
+
+#[tokio::test]
+async fn test_streaming_client_over_scripted_transport() {
+    let (transport, mut connections) = scripted_transport();
+    let mut client = StreamingClient::with_session_token("app".to_string(), "token".to_string());
+    client.set_transport(transport);
+
+    let server = tokio::spawn(async move {
+        let mut conn = connections.recv().await.unwrap();
+        let auth = conn.read_message().await;
+        assert_eq!(auth["op"], "authentication");
+        assert_eq!(auth["session"], "token");
+        conn.send(r#"{"op":"connection","connectionId":"test-1"}"#)
+            .await;
+        conn.send(r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false}"#)
+            .await;
+        conn
+    });
+
+    client.start().await.unwrap();
+    let mut conn = server.await.unwrap();
+
+    client
+        .subscribe_to_market("1.23".to_string(), 3)
+        .await
+        .unwrap();
+    let subscription = conn.read_message().await;
+    assert_eq!(subscription["op"], "marketSubscription");
+    assert_eq!(
+        subscription["marketFilter"]["marketIds"],
+        serde_json::json!(["1.23"])
+    );
+
+    conn.send(r#"{"op":"mcm","id":2,"clk":"C1","initialClk":"I1","pt":1,"ct":"SUB_IMAGE","mc":[{"id":"1.23","rc":[{"id":47972,"batb":[[0,2.5,10.0]],"batl":[[0,2.6,4.0]]}]}]}"#)
+        .await;
+
+    let orderbooks = client.get_orderbooks();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !orderbooks.read().unwrap().contains_key("1.23") {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("market image was not applied");
+
+    let books = orderbooks.read().unwrap();
+    let book = &books["1.23"][&RunnerKey::from(47972)];
+    assert_eq!(
+        book.get_best_bid().unwrap().price,
+        rust_decimal::Decimal::new(25, 1)
+    );
+    assert_eq!(client.get_market_clock().clk.as_deref(), Some("C1"));
+
+    let snapshot = client.get_market_snapshot("1.23").unwrap();
+    assert_eq!(snapshot.last_update, 1);
+    assert!(snapshot.get_runner(RunnerKey::from(47972)).is_some());
+}
+
+#[tokio::test]
+async fn test_streaming_client_start_fails_on_rejected_session() {
+    let (transport, mut connections) = scripted_transport();
+    let mut client = StreamingClient::with_session_token("app".to_string(), "expired".to_string());
+    client.set_transport(transport);
+
+    tokio::spawn(async move {
+        let mut conn = connections.recv().await.unwrap();
+        conn.read_message().await;
+        conn.send(r#"{"op":"status","id":1,"statusCode":"FAILURE","errorCode":"INVALID_SESSION_INFORMATION","connectionClosed":true}"#)
+            .await;
+        // Hold the connection open so the failure, not EOF, ends start()
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let err = client.start().await.unwrap_err();
+    assert!(err.to_string().contains("INVALID_SESSION_INFORMATION"));
+    assert!(matches!(
+        client.get_connection_state().await,
+        betfair_rs::connection_state::ConnectionState::Failed(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_reconnection_replays_subscriptions_with_clocks() {
+    let (transport, mut connections) = scripted_transport();
+    let mut client = StreamingClient::with_session_token("app".to_string(), "token".to_string());
+    client.set_transport(transport);
+
+    let server = tokio::spawn(async move {
+        let mut conn = connections.recv().await.unwrap();
+        conn.read_message().await;
+        conn.send(r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false}"#)
+            .await;
+        (conn, connections)
+    });
+
+    client.start().await.unwrap();
+    let (mut conn, mut connections) = server.await.unwrap();
+
+    client
+        .subscribe_to_markets(vec!["1.23".to_string(), "1.24".to_string()], 2)
+        .await
+        .unwrap();
+    conn.read_message().await;
+    client
+        .subscribe_to_orders(Some(OrderFilter {
+            include_overall_position: Some(false),
+            customer_strategy_refs: Some(vec!["alpha".to_string()]),
+            partition_matched_by_strategy_ref: Some(true),
+        }))
+        .await
+        .unwrap();
+    conn.read_message().await;
+
+    conn.send(
+        r#"{"op":"mcm","id":2,"clk":"MC1","initialClk":"MI1","pt":1,"ct":"SUB_IMAGE","mc":[]}"#,
+    )
+    .await;
+    conn.send(
+        r#"{"op":"ocm","id":3,"clk":"OC1","initialClk":"OI1","pt":1,"ct":"SUB_IMAGE","oc":[]}"#,
+    )
+    .await;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while client.get_order_clock().clk.is_none() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("clocks were not recorded");
+
+    // Drop the connection; the replacement must restore both subscriptions as they were
+    drop(conn);
+    let mut conn = tokio::time::timeout(Duration::from_secs(10), connections.recv())
+        .await
+        .expect("client did not reconnect")
+        .unwrap();
+    assert_eq!(conn.read_message().await["op"], "authentication");
+    conn.send(r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false}"#)
+        .await;
+
+    let markets = conn.read_message().await;
+    assert_eq!(markets["op"], "marketSubscription");
+    assert_eq!(
+        markets["marketFilter"]["marketIds"],
+        serde_json::json!(["1.23", "1.24"])
+    );
+    assert_eq!(markets["marketDataFilter"]["ladderLevels"], 2);
+    assert_eq!(markets["initialClk"], "MI1");
+    assert_eq!(markets["clk"], "MC1");
+
+    let orders = conn.read_message().await;
+    assert_eq!(orders["op"], "orderSubscription");
+    assert_eq!(
+        orders["orderFilter"],
+        serde_json::json!({
+            "includeOverallPosition": false,
+            "customerStrategyRefs": ["alpha"],
+            "partitionMatchedByStrategyRef": true
+        })
+    );
+    assert_eq!(orders["initialClk"], "OI1");
+    assert_eq!(orders["clk"], "OC1");
+
+    // Commands issued after the reconnection go to the new connection
+    client
+        .subscribe_to_market("1.25".to_string(), 2)
+        .await
+        .unwrap();
+    let markets = conn.read_message().await;
+    assert_eq!(
+        markets["marketFilter"]["marketIds"],
+        serde_json::json!(["1.23", "1.24", "1.25"])
+    );
+}
+
+#[tokio::test]
+async fn test_dropped_connection_reports_state_changes() {
+    use betfair_rs::connection_state::ConnectionState;
+
+    let (transport, mut connections) = scripted_transport();
+    let mut client = StreamingClient::with_session_token("app".to_string(), "token".to_string());
+    client.set_transport(transport);
+    let mut events = client.events();
+
+    let server = tokio::spawn(async move {
+        let mut conn = connections.recv().await.unwrap();
+        conn.read_message().await;
+        conn.send(r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false}"#)
+            .await;
+        (conn, connections)
+    });
+    client.start().await.unwrap();
+    let (conn, mut connections) = server.await.unwrap();
+    assert!(client.is_connected());
+
+    drop(conn);
+    let mut conn = tokio::time::timeout(Duration::from_secs(10), connections.recv())
+        .await
+        .expect("client did not reconnect")
+        .unwrap();
+    conn.read_message().await;
+    conn.send(r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false}"#)
+        .await;
+
+    let mut states = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while states.len() < 6 {
+            if let Ok(StreamEvent::ConnectionStateChange(state)) = events.recv().await {
+                states.push(state);
+            }
+        }
+    })
+    .await
+    .expect("missing connection state changes");
+    assert_eq!(
+        states,
+        vec![
+            ConnectionState::Connecting,
+            ConnectionState::Connected,
+            ConnectionState::Disconnected,
+            ConnectionState::Reconnecting,
+            ConnectionState::Connecting,
+            ConnectionState::Connected,
+        ]
+    );
+    assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn test_reconnects_with_refreshed_session_after_rejection() {
+    let (transport, mut connections) = scripted_transport();
+    let session = SessionToken::new("token1".to_string());
+    let mut client = StreamingClient::new("app".to_string());
+    client.attach_session(session.clone());
+    client.set_transport(transport);
+
+    let server = tokio::spawn(async move {
+        let mut conn = connections.recv().await.unwrap();
+        assert_eq!(conn.read_message().await["session"], "token1");
+        conn.send(r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false}"#)
+            .await;
+        (conn, connections)
+    });
+
+    client.start().await.unwrap();
+    let (mut conn, mut connections) = server.await.unwrap();
+
+    // The REST client logs in again, then the stream rejects the old session
+    session.set("token2".to_string());
+    conn.send(r#"{"op":"status","id":2,"statusCode":"FAILURE","errorCode":"INVALID_SESSION_INFORMATION","connectionClosed":true}"#)
+        .await;
+    drop(conn);
+
+    let mut conn = tokio::time::timeout(Duration::from_secs(10), connections.recv())
+        .await
+        .expect("client did not reconnect with the refreshed session")
+        .unwrap();
+    let auth = conn.read_message().await;
+    assert_eq!(auth["op"], "authentication");
+    assert_eq!(auth["session"], "token2");
+}
+
+#[tokio::test]
+async fn test_order_events_published_from_order_stream() {
+    let (transport, mut connections) = scripted_transport();
+    let mut client = StreamingClient::with_session_token("app".to_string(), "token".to_string());
+    client.set_transport(transport);
+
+    let server = tokio::spawn(async move {
+        let mut conn = connections.recv().await.unwrap();
+        conn.read_message().await;
+        conn.send(r#"{"op":"status","id":1,"statusCode":"SUCCESS","connectionClosed":false}"#)
+            .await;
+        conn
+    });
+
+    client.start().await.unwrap();
+    let mut conn = server.await.unwrap();
+    let mut events = client.events();
+
+    client.subscribe_to_orders(None).await.unwrap();
+    conn.read_message().await;
+    conn.send(r#"{"op":"ocm","id":2,"clk":"1","initialClk":"I","pt":1,"ct":"SUB_IMAGE","oc":[{"id":"1.23","fullImage":true,"orc":[{"id":47972,"fullImage":true,"uo":[]}]}]}"#)
+        .await;
+    conn.send(r#"{"op":"ocm","id":2,"clk":"2","pt":2,"oc":[{"id":"1.23","orc":[{"id":47972,"uo":[{"id":"bet1","p":2.0,"s":10,"side":"B","status":"EC","pt":"L","ot":"L","pd":2,"md":2,"sm":10,"sr":0,"rfo":"ref1"}]}]}]}"#)
+        .await;
+
+    let mut order_events = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while order_events.len() < 2 {
+            if let StreamEvent::OrderEvent(event) = events.recv().await.unwrap() {
+                order_events.push(event);
+            }
+        }
+    })
+    .await
+    .expect("order events were not published");
+
+    assert_eq!(order_events[0].kind, OrderEventKind::OrderAccepted);
+    assert_eq!(order_events[1].kind, OrderEventKind::FullyMatched);
+    assert_eq!(order_events[1].size, rust_decimal::Decimal::new(10, 0));
+    assert_eq!(order_events[1].customer_order_ref.as_deref(), Some("ref1"));
+    assert_eq!(order_events[1].market_id, "1.23");
+}
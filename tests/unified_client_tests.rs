@@ -14,6 +14,9 @@ fn create_test_config() -> Config {
             password: "test_pass".to_string(),
             api_key: "test_api_key".to_string(),
             pem_path: "test.pem".to_string(),
+            stream_url: None,
+            jurisdiction: Default::default(),
+            endpoints: None,
         },
     }
 }